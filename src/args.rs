@@ -0,0 +1,107 @@
+// mochou-p/text-editor/src/args.rs
+
+//! what the editor got started with
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+
+pub const USAGE: &str = "\
+usage: text-editor [options] [path[:line[:column]]]...
+
+paths:
+  files open in tabs, at the line and column if given,
+  ones that do not exist yet get created on the first save,
+  a directory becomes the root of the file browser
+
+options:
+  --readonly  open the files read-only
+  --version   print the version and exit
+  --help      print this and exit
+  --          treat the rest as paths
+
+files:
+  $XDG_CONFIG_HOME/text-editor/config  settings, a `setting = value` per line
+  $XDG_CONFIG_HOME/text-editor/keymap  key bindings, a `keys = command` per line
+  .text-editor.conf                    settings for the project, in the browsing root
+
+environment:
+  NO_COLOR   not empty, draws with reverse, bold and underline instead of colours
+  COLORTERM  truecolor or 24bit for 24-bit colours, else TERM with 256color for 256,
+             else 16, unless `color_depth` in the config says otherwise";
+
+pub enum Command {
+    Run(Args),
+    Help,
+    Version
+}
+
+#[derive(Default)]
+pub struct Args {
+    // NOTE: absolute, with a 0-based column and line if given
+    pub files:    Vec<(PathBuf, Option<(isize, isize)>)>,
+    pub dir:      Option<PathBuf>,
+    pub readonly: bool
+}
+
+/// `args` without the program name
+pub fn parse(args: impl Iterator<Item = OsString>) -> Result<Command, String> {
+    let mut parsed  = Args::default();
+    let mut options = true;
+
+    for arg in args {
+        if options && arg.to_str().is_some_and(|arg| arg.starts_with('-') && arg != "-") {
+            match arg.to_str().unwrap() {
+                "--readonly" => { parsed.readonly = true;                           },
+                "--version"  => { return Ok(Command::Version);                      },
+                "--help"     => { return Ok(Command::Help);                         },
+                "--"         => { options = false;                                  },
+                other        => { return Err(format!("unknown option {other}")); }
+            }
+
+            continue;
+        }
+
+        let (path, position) = split_position(arg);
+        let path             = std::path::absolute(&path)
+            .map_err(|error| format!("bad path {}: {error}", path.display()))?;
+
+        if path.is_dir() {
+            if parsed.dir.is_some() {
+                return Err(String::from("only one directory can be opened"));
+            }
+
+            parsed.dir = Some(path);
+        } else {
+            parsed.files.push((path, position));
+        }
+    }
+
+    Ok(Command::Run(parsed))
+}
+
+/// `file:line:column` or `file:line`, 1-based, unless there is a file by the whole name
+fn split_position(arg: OsString) -> (PathBuf, Option<(isize, isize)>) {
+    let whole = PathBuf::from(&arg);
+
+    let Some(text) = arg.to_str() else { return (whole, None); };
+
+    if whole.exists() {
+        return (whole, None);
+    }
+
+    let number = |part: &str| part.parse::<isize>().ok().filter(|n| *n > 0);
+
+    let Some((head, last)) = text.rsplit_once(':') else { return (whole, None); };
+    let Some(last)         = number(last)           else { return (whole, None); };
+
+    if let Some((path, line)) = head.rsplit_once(':') && let Some(line) = number(line) && !path.is_empty() {
+        return (PathBuf::from(path), Some((last - 1, line - 1)));
+    }
+
+    if head.is_empty() {
+        return (whole, None);
+    }
+
+    (PathBuf::from(head), Some((0, last - 1)))
+}
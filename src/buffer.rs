@@ -0,0 +1,437 @@
+// mochou-p/text-editor/src/buffer.rs
+
+//! a shallow rope: lines are kept in chunks so inserting or removing lines
+//! only shifts one chunk, and every line caches its char count plus byte
+//! offsets at most `STRIDE` chars apart so indexing into long lines stays cheap,
+//! kept up to date around each edit instead of by walking the whole line again
+
+use std::ops::RangeInclusive;
+
+
+const CHUNK:  usize = 512;
+const STRIDE: usize = 64;
+
+pub struct Line {
+    text:        String,
+    len:         isize,
+    // NOTE: (char, byte) at most `STRIDE` chars apart, after an implied (0, 0),
+    //       empty for ascii lines, where char and byte indices match anyway
+    checkpoints: Vec<(usize, usize)>
+}
+
+pub struct Buffer {
+    chunks: Vec<Vec<Line>>,
+    // NOTE: index of the first line of each chunk
+    starts: Vec<usize>,
+    count:  usize,
+    // NOTE: goes up with every insert and remove, to tell whether anything changed since
+    edits:  u64
+}
+
+impl Line {
+    fn new(text: String) -> Self {
+        let mut checkpoints = Vec::new();
+
+        let len = if text.is_ascii() {
+            text.len()
+        } else {
+            walk(&text, (0, 0), &mut checkpoints);
+            text.chars().count()
+        };
+
+        Self { text, len: len as isize, checkpoints }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn len(&self) -> isize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// byte index of the char at `x`, clamped to the line
+    pub fn index(&self, x: isize) -> usize {
+        if x <= 0 {
+            return 0;
+        }
+        if x >= self.len {
+            return self.text.len();
+        }
+        if self.len as usize == self.text.len() {
+            return x as usize;
+        }
+
+        let x          = x as usize;
+        let i        = self.checkpoints.partition_point(|(at, _)| *at <= x);
+        let (at, to) = if i == 0 { (0, 0) } else { self.checkpoints[i - 1] };
+
+        self.text[to..]
+            .char_indices()
+            .nth(x - at)
+            .map_or(self.text.len(), |(i, _)| to + i)
+    }
+
+    pub fn range(&self, start: isize, end: isize) -> &str {
+        let start = self.index(start);
+        let end   = self.index(end).max(start);
+
+        &self.text[start..end]
+    }
+
+    pub fn char_at(&self, x: isize) -> Option<char> {
+        if x < 0 || x >= self.len {
+            return None;
+        }
+
+        self.text[self.index(x)..].chars().next()
+    }
+
+    fn insert_str(&mut self, x: isize, s: &str) {
+        self.splice(x, x, s);
+    }
+
+    fn push_str(&mut self, s: &str) {
+        self.splice(self.len, self.len, s);
+    }
+
+    fn split_off(&mut self, x: isize) -> String {
+        self.splice(x, self.len, "")
+    }
+
+    fn drain(&mut self, start: isize, end: isize) {
+        self.splice(start, end, "");
+    }
+
+    /// replaces chars `start..end` with `text`, returns what was there
+    ///
+    /// only the edited stretch is walked for checkpoints, the ones after it get shifted
+    fn splice(&mut self, start: isize, end: isize, text: &str) -> String {
+        let start = start.clamp(0, self.len);
+        let end   = end.clamp(start, self.len);
+        let from  = self.index(start);
+        let to    = self.index(end);
+        let ascii = self.len as usize == self.text.len();
+
+        let removed = self.text[from..to].to_owned();
+        self.text.replace_range(from..to, text);
+
+        self.len += text.chars().count() as isize - (end - start);
+
+        if self.len as usize == self.text.len() {
+            self.checkpoints.clear();
+            return removed;
+        }
+
+        // NOTE: an ascii line had none, but they would have been every `STRIDE` chars
+        let old = if ascii {
+            (STRIDE..self.text.len() - text.len() + removed.len())
+                .step_by(STRIDE)
+                .map(|i| (i, i))
+                .collect()
+        } else {
+            std::mem::take(&mut self.checkpoints)
+        };
+
+        let (start, end) = (start as usize, end as usize);
+        let chars        = text.chars().count() as isize - (end - start) as isize;
+        let bytes        = text.len() as isize - removed.len() as isize;
+
+        let keep  = old.partition_point(|(x, _)| *x <= start);
+        let first = if keep == 0 { (0, 0) } else { old[keep - 1] };
+        let tail  = old[keep..]
+            .iter()
+            .filter(|(x, _)| *x >= end)
+            .map(|(x, byte)| ((*x as isize + chars) as usize, (*byte as isize + bytes) as usize))
+            .collect::<Vec<(usize, usize)>>();
+
+        let stop = tail.first().map_or(self.text.len(), |(_, byte)| *byte);
+
+        self.checkpoints = old[..keep].to_vec();
+        walk(&self.text[first.1..stop], first, &mut self.checkpoints);
+        self.checkpoints.extend(tail);
+
+        removed
+    }
+}
+
+/// adds a checkpoint every `STRIDE` chars of `text`, which starts at char `start.0` and byte `start.1`
+fn walk(text: &str, start: (usize, usize), checkpoints: &mut Vec<(usize, usize)>) {
+    let mut last = start.0;
+
+    for (x, (i, _)) in (start.0..).zip(text.char_indices()) {
+        if x - last >= STRIDE {
+            checkpoints.push((x, start.1 + i));
+            last = x;
+        }
+    }
+}
+
+impl Default for Buffer {
+    fn default() -> Self {
+        Self::from_lines(std::iter::empty())
+    }
+}
+
+impl Buffer {
+    /// always keeps at least one (possibly empty) line
+    pub fn from_lines(lines: impl Iterator<Item = String>) -> Self {
+        let mut chunks = Vec::new();
+        let mut chunk  = Vec::with_capacity(CHUNK);
+
+        for line in lines {
+            chunk.push(Line::new(line));
+
+            if chunk.len() == CHUNK {
+                chunks.push(std::mem::replace(&mut chunk, Vec::with_capacity(CHUNK)));
+            }
+        }
+
+        if !chunk.is_empty() || chunks.is_empty() {
+            if chunk.is_empty() {
+                chunk.push(Line::new(String::new()));
+            }
+
+            chunks.push(chunk);
+        }
+
+        let mut buffer = Self { chunks, starts: Vec::new(), count: 0, edits: 0 };
+        buffer.restart(0);
+        buffer
+    }
+
+    pub fn edits(&self) -> u64 {
+        self.edits
+    }
+
+    pub fn line_count(&self) -> isize {
+        self.count as isize
+    }
+
+    pub fn line(&self, y: isize) -> &Line {
+        let (chunk, i) = self.locate(y as usize);
+        &self.chunks[chunk][i]
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &Line> {
+        self.chunks.iter().flatten()
+    }
+
+    pub fn join(&self, separator: &str) -> String {
+        let mut string = String::new();
+
+        for (i, line) in self.lines().enumerate() {
+            if i != 0 {
+                string.push_str(separator);
+            }
+
+            string.push_str(line.as_str());
+        }
+
+        string
+    }
+
+    /// text between two positions, lines joined with `\n`
+    pub fn slice(&self, x1: isize, y1: isize, x2: isize, y2: isize) -> String {
+        if y1 == y2 {
+            return String::from(self.line(y1).range(x1, x2));
+        }
+
+        let first = self.line(y1);
+        let mut text = String::from(first.range(x1, first.len()));
+
+        for y in y1 + 1..y2 {
+            text.push('\n');
+            text.push_str(self.line(y).as_str());
+        }
+
+        text.push('\n');
+        text.push_str(self.line(y2).range(0, x2));
+
+        text
+    }
+
+    /// next `needle` starting at or after `(x, y)`, wrapping around the end
+    pub fn find(&self, needle: &str, x: isize, y: isize) -> Option<((isize, isize), (isize, isize))> {
+        if needle.is_empty() {
+            return None;
+        }
+
+        let parts = needle.split('\n').collect::<Vec<&str>>();
+        let count = self.line_count();
+
+        for n in 0..=count {
+            let line_y = (y + n) % count;
+            let line   = self.line(line_y);
+            let text   = line.as_str();
+
+            let (from, to) = match n {
+                0               => (line.index(x), text.len() + 1),
+                n if n == count => (0, line.index(x)),
+                _               => (0, text.len() + 1)
+            };
+
+            let start = if parts.len() == 1 {
+                // NOTE: from `from` on, a match there can overlap one that starts before it
+                text[from..]
+                    .find(parts[0])
+                    .map(|i| i + from)
+                    .filter(|i| *i < to)
+            } else {
+                let last_y  = line_y + parts.len() as isize - 1;
+                let start   = text.len().checked_sub(parts[0].len());
+                let matches = last_y < count
+                    && text.ends_with(parts[0])
+                    && parts[1..parts.len() - 1]
+                        .iter()
+                        .enumerate()
+                        .all(|(i, part)| self.line(line_y + 1 + i as isize).as_str() == *part)
+                    && self.line(last_y).as_str().starts_with(parts[parts.len() - 1]);
+
+                start.filter(|i| matches && *i >= from && *i < to)
+            };
+
+            if let Some(start) = start {
+                let start_x = text[..start].chars().count() as isize;
+                let last    = parts[parts.len() - 1].chars().count() as isize;
+
+                let end = if parts.len() == 1 {
+                    (start_x + last, line_y)
+                } else {
+                    (last, line_y + parts.len() as isize - 1)
+                };
+
+                return Some(((start_x, line_y), end));
+            }
+        }
+
+        None
+    }
+
+    /// returns the position right after the inserted text
+    pub fn insert(&mut self, x: isize, y: isize, text: &str) -> (isize, isize) {
+        self.edits += 1;
+
+        let mut parts = text.split('\n');
+        let     first = parts.next().unwrap();
+        let     rest  = parts.collect::<Vec<&str>>();
+
+        let Some((last, middle)) = rest.split_last() else {
+            self.line_mut(y).insert_str(x, first);
+            return (x + first.chars().count() as isize, y);
+        };
+
+        let trail = self.line_mut(y).split_off(x);
+        self.line_mut(y).push_str(first);
+
+        let     end_x = last.chars().count() as isize;
+        let mut lines = middle
+            .iter()
+            .map(|part| Line::new(String::from(*part)))
+            .collect::<Vec<Line>>();
+
+        lines.push(Line::new(format!("{last}{trail}")));
+
+        let count = lines.len() as isize;
+        self.insert_lines(y as usize + 1, lines);
+
+        (end_x, y + count)
+    }
+
+    /// returns the removed text
+    pub fn remove(&mut self, x1: isize, y1: isize, x2: isize, y2: isize) -> String {
+        let text = self.slice(x1, y1, x2, y2);
+
+        self.edits += 1;
+
+        if y1 == y2 {
+            self.line_mut(y1).drain(x1, x2);
+        } else {
+            let trail = self.line_mut(y2).split_off(x2);
+            let first = self.line_mut(y1);
+
+            first.split_off(x1);
+            first.push_str(&trail);
+
+            self.remove_lines(y1 as usize + 1..=y2 as usize);
+        }
+
+        text
+    }
+
+    fn line_mut(&mut self, y: isize) -> &mut Line {
+        let (chunk, i) = self.locate(y as usize);
+        &mut self.chunks[chunk][i]
+    }
+
+    fn locate(&self, y: usize) -> (usize, usize) {
+        let chunk = self.starts.partition_point(|start| *start <= y) - 1;
+        (chunk, y - self.starts[chunk])
+    }
+
+    fn restart(&mut self, from: usize) {
+        self.starts.truncate(from);
+
+        let mut start = if from == 0 {
+            0
+        } else {
+            self.starts[from - 1] + self.chunks[from - 1].len()
+        };
+
+        for chunk in &self.chunks[from..] {
+            self.starts.push(start);
+            start += chunk.len();
+        }
+
+        self.count = start;
+    }
+
+    fn insert_lines(&mut self, y: usize, lines: Vec<Line>) {
+        let (chunk, i) = if y == self.count {
+            let last = self.chunks.len() - 1;
+            (last, self.chunks[last].len())
+        } else {
+            self.locate(y)
+        };
+
+        self.chunks[chunk].splice(i..i, lines);
+
+        if self.chunks[chunk].len() > CHUNK * 2 {
+            let mut rest   = self.chunks[chunk].split_off(CHUNK);
+            let mut pieces = Vec::new();
+
+            while rest.len() > CHUNK {
+                let tail = rest.split_off(CHUNK);
+                pieces.push(std::mem::replace(&mut rest, tail));
+            }
+            pieces.push(rest);
+
+            self.chunks.splice(chunk + 1..chunk + 1, pieces);
+        }
+
+        self.restart(chunk);
+    }
+
+    fn remove_lines(&mut self, range: RangeInclusive<usize>) {
+        let (first, _) = self.locate(*range.start());
+        let (last,  _) = self.locate(*range.end());
+
+        for chunk in (first..=last).rev() {
+            let start = self.starts[chunk];
+            let from  = range.start().saturating_sub(start);
+            let to    = (range.end() + 1 - start).min(self.chunks[chunk].len());
+
+            self.chunks[chunk].drain(from..to);
+
+            if self.chunks[chunk].is_empty() && self.chunks.len() > 1 {
+                self.chunks.remove(chunk);
+            }
+        }
+
+        self.restart(first.min(self.chunks.len() - 1));
+    }
+}
@@ -0,0 +1,55 @@
+// mochou-p/text-editor/src/clipboard.rs
+
+use std::collections::HashMap;
+use std::io::Write;
+use crate::utils::base64;
+
+
+#[derive(Clone, Default)]
+pub struct Clip {
+    pub text:     String,
+    // NOTE: whole lines, pasted above the cursor
+    pub linewise: bool
+}
+
+#[derive(Default)]
+pub struct Registers {
+    unnamed:      Clip,
+    named:        HashMap<char, Clip>,
+    selected:     Option<char>,
+    pub awaiting: bool
+}
+
+impl Registers {
+    /// picks the register the next copy, cut or paste goes through
+    pub fn select(&mut self, name: char) {
+        self.selected = Some(name);
+        self.awaiting = false;
+    }
+
+    /// returns whether the clip went to the unnamed register
+    pub fn store(&mut self, clip: Clip) -> bool {
+        if let Some(name) = self.selected.take() {
+            self.named.insert(name, clip);
+            false
+        } else {
+            self.unnamed = clip;
+            true
+        }
+    }
+
+    pub fn load(&mut self) -> Option<Clip> {
+        let clip = if let Some(name) = self.selected.take() {
+            self.named.get(&name)?
+        } else {
+            &self.unnamed
+        };
+
+        (!clip.text.is_empty() || clip.linewise).then(|| clip.clone())
+    }
+}
+
+/// sets the system clipboard through the terminal, works over ssh
+pub fn osc52(stdout: &mut impl Write, text: &str) {
+    write!(stdout, "\x1B]52;c;{}\x07", base64::encode(text.as_bytes())).unwrap();
+}
@@ -0,0 +1,135 @@
+// mochou-p/text-editor/src/command.rs
+
+//! every action with a name, for keys to run and for the palette to find
+
+use crate::Editor;
+use crate::keymap::Context;
+use crate::view::{Browsing, Editing, Exiting, Hex, Palette, Themes};
+use Run::{Global, Editing as E, Browsing as B, Hex as H};
+
+
+pub struct Command {
+    pub id:    &'static str,
+    pub title: &'static str,
+    // NOTE: default bindings, written the way the keymap file takes them
+    pub keys:  &'static [&'static str],
+    // NOTE: changes the text, refused on read-only files
+    pub edits: bool,
+    pub run:   Run
+}
+
+pub enum Run {
+    Global  (fn(&mut Editor)),
+    Editing (fn(&mut Editing,  &mut Editor)),
+    Browsing(fn(&mut Browsing, &mut Editor)),
+    Hex     (fn(&mut Hex,      &mut Editor))
+}
+
+impl Run {
+    pub fn context(&self) -> Context {
+        match self {
+            Self::Global  (_) => Context::Global,
+            Self::Editing (_) => Context::Editing,
+            Self::Browsing(_) => Context::Browsing,
+            Self::Hex     (_) => Context::Hex
+        }
+    }
+}
+
+impl Command {
+    const fn new(id: &'static str, title: &'static str, keys: &'static [&'static str], edits: bool, run: Run) -> Self {
+        Self { id, title, keys, edits, run }
+    }
+
+    pub fn find(id: &str) -> Option<&'static Self> {
+        COMMANDS.iter().find(|command| command.id == id)
+    }
+}
+
+pub const COMMANDS: &[Command] = &[
+    Command::new("editor.quit",                "Quit",                                &["Ctrl+Q"],            false, Global(|editor| editor.view::<Exiting, ()>(|editor, view| view.open(editor)))),
+    Command::new("editor.palette",             "Command palette",                     &["Ctrl+P"],            false, Global(|editor| editor.view::<Palette, ()>(|editor, view| view.open(editor)))),
+    Command::new("editor.pick_theme",          "Pick a theme",                        &["Ctrl+K Ctrl+T"],     false, Global(|editor| editor.view::<Themes, ()>(|editor, view| view.open(editor)))),
+    Command::new("editor.reload_config",       "Reload the config and the keymap",    &[],                    false, Global(|editor| editor.reload_config())),
+
+    Command::new("file.save",                  "Save",                                &["Ctrl+S"],            false, E(|view, editor| view.save(editor))),
+    Command::new("file.save_all",              "Save all",                            &["Alt+S"],             false, E(|view, editor| { view.save_all(editor); })),
+    Command::new("file.toggle_line_ending",    "Toggle line ending, LF or CRLF",      &["Alt+L"],             true , E(|view, _     | view.toggle_line_ending())),
+    Command::new("file.reopen_in_encoding",    "Reopen in the next encoding",         &["Alt+E"],             false, E(|view, editor| view.reopen_in_next_encoding(editor))),
+    Command::new("view.open_in_hex",           "Open in hex view",                    &["Alt+H"],             false, E(|view, editor| view.open_in_hex(editor))),
+
+    Command::new("search.find",                "Find and replace",                    &["Ctrl+F"],            false, E(|view, _     | view.find())),
+    Command::new("search.project",             "Find in files",                       &["Alt+F"],             false, E(|view, editor| view.search_project(editor))),
+
+    Command::new("history.undo",               "Undo",                                &["Ctrl+Z"],            true , E(|view, _     | view.undo())),
+    Command::new("history.redo",               "Redo",                                &["Ctrl+Y"],            true , E(|view, _     | view.redo())),
+
+    Command::new("clipboard.copy",             "Copy",                                &["Ctrl+C"],            false, E(|view, editor| view.copy(editor))),
+    Command::new("clipboard.cut",              "Cut",                                 &["Ctrl+X"],            true , E(|view, editor| view.cut(editor))),
+    Command::new("clipboard.paste",            "Paste",                               &["Ctrl+V"],            true , E(|view, editor| view.paste(editor))),
+    Command::new("clipboard.register",         "Pick a register",                     &["Ctrl+R"],            false, E(|_,    editor| editor.registers.awaiting = true)),
+
+    Command::new("cursor.collapse",            "Collapse cursors",                    &["Esc"],               false, E(|view, _     | view.escape())),
+    Command::new("cursor.add_next_occurrence", "Add a cursor at the next occurrence", &["Ctrl+D"],            false, E(|view, _     | view.add_next_occurrence())),
+    Command::new("cursor.add_above",           "Add a cursor above",                  &["Ctrl+Alt+Up"],       false, E(|view, _     | view.add_cursor_above())),
+    Command::new("cursor.add_below",           "Add a cursor below",                  &["Ctrl+Alt+Down"],     false, E(|view, _     | view.add_cursor_below())),
+    Command::new("cursor.left",                "Move left",                           &["Left"],              false, E(|view, editor| view.left      (false, editor.preferences))),
+    Command::new("cursor.right",               "Move right",                          &["Right"],             false, E(|view, editor| view.right     (false, editor.preferences))),
+    Command::new("cursor.up",                  "Move up",                             &["Up"],                false, E(|view, editor| view.up        (false, editor.preferences))),
+    Command::new("cursor.down",                "Move down",                           &["Down"],              false, E(|view, editor| view.down      (false, editor.preferences))),
+    Command::new("cursor.prev_word",           "Move to the previous word",           &["Ctrl+Left"],         false, E(|view, editor| view.prev_word (false, editor.preferences))),
+    Command::new("cursor.next_word",           "Move to the next word",               &["Ctrl+Right"],        false, E(|view, editor| view.next_word (false, editor.preferences))),
+    Command::new("cursor.line_start",          "Move to the start of the line",       &["Home"],              false, E(|view, _     | view.line_start(false))),
+    Command::new("cursor.line_end",            "Move to the end of the line",         &["End"],               false, E(|view, _     | view.line_end  (false))),
+    Command::new("cursor.file_start",          "Move to the start of the file",       &["Ctrl+Home"],         false, E(|view, _     | view.file_start(false))),
+    Command::new("cursor.file_end",            "Move to the end of the file",         &["Ctrl+End"],          false, E(|view, _     | view.file_end  (false))),
+
+    Command::new("select.left",                "Select left",                         &["Shift+Left"],        false, E(|view, editor| view.left      (true , editor.preferences))),
+    Command::new("select.right",               "Select right",                        &["Shift+Right"],       false, E(|view, editor| view.right     (true , editor.preferences))),
+    Command::new("select.up",                  "Select up",                           &["Shift+Up"],          false, E(|view, editor| view.up        (true , editor.preferences))),
+    Command::new("select.down",                "Select down",                         &["Shift+Down"],        false, E(|view, editor| view.down      (true , editor.preferences))),
+    Command::new("select.prev_word",           "Select to the previous word",         &["Ctrl+Shift+Left"],   false, E(|view, editor| view.prev_word (true , editor.preferences))),
+    Command::new("select.next_word",           "Select to the next word",             &["Ctrl+Shift+Right"],  false, E(|view, editor| view.next_word (true , editor.preferences))),
+    Command::new("select.line_start",          "Select to the start of the line",     &["Shift+Home"],        false, E(|view, _     | view.line_start(true ))),
+    Command::new("select.line_end",            "Select to the end of the line",       &["Shift+End"],         false, E(|view, _     | view.line_end  (true ))),
+    Command::new("select.file_start",          "Select to the start of the file",     &["Ctrl+Shift+Home"],   false, E(|view, _     | view.file_start(true ))),
+    Command::new("select.file_end",            "Select to the end of the file",       &["Ctrl+Shift+End"],    false, E(|view, _     | view.file_end  (true ))),
+
+    Command::new("edit.newline",               "Insert a line break",                 &["Enter"],             true , E(|view, _     | view.newline())),
+    Command::new("edit.indent",                "Indent",                              &["Tab"],               true , E(|view, editor| view.tab(editor.tab_width))),
+    Command::new("edit.erase_left",            "Erase left",                          &["Backspace"],         true , E(|view, editor| view.erase_left(editor.preferences))),
+    Command::new("edit.erase_right",           "Erase right",                         &["Delete"],            true , E(|view, editor| view.erase_right(editor.preferences))),
+    Command::new("edit.erase_prev_word",       "Erase the previous word",             &["Ctrl+Backspace"],    true , E(|view, editor| view.erase_prev_word(editor.preferences))),
+    Command::new("edit.erase_next_word",       "Erase the next word",                 &["Ctrl+Delete"],       true , E(|view, editor| view.erase_next_word(editor.preferences))),
+    Command::new("edit.move_line_up",          "Move the line up",                    &["Alt+Up"],            true , E(|view, _     | view.move_line_up())),
+    Command::new("edit.move_line_down",        "Move the line down",                  &["Alt+Down"],          true , E(|view, _     | view.move_line_down())),
+
+    Command::new("browse.up",                  "Browse up",                           &["Up"],                false, B(|view, _     | view.up())),
+    Command::new("browse.down",                "Browse down",                         &["Down"],              false, B(|view, _     | view.down())),
+    Command::new("browse.out",                 "Go to the parent directory",          &["Left", "Backspace"], false, B(|view, _     | view.go_out())),
+    Command::new("browse.in",                  "Open the focused entry",              &["Right", "Enter"],    false, B(|view, editor| view.go_in(editor))),
+    Command::new("browse.search",              "Find in files under this directory",  &["Alt+F"],             false, B(|view, editor| view.search_here(editor))),
+
+    Command::new("hex.save",                   "Save",                                &["Ctrl+S"],            false, H(|view, editor| view.save(editor))),
+    Command::new("hex.save_all",               "Save all",                            &["Alt+S"],             false, H(|view, editor| { view.save_all(editor); })),
+    Command::new("hex.copy",                   "Copy",                                &["Ctrl+C"],            false, H(|view, editor| view.copy(editor))),
+    Command::new("hex.show_as_text",           "Show as text",                        &["Alt+H"],             false, H(|view, editor| view.show_as_text(editor))),
+    Command::new("hex.switch_side",            "Switch between hex and ascii",        &["Tab"],               false, H(|view, _     | view.switch_side())),
+    Command::new("hex.collapse",               "Drop the selection",                  &["Esc"],               false, H(|view, _     | view.escape())),
+    Command::new("hex.left",                   "Move left",                           &["Left"],              false, H(|view, _     | view.left      (false))),
+    Command::new("hex.right",                  "Move right",                          &["Right"],             false, H(|view, _     | view.right     (false))),
+    Command::new("hex.up",                     "Move up",                             &["Up"],                false, H(|view, _     | view.up        (false))),
+    Command::new("hex.down",                   "Move down",                           &["Down"],              false, H(|view, _     | view.down      (false))),
+    Command::new("hex.row_start",              "Move to the start of the row",        &["Home"],              false, H(|view, _     | view.row_start (false))),
+    Command::new("hex.row_end",                "Move to the end of the row",          &["End"],               false, H(|view, _     | view.row_end   (false))),
+    Command::new("hex.file_start",             "Move to the start of the file",       &["Ctrl+Home"],         false, H(|view, _     | view.file_start(false))),
+    Command::new("hex.file_end",               "Move to the end of the file",         &["Ctrl+End"],          false, H(|view, _     | view.file_end  (false))),
+    Command::new("hex.select_left",            "Select left",                         &["Shift+Left"],        false, H(|view, _     | view.left      (true ))),
+    Command::new("hex.select_right",           "Select right",                        &["Shift+Right"],       false, H(|view, _     | view.right     (true ))),
+    Command::new("hex.select_up",              "Select up",                           &["Shift+Up"],          false, H(|view, _     | view.up        (true ))),
+    Command::new("hex.select_down",            "Select down",                         &["Shift+Down"],        false, H(|view, _     | view.down      (true ))),
+    Command::new("hex.select_row_start",       "Select to the start of the row",      &["Shift+Home"],        false, H(|view, _     | view.row_start (true ))),
+    Command::new("hex.select_row_end",         "Select to the end of the row",        &["Shift+End"],         false, H(|view, _     | view.row_end   (true ))),
+    Command::new("hex.select_file_start",      "Select to the start of the file",     &["Ctrl+Shift+Home"],   false, H(|view, _     | view.file_start(true ))),
+    Command::new("hex.select_file_end",        "Select to the end of the file",       &["Ctrl+Shift+End"],    false, H(|view, _     | view.file_end  (true )))
+];
@@ -0,0 +1,120 @@
+// mochou-p/text-editor/src/config/color.rs
+
+//! how many colours the terminal shows, and theme colours brought down to that
+
+use betterm::color::{BgRgb, FgRgb};
+
+
+pub type Rgb = (u8, u8, u8);
+
+// NOTE: the levels of the 6x6x6 cube in the xterm 256 colours, from 16 up
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+// NOTE: the xterm defaults, other terminals are close enough
+const ANSI16: [Rgb; 16] = [
+    (  0,   0,   0), (205,   0,   0), (  0, 205,   0), (205, 205,   0),
+    (  0,   0, 238), (205,   0, 205), (  0, 205, 205), (229, 229, 229),
+    (127, 127, 127), (255,   0,   0), (  0, 255,   0), (255, 255,   0),
+    ( 92,  92, 255), (255,   0, 255), (  0, 255, 255), (255, 255, 255)
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    // NOTE: attributes only, for `NO_COLOR`
+    Monochrome
+}
+
+impl ColorDepth {
+    /// from `NO_COLOR`, then `COLORTERM`, then `TERM`, 16 colours if none of them tell
+    pub fn detect() -> Self {
+        let var = |name: &str| std::env::var(name).unwrap_or_default();
+
+        // NOTE: https://no-color.org, set and not empty
+        if !var("NO_COLOR").is_empty() {
+            return Self::Monochrome;
+        }
+
+        if matches!(var("COLORTERM").as_str(), "truecolor" | "24bit") {
+            return Self::TrueColor;
+        }
+
+        let term = var("TERM");
+
+        if term.ends_with("-direct") {
+            Self::TrueColor
+        } else if term.contains("256color") {
+            Self::Ansi256
+        } else {
+            Self::Ansi16
+        }
+    }
+
+    /// what `color_depth` takes in the config, `auto` being `detect`
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "auto"      => Some(Self::detect()),
+            "truecolor" => Some(Self::TrueColor),
+            "256"       => Some(Self::Ansi256),
+            "16"        => Some(Self::Ansi16),
+            "none"      => Some(Self::Monochrome),
+            _           => None
+        }
+    }
+
+    // NOTE: empty for `Monochrome`, there themes pick attributes instead
+    pub fn background(self, rgb: Rgb) -> String {
+        match self {
+            Self::TrueColor  => BgRgb(rgb.0, rgb.1, rgb.2).to_string(),
+            Self::Ansi256    => format!("\x1b[48;5;{}m", nearest_256(rgb)),
+            Self::Ansi16     => match nearest_16(rgb) {
+                i @ 0..8 => format!("\x1b[{}m", 40 + i),
+                i        => format!("\x1b[{}m", 100 + i - 8)
+            },
+            Self::Monochrome => String::new()
+        }
+    }
+
+    pub fn foreground(self, rgb: Rgb) -> String {
+        match self {
+            Self::TrueColor  => FgRgb(rgb.0, rgb.1, rgb.2).to_string(),
+            Self::Ansi256    => format!("\x1b[38;5;{}m", nearest_256(rgb)),
+            Self::Ansi16     => match nearest_16(rgb) {
+                i @ 0..8 => format!("\x1b[{}m", 30 + i),
+                i        => format!("\x1b[{}m", 90 + i - 8)
+            },
+            Self::Monochrome => String::new()
+        }
+    }
+}
+
+fn distance(a: Rgb, b: Rgb) -> u32 {
+    let channel = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+
+    channel(a.0, b.0) + channel(a.1, b.1) + channel(a.2, b.2)
+}
+
+/// the closer of the nearest colour in the cube and the nearest grey in the ramp
+fn nearest_256(rgb: Rgb) -> u8 {
+    let level = |channel: u8| (0..CUBE.len()).min_by_key(|&i| CUBE[i].abs_diff(channel)).unwrap();
+
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube      = (CUBE[r], CUBE[g], CUBE[b]);
+
+    // NOTE: 24 greys from 8 to 238, 10 apart
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let step    = (average.saturating_sub(3) / 10).min(23) as u8;
+    let grey    = 8 + 10 * step;
+
+    if distance(rgb, (grey, grey, grey)) < distance(rgb, cube) {
+        232 + step
+    } else {
+        16 + 36 * r as u8 + 6 * g as u8 + b as u8
+    }
+}
+
+fn nearest_16(rgb: Rgb) -> u8 {
+    (0..ANSI16.len()).min_by_key(|&i| distance(rgb, ANSI16[i])).unwrap() as u8
+}
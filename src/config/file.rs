@@ -0,0 +1,183 @@
+// mochou-p/text-editor/src/config/file.rs
+
+//! settings from `$XDG_CONFIG_HOME/text-editor/config`, then from `.text-editor.conf`
+//! in the browsing root, one per line, later ones winning:
+//!
+//! ```text
+//! # a comment
+//! theme                            = catppuccin-mocha
+//! theme.backgrounds.primary.normal = #1e1e2e
+//! tab_width                        = 2
+//! color_depth                      = 256
+//! file_save_on_editor_exit         = true
+//! ```
+//!
+//! `theme` starts over from a whole theme, so it goes before the colours it is under,
+//! `color_depth` is one of auto, truecolor, 256, 16 or none, and auto goes by the environment
+
+use std::path::Path;
+use super::{PreferenceMask, Theme};
+use super::color::{ColorDepth, Rgb};
+use crate::utils::xdg;
+
+
+// NOTE: in the browsing root, over the user file
+const PROJECT_FILE: &str = ".text-editor.conf";
+
+pub struct Config {
+    pub theme:       Theme,
+    pub preferences: PreferenceMask,
+    // NOTE: tab inserts spaces up to the next multiple of this
+    pub tab_width:   usize,
+    // NOTE: the theme gets painted in this once both files are in, detected if not set
+    depth:           Option<ColorDepth>,
+    // NOTE: `theme.` colours since the last `theme`, to paint again
+    colors:          Vec<(String, Rgb)>
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            theme:       Theme::default(),
+            preferences: PreferenceMask::default(),
+            tab_width:   4,
+            depth:       None,
+            colors:      Vec::new()
+        }
+    }
+}
+
+impl Config {
+    /// the defaults, changed by the user file and the one in `root`, with what was wrong in them
+    pub fn load(root: &Path) -> (Self, Vec<String>) {
+        let mut config = Self::default();
+        let mut errors = Vec::new();
+
+        if let Some(path) = xdg::config_dir().map(|dir| dir.join("config")) {
+            config.load_file(&path, "config", &mut errors);
+        }
+
+        config.load_file(&root.join(PROJECT_FILE), PROJECT_FILE, &mut errors);
+
+        let depth = config.depth.unwrap_or_else(ColorDepth::detect);
+
+        // NOTE: a built-in one, so it is there
+        config.theme = Theme::named(config.theme.name, depth).unwrap();
+
+        for (path, rgb) in &config.colors {
+            config.theme.set(path, *rgb);
+        }
+
+        (config, errors)
+    }
+
+    /// puts `theme = name` in the user file, over the one there or else at the top
+    pub fn save_theme(name: &str) -> Result<(), String> {
+        let path = xdg::config_dir().map(|dir| dir.join("config")).ok_or("config: there is no config directory")?;
+
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text)                                                    => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(error)                                                  => return Err(format!("config: {error}"))
+        };
+
+        let mut lines = text.lines().map(String::from).collect::<Vec<String>>();
+        let     line  = format!("theme = {name}");
+
+        // NOTE: at the top, so the colours already in there stay over it
+        match lines.iter_mut().find(|other| other.split_once('=').is_some_and(|(key, _)| key.trim() == "theme")) {
+            Some(other) => *other = line,
+            None        => lines.insert(0, line)
+        }
+
+        path.parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&path, lines.join("\n") + "\n"))
+            .map_err(|error| format!("config: {error}"))
+    }
+
+    // NOTE: `name` is short, for the status bar
+    fn load_file(&mut self, path: &Path, name: &str, errors: &mut Vec<String>) {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text)                                                    => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return,
+            Err(error)                                                  => {
+                errors.push(format!("{name}: {error}"));
+                return;
+            }
+        };
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Err(error) = self.load_line(line) {
+                errors.push(format!("{name}:{}: {error}", i + 1));
+            }
+        }
+    }
+
+    fn load_line(&mut self, line: &str) -> Result<(), String> {
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(String::from("expected `setting = value`"));
+        };
+
+        let (key, value) = (key.trim(), value.trim());
+
+        if key == "theme" {
+            self.theme = Theme::named(value, ColorDepth::TrueColor).ok_or_else(|| format!("unknown theme `{value}`"))?;
+            self.colors.clear();
+            return Ok(());
+        }
+
+        if key == "color_depth" {
+            self.depth = Some(ColorDepth::parse(value).ok_or_else(|| format!("`color_depth` takes auto, truecolor, 256, 16 or none, not `{value}`"))?);
+            return Ok(());
+        }
+
+        if key == "tab_width" {
+            self.tab_width = value
+                .parse()
+                .ok()
+                .filter(|width| (1..=16).contains(width))
+                .ok_or_else(|| format!("`tab_width` takes a number from 1 to 16, not `{value}`"))?;
+            return Ok(());
+        }
+
+        if let Some(path) = key.strip_prefix("theme.") {
+            let rgb = parse_color(value).ok_or_else(|| format!("`{key}` takes a colour like #1e1e2e, not `{value}`"))?;
+
+            if !self.theme.set(path, rgb) {
+                return Err(format!("unknown theme colour `{key}`"));
+            }
+
+            self.colors.push((String::from(path), rgb));
+            return Ok(());
+        }
+
+        let Some((_, flag)) = PreferenceMask::NAMES.iter().find(|(name, _)| name.eq_ignore_ascii_case(key)) else {
+            return Err(format!("unknown setting `{key}`"));
+        };
+
+        let on = match value {
+            "true"  => true,
+            "false" => false,
+            _       => return Err(format!("`{key}` takes true or false, not `{value}`"))
+        };
+
+        self.preferences.set(*flag, on);
+
+        Ok(())
+    }
+}
+
+/// `#rrggbb`
+fn parse_color(text: &str) -> Option<Rgb> {
+    let hex  = text.strip_prefix('#').filter(|hex| hex.len() == 6 && hex.is_ascii())?;
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+    Some((byte(0)?, byte(2)?, byte(4)?))
+}
@@ -1,5 +1,8 @@
 // mochou-p/text-editor/src/config/mod.rs
 
+mod color;
+mod file;
+mod preferences;
 mod theme;
 
-pub use theme::Theme;
+pub use {file::Config, preferences::PreferenceMask, theme::{Theme, THEMES}};
@@ -1,30 +1,63 @@
-/* mochou-p/text-editor/src/config/preferences.rs
+// mochou-p/text-editor/src/config/preferences.rs
 
 type Mask = u16;
 
+#[derive(Clone, Copy)]
 pub struct PreferenceMask(pub Mask);
 
-// TODO: macro
-impl PreferenceMask {
-    pub const FILE_SAVE_ON_EDITOR_EXIT:                            Mask = 0b0000_0000_0000_0001;
-
-    pub const CURSOR_MOVE_LEFT_WRAPS_AT_LINE_BOUNDARY:             Mask = 0b0000_0000_0000_0010;
-    pub const CURSOR_MOVE_RIGHT_WRAPS_AT_LINE_BOUNDARY:            Mask = 0b0000_0000_0000_0100;
-    pub const CURSOR_MOVE_TO_PREVIOUS_WORD_WRAPS_AT_LINE_BOUNDARY: Mask = 0b0000_0000_0000_1000;
-    pub const CURSOR_MOVE_TO_NEXT_WORD_WRAPS_AT_LINE_BOUNDARY:     Mask = 0b0000_0000_0001_0000;
-    pub const CURSOR_MOVE_UP_SCROLLS_AT_SCREEN_BOUNDARY:           Mask = 0b0000_0000_0010_0000;
-    pub const CURSOR_MOVE_DOWN_SCROLLS_AT_SCREEN_BOUNDARY:         Mask = 0b0000_0000_0100_0000;
-    pub const CURSOR_MOVE_UP_GOES_TO_START_OF_FILE_AT_FIRST_LINE:  Mask = 0b0000_0000_1000_0000;
-    pub const CURSOR_MOVE_DOWN_GOES_TO_END_OF_FILE_AT_LAST_LINE:   Mask = 0b0000_0001_0000_0000;
-
-    pub const TYPING_ERASE_CHARACTER_LEFT_WRAPS_AT_LINE_BOUNDARY:  Mask = 0b0000_0010_0000_0000;
-    pub const TYPING_ERASE_CHARACTER_RIGHT_WRAPS_AT_LINE_BOUNDARY: Mask = 0b0000_0100_0000_0000;
-    pub const TYPING_ERASE_WORD_LEFT_WRAPS_AT_LINE_BOUNDARY:       Mask = 0b0000_1000_0000_0000;
-    pub const TYPING_ERASE_WORD_RIGHT_WRAPS_AT_LINE_BOUNDARY:      Mask = 0b0001_0000_0000_0000;
+/// a bit per flag, in order, and `NAMES` for the config file to find them by
+macro_rules! preferences {
+    ($($flag:ident),* $(,)?) => {
+        #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+        enum Bit {
+            $($flag),*
+        }
+
+        impl PreferenceMask {
+            $(pub const $flag: Mask = 1 << Bit::$flag as Mask;)*
+
+            // NOTE: the config file takes them in any case
+            pub const NAMES: &[(&str, Mask)] = &[$((stringify!($flag), Self::$flag)),*];
+        }
+
+        const _: () = assert!(PreferenceMask::NAMES.len() <= Mask::BITS as usize, "too many flags for the mask");
+    };
+}
+
+preferences! {
+    FILE_SAVE_ON_EDITOR_EXIT,
+
+    CURSOR_MOVE_LEFT_WRAPS_AT_LINE_BOUNDARY,
+    CURSOR_MOVE_RIGHT_WRAPS_AT_LINE_BOUNDARY,
+    CURSOR_MOVE_TO_PREVIOUS_WORD_WRAPS_AT_LINE_BOUNDARY,
+    CURSOR_MOVE_TO_NEXT_WORD_WRAPS_AT_LINE_BOUNDARY,
+    CURSOR_MOVE_UP_SCROLLS_AT_SCREEN_BOUNDARY,
+    CURSOR_MOVE_DOWN_SCROLLS_AT_SCREEN_BOUNDARY,
+    CURSOR_MOVE_UP_GOES_TO_START_OF_FILE_AT_FIRST_LINE,
+    CURSOR_MOVE_DOWN_GOES_TO_END_OF_FILE_AT_LAST_LINE,
 
+    TYPING_ERASE_CHARACTER_LEFT_WRAPS_AT_LINE_BOUNDARY,
+    TYPING_ERASE_CHARACTER_RIGHT_WRAPS_AT_LINE_BOUNDARY,
+    TYPING_ERASE_WORD_LEFT_WRAPS_AT_LINE_BOUNDARY,
+    TYPING_ERASE_WORD_RIGHT_WRAPS_AT_LINE_BOUNDARY
+}
+
+impl PreferenceMask {
     const fn all() -> Self {
         Self(Mask::MAX)
     }
+
+    pub fn has(&self, flag: Mask) -> bool {
+        self.0 & flag != 0
+    }
+
+    pub fn set(&mut self, flag: Mask, on: bool) {
+        if on {
+            self.0 |= flag;
+        } else {
+            self.0 &= !flag;
+        }
+    }
 }
 
 impl Default for PreferenceMask {
@@ -36,4 +69,3 @@ impl Default for PreferenceMask {
         )
     }
 }
-*/
@@ -1,37 +1,73 @@
 // mochou-p/text-editor/src/config/theme.rs
 
-use betterm::color::{BgRgb, FgRgb};
+use super::color::{ColorDepth, Rgb};
+use crate::highlight::Kind;
 
 
-#[allow(dead_code)]
+// NOTE: select graphic rendition, each one undoes what the others in its slot do
+const NORMAL:         &str = "\x1b[22;24m";
+const BOLD:           &str = "\x1b[1;24m";
+const BOLD_UNDERLINE: &str = "\x1b[1;4m";
+const PLAIN:          &str = "\x1b[27m";
+const REVERSE:        &str = "\x1b[7m";
+
+// NOTE: the first one is the default
+pub const THEMES: &[(&str, Palette)] = &[
+    ("catppuccin-mocha",     CATPPUCCIN_MOCHA),
+    ("catppuccin-macchiato", CATPPUCCIN_MACCHIATO),
+    ("catppuccin-frappe",    CATPPUCCIN_FRAPPE),
+    ("catppuccin-latte",     CATPPUCCIN_LATTE),
+    ("gruvbox-dark",         GRUVBOX_DARK),
+    ("solarized-light",      SOLARIZED_LIGHT),
+    ("high-contrast",        HIGH_CONTRAST)
+];
+
+#[derive(Clone)]
 pub struct Theme {
-    pub backgrounds: Backgrounds,
-    pub foreground:  Foreground,
-    pub special:     Special,
-    pub ansi:        Ansi
+    pub name:         &'static str,
+    pub depth:        ColorDepth,
+    pub backgrounds:  Backgrounds,
+    pub foreground:   Foreground,
+    pub special:      Special,
+    pub ansi:         Ansi,
+    // NOTE: a background
+    pub selection:    String,
+    pub search_match: Highlight,
+    pub line_number:  Foreground,
+    // NOTE: a background, behind the line numbers
+    pub gutter:       String,
+    pub diagnostics:  Diagnostics,
+    pub syntax:       Scopes
 }
 
+#[derive(Clone)]
 pub struct Backgrounds {
     pub primary:   Background,
     pub secondary: Background
 }
 
+#[derive(Clone)]
 pub struct Background {
     pub active:   String,
     pub normal:   String,
     pub disabled: String
 }
 
+#[derive(Clone)]
 pub struct Foreground {
     pub active: String,
     pub normal: String
 }
 
+#[derive(Clone)]
 pub struct Special {
     pub error:    String,
+    // NOTE: for the `<` and `>` at the edges of scrolled lines, drawn again some day
+    #[allow(dead_code)]
     pub overflow: String
 }
 
+#[derive(Clone)]
 #[allow(dead_code)]
 pub struct Ansi {
     pub red:     String,
@@ -42,44 +78,416 @@ pub struct Ansi {
     pub cyan:    String
 }
 
+#[derive(Clone)]
+pub struct Highlight {
+    pub background: String,
+    pub foreground: String
+}
+
+#[derive(Clone)]
+pub struct Diagnostics {
+    pub error:   String,
+    pub warning: String,
+    pub info:    String
+}
+
+/// a foreground for every kind of token
+#[derive(Clone)]
+pub struct Scopes {
+    pub comment:   String,
+    pub string:    String,
+    pub number:    String,
+    pub keyword:   String,
+    pub types:     String,
+    pub function:  String,
+    pub constant:  String,
+    pub attribute: String,
+    pub variable:  String,
+    pub key:       String,
+    pub heading:   String,
+    pub emphasis:  String,
+    pub code:      String,
+    pub link:      String
+}
+
+impl Scopes {
+    pub fn of(&self, kind: Kind) -> &String {
+        match kind {
+            Kind::Comment   => &self.comment,
+            Kind::String    => &self.string,
+            Kind::Number    => &self.number,
+            Kind::Keyword   => &self.keyword,
+            Kind::Type      => &self.types,
+            Kind::Function  => &self.function,
+            Kind::Constant  => &self.constant,
+            Kind::Attribute => &self.attribute,
+            Kind::Variable  => &self.variable,
+            Kind::Key       => &self.key,
+            Kind::Heading   => &self.heading,
+            Kind::Emphasis  => &self.emphasis,
+            Kind::Code      => &self.code,
+            Kind::Link      => &self.link
+        }
+    }
+}
+
+/// the colours a theme is made of, darkest background first, slots get picked from these
+pub struct Palette {
+    crust:    Rgb,
+    mantle:   Rgb,
+    base:     Rgb,
+    surface0: Rgb,
+    surface1: Rgb,
+    surface2: Rgb,
+    subtext:  Rgb,
+    text:     Rgb,
+    red:      Rgb,
+    green:    Rgb,
+    yellow:   Rgb,
+    blue:     Rgb,
+    magenta:  Rgb,
+    cyan:     Rgb,
+    orange:   Rgb
+}
+
 impl Default for Theme {
     fn default() -> Self {
-        Self::catppuccin_mocha()
+        Self::from_palette(THEMES[0].0, &THEMES[0].1, ColorDepth::TrueColor)
     }
 }
 
 impl Theme {
-    // https://catppuccin.com/palette/
-    fn catppuccin_mocha() -> Self {
+    pub fn named(name: &str, depth: ColorDepth) -> Option<Self> {
+        THEMES
+            .iter()
+            .find(|(other, _)| *other == name)
+            .map(|(name, palette)| Self::from_palette(name, palette, depth))
+    }
+
+    /// paints the slot at `path`, like `ansi.red`, returns whether there is one
+    // NOTE: `special` ones are a background and a foreground, so they are left alone,
+    //       and nothing gets painted in monochrome
+    pub fn set(&mut self, path: &str, rgb: Rgb) -> bool {
+        let background = self.depth.background(rgb);
+        let foreground = self.depth.foreground(rgb);
+
+        let (slot, color) = match path {
+            "backgrounds.primary.active"     => (&mut self.backgrounds.primary.active,     background),
+            "backgrounds.primary.normal"     => (&mut self.backgrounds.primary.normal,     background),
+            "backgrounds.primary.disabled"   => (&mut self.backgrounds.primary.disabled,   background),
+            "backgrounds.secondary.active"   => (&mut self.backgrounds.secondary.active,   background),
+            "backgrounds.secondary.normal"   => (&mut self.backgrounds.secondary.normal,   background),
+            "backgrounds.secondary.disabled" => (&mut self.backgrounds.secondary.disabled, background),
+            "foreground.active"              => (&mut self.foreground.active,              foreground),
+            "foreground.normal"              => (&mut self.foreground.normal,              foreground),
+            "ansi.red"                       => (&mut self.ansi.red,                       foreground),
+            "ansi.green"                     => (&mut self.ansi.green,                     foreground),
+            "ansi.yellow"                    => (&mut self.ansi.yellow,                    foreground),
+            "ansi.blue"                      => (&mut self.ansi.blue,                      foreground),
+            "ansi.magenta"                   => (&mut self.ansi.magenta,                   foreground),
+            "ansi.cyan"                      => (&mut self.ansi.cyan,                      foreground),
+            "selection"                      => (&mut self.selection,                      background),
+            "search_match.background"        => (&mut self.search_match.background,        background),
+            "search_match.foreground"        => (&mut self.search_match.foreground,        foreground),
+            "line_number.active"             => (&mut self.line_number.active,             foreground),
+            "line_number.normal"             => (&mut self.line_number.normal,             foreground),
+            "gutter"                         => (&mut self.gutter,                         background),
+            "diagnostics.error"              => (&mut self.diagnostics.error,              foreground),
+            "diagnostics.warning"            => (&mut self.diagnostics.warning,            foreground),
+            "diagnostics.info"               => (&mut self.diagnostics.info,               foreground),
+            "syntax.comment"                 => (&mut self.syntax.comment,                 foreground),
+            "syntax.string"                  => (&mut self.syntax.string,                  foreground),
+            "syntax.number"                  => (&mut self.syntax.number,                  foreground),
+            "syntax.keyword"                 => (&mut self.syntax.keyword,                 foreground),
+            "syntax.type"                    => (&mut self.syntax.types,                   foreground),
+            "syntax.function"                => (&mut self.syntax.function,                foreground),
+            "syntax.constant"                => (&mut self.syntax.constant,                foreground),
+            "syntax.attribute"               => (&mut self.syntax.attribute,               foreground),
+            "syntax.variable"                => (&mut self.syntax.variable,                foreground),
+            "syntax.key"                     => (&mut self.syntax.key,                     foreground),
+            "syntax.heading"                 => (&mut self.syntax.heading,                 foreground),
+            "syntax.emphasis"                => (&mut self.syntax.emphasis,                foreground),
+            "syntax.code"                    => (&mut self.syntax.code,                    foreground),
+            "syntax.link"                    => (&mut self.syntax.link,                    foreground),
+            _                                => return false
+        };
+
+        if self.depth != ColorDepth::Monochrome {
+            *slot = color;
+        }
+
+        true
+    }
+
+    fn from_palette(name: &'static str, palette: &Palette, depth: ColorDepth) -> Self {
+        if depth == ColorDepth::Monochrome {
+            return Self::monochrome(name);
+        }
+
+        let bg = |rgb: Rgb| depth.background(rgb);
+        let fg = |rgb: Rgb| depth.foreground(rgb);
+
+        Self {
+            name,
+            depth,
+            backgrounds: Backgrounds {
+                primary: Background {
+                    active:   bg(palette.surface0),
+                    normal:   bg(palette.base),
+                    disabled: bg(palette.crust)
+                },
+                secondary: Background {
+                    active:   bg(palette.surface1),
+                    normal:   bg(palette.surface0),
+                    disabled: bg(palette.mantle)
+                }
+            },
+            foreground: Foreground {
+                active: fg(palette.text),
+                normal: fg(palette.subtext)
+            },
+            special: Special {
+                error:    bg(palette.red)    + &fg(palette.crust),
+                overflow: bg(palette.orange) + &fg(palette.crust)
+            },
+            ansi: Ansi {
+                red:     fg(palette.red),
+                green:   fg(palette.green),
+                yellow:  fg(palette.yellow),
+                blue:    fg(palette.blue),
+                magenta: fg(palette.magenta),
+                cyan:    fg(palette.cyan)
+            },
+            selection:    bg(palette.surface1),
+            search_match: Highlight {
+                background: bg(palette.surface0),
+                foreground: fg(palette.yellow)
+            },
+            line_number: Foreground {
+                active: fg(palette.text),
+                normal: fg(palette.surface2)
+            },
+            gutter:       bg(palette.mantle),
+            diagnostics:  Diagnostics {
+                error:   fg(palette.red),
+                warning: fg(palette.yellow),
+                info:    fg(palette.text)
+            },
+            syntax: Scopes {
+                comment:   fg(palette.surface2),
+                string:    fg(palette.green),
+                number:    fg(palette.orange),
+                keyword:   fg(palette.magenta),
+                types:     fg(palette.yellow),
+                function:  fg(palette.blue),
+                constant:  fg(palette.orange),
+                attribute: fg(palette.cyan),
+                variable:  fg(palette.red),
+                key:       fg(palette.blue),
+                heading:   fg(palette.red),
+                emphasis:  fg(palette.magenta),
+                code:      fg(palette.green),
+                link:      fg(palette.cyan)
+            }
+        }
+    }
+
+    /// reverse video for what stands out behind, bold and underline for what does in front
+    fn monochrome(name: &'static str) -> Self {
+        let s = String::from;
+
         Self {
+            name,
+            depth: ColorDepth::Monochrome,
             backgrounds: Backgrounds {
                 primary: Background {
-                    active:   BgRgb(49, 50, 68).to_string(),
-                    normal:   BgRgb(30, 30, 46).to_string(),
-                    disabled: BgRgb(17, 17, 27).to_string()
+                    active:   s(PLAIN),
+                    normal:   s(PLAIN),
+                    disabled: s(PLAIN)
                 },
                 secondary: Background {
-                    active:   BgRgb(69, 71, 90).to_string(),
-                    normal:   BgRgb(49, 50, 68).to_string(),
-                    disabled: BgRgb(24, 24, 37).to_string()
+                    active:   s(REVERSE),
+                    normal:   s(PLAIN),
+                    disabled: s(PLAIN)
                 }
             },
             foreground: Foreground {
-                active: FgRgb(205, 214, 244).to_string(),
-                normal: FgRgb(166, 173, 200).to_string()
+                active: s(BOLD),
+                normal: s(NORMAL)
             },
             special: Special {
-                error:    BgRgb(210,  15, 57).to_string() + &FgRgb(17, 17, 27).to_string(),
-                overflow: BgRgb(223, 142, 29).to_string() + &FgRgb(17, 17, 27).to_string()
+                error:    s(REVERSE) + BOLD,
+                overflow: s(REVERSE) + NORMAL
             },
             ansi: Ansi {
-                red:     FgRgb(243, 139, 168).to_string(),
-                green:   FgRgb(166, 227, 161).to_string(),
-                yellow:  FgRgb(249, 226, 175).to_string(),
-                blue:    FgRgb(137, 180, 250).to_string(),
-                magenta: FgRgb(245, 194, 231).to_string(),
-                cyan:    FgRgb(137, 220, 235).to_string()
+                red:     s(BOLD),
+                green:   s(NORMAL),
+                yellow:  s(BOLD),
+                blue:    s(BOLD),
+                magenta: s(NORMAL),
+                cyan:    s(NORMAL)
+            },
+            selection:    s(REVERSE),
+            search_match: Highlight {
+                background: s(PLAIN),
+                foreground: s(BOLD_UNDERLINE)
+            },
+            line_number: Foreground {
+                active: s(BOLD),
+                normal: s(NORMAL)
+            },
+            gutter:       s(PLAIN),
+            diagnostics:  Diagnostics {
+                error:   s(BOLD_UNDERLINE),
+                warning: s(BOLD),
+                info:    s(NORMAL)
+            },
+            syntax: Scopes {
+                comment:   s(NORMAL),
+                string:    s(NORMAL),
+                number:    s(NORMAL),
+                keyword:   s(BOLD),
+                types:     s(NORMAL),
+                function:  s(NORMAL),
+                constant:  s(NORMAL),
+                attribute: s(NORMAL),
+                variable:  s(NORMAL),
+                key:       s(BOLD),
+                heading:   s(BOLD_UNDERLINE),
+                emphasis:  s(BOLD),
+                code:      s(NORMAL),
+                link:      s(BOLD_UNDERLINE)
             }
         }
     }
 }
+
+// https://catppuccin.com/palette/
+const CATPPUCCIN_MOCHA: Palette = Palette {
+    crust:    ( 17,  17,  27),
+    mantle:   ( 24,  24,  37),
+    base:     ( 30,  30,  46),
+    surface0: ( 49,  50,  68),
+    surface1: ( 69,  71,  90),
+    surface2: ( 88,  91, 112),
+    subtext:  (166, 173, 200),
+    text:     (205, 214, 244),
+    red:      (243, 139, 168),
+    green:    (166, 227, 161),
+    yellow:   (249, 226, 175),
+    blue:     (137, 180, 250),
+    magenta:  (245, 194, 231),
+    cyan:     (137, 220, 235),
+    orange:   (250, 179, 135)
+};
+
+const CATPPUCCIN_MACCHIATO: Palette = Palette {
+    crust:    ( 24,  25,  38),
+    mantle:   ( 30,  32,  48),
+    base:     ( 36,  39,  58),
+    surface0: ( 54,  58,  79),
+    surface1: ( 73,  77, 100),
+    surface2: ( 91,  96, 120),
+    subtext:  (165, 173, 203),
+    text:     (202, 211, 245),
+    red:      (237, 135, 150),
+    green:    (166, 218, 149),
+    yellow:   (238, 212, 159),
+    blue:     (138, 173, 244),
+    magenta:  (245, 189, 230),
+    cyan:     (145, 215, 227),
+    orange:   (245, 169, 127)
+};
+
+const CATPPUCCIN_FRAPPE: Palette = Palette {
+    crust:    ( 35,  38,  52),
+    mantle:   ( 41,  44,  60),
+    base:     ( 48,  52,  70),
+    surface0: ( 65,  69,  89),
+    surface1: ( 81,  87, 109),
+    surface2: ( 98, 104, 128),
+    subtext:  (165, 173, 206),
+    text:     (198, 208, 245),
+    red:      (231, 130, 132),
+    green:    (166, 209, 137),
+    yellow:   (229, 200, 144),
+    blue:     (140, 170, 238),
+    magenta:  (244, 184, 228),
+    cyan:     (153, 209, 219),
+    orange:   (239, 159, 118)
+};
+
+const CATPPUCCIN_LATTE: Palette = Palette {
+    crust:    (220, 224, 232),
+    mantle:   (230, 233, 239),
+    base:     (239, 241, 245),
+    surface0: (204, 208, 218),
+    surface1: (188, 192, 204),
+    surface2: (172, 176, 190),
+    subtext:  (108, 111, 133),
+    text:     ( 76,  79, 105),
+    red:      (210,  15,  57),
+    green:    ( 64, 160,  43),
+    yellow:   (223, 142,  29),
+    blue:     ( 30, 102, 245),
+    magenta:  (234, 118, 203),
+    cyan:     (  4, 165, 229),
+    orange:   (254, 100,  11)
+};
+
+// https://github.com/morhetz/gruvbox
+const GRUVBOX_DARK: Palette = Palette {
+    crust:    ( 29,  32,  33),
+    mantle:   ( 29,  32,  33),
+    base:     ( 40,  40,  40),
+    surface0: ( 60,  56,  54),
+    surface1: ( 80,  73,  69),
+    surface2: (102,  92,  84),
+    subtext:  (168, 153, 132),
+    text:     (235, 219, 178),
+    red:      (251,  73,  52),
+    green:    (184, 187,  38),
+    yellow:   (250, 189,  47),
+    blue:     (131, 165, 152),
+    magenta:  (211, 134, 155),
+    cyan:     (142, 192, 124),
+    orange:   (254, 128,  25)
+};
+
+// https://ethanschoonover.com/solarized/
+// NOTE: solarized has no darker backgrounds than base2, crust and surface1 are in between
+const SOLARIZED_LIGHT: Palette = Palette {
+    crust:    (227, 220, 200),
+    mantle:   (238, 232, 213),
+    base:     (253, 246, 227),
+    surface0: (238, 232, 213),
+    surface1: (220, 214, 196),
+    surface2: (147, 161, 161),
+    subtext:  (101, 123, 131),
+    text:     ( 88, 110, 117),
+    red:      (220,  50,  47),
+    green:    (133, 153,   0),
+    yellow:   (181, 137,   0),
+    blue:     ( 38, 139, 210),
+    magenta:  (211,  54, 130),
+    cyan:     ( 42, 161, 152),
+    orange:   (203,  75,  22)
+};
+
+const HIGH_CONTRAST: Palette = Palette {
+    crust:    (  0,   0,   0),
+    mantle:   (  0,   0,   0),
+    base:     (  0,   0,   0),
+    surface0: ( 48,  48,  48),
+    surface1: ( 96,  96,  96),
+    surface2: (160, 160, 160),
+    subtext:  (230, 230, 230),
+    text:     (255, 255, 255),
+    red:      (255,  85,  85),
+    green:    ( 85, 255,  85),
+    yellow:   (255, 255,  85),
+    blue:     ( 85, 170, 255),
+    magenta:  (255,  85, 255),
+    cyan:     ( 85, 255, 255),
+    orange:   (255, 170,   0)
+};
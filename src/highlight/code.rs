@@ -0,0 +1,388 @@
+// mochou-p/text-editor/src/highlight/code.rs
+
+//! one lexer for the languages that differ mostly in their words and delimiters,
+//! told apart by a `Syntax`
+
+use super::{Kind, State, Token};
+
+
+// NOTE: every delimiter is ascii, so its length in bytes is its length in chars
+pub struct Syntax {
+    pub line_comments: &'static [&'static str],
+    pub block_comment: Option<(&'static str, &'static str)>,
+    // NOTE: block comments inside block comments
+    pub nested:        bool,
+    // NOTE: tried in order, so longer ones that start the same go first
+    pub quotes:        &'static [Quote],
+    // NOTE: letters that can go right before a quote, like `b"` or `f'`
+    pub prefixes:      &'static [&'static str],
+    // NOTE: rust `'a'` chars, told apart from `'a` lifetimes
+    pub chars:         bool,
+    pub keywords:      &'static [&'static str],
+    pub types:         &'static [&'static str],
+    pub constants:     &'static [&'static str],
+    // NOTE: `Capitalised` words are types, `ALL_CAPS` ones constants
+    pub capitals:      bool,
+    // NOTE: `name!` calls
+    pub macros:        bool,
+    pub attribute:     Attribute,
+    pub variable:      Option<char>,
+    pub keys:          Keys,
+    // NOTE: `[table]` headers at the start of a line
+    pub tables:        bool,
+    // NOTE: line comments only at the start of a line or after a space
+    pub spaced:        bool
+}
+
+pub struct Quote {
+    pub open:    &'static str,
+    pub close:   &'static str,
+    pub escapes: bool,
+    // NOTE: whether it goes on to the next line when not closed
+    pub lines:   bool
+}
+
+pub enum Attribute {
+    None,
+    // NOTE: the rest of a line starting with it, like `#include`
+    Directive(char),
+    // NOTE: up to the matching `]`, like `#[derive(Clone)]`
+    Bracketed(&'static str),
+    // NOTE: and the dotted name after it, like `@functools.cache`
+    Decorator(char)
+}
+
+pub enum Keys {
+    None,
+    // NOTE: strings followed by `:`
+    Colon,
+    // NOTE: what comes before `=` at the start of a line
+    Equals
+}
+
+/// adds the tokens of `line` from `from` on to `tokens`, returns what the next line starts in
+pub fn lex(syntax: &Syntax, line: &[char], from: usize, state: State, tokens: &mut Vec<Token>) -> State {
+    let mut i = from;
+
+    match state {
+        State::Comment(depth) => {
+            let (end, depth) = block_comment(syntax, line, i, depth);
+            push(tokens, i..end, Kind::Comment);
+
+            if depth > 0 {
+                return State::Comment(depth);
+            }
+
+            i = end;
+        },
+        State::String(quote) => {
+            let Some(open) = syntax.quotes.get(quote as usize) else { return State::Normal; };
+
+            let (end, closed) = string_end(open, line, i);
+            push(tokens, i..end, Kind::String);
+
+            if !closed {
+                return state;
+            }
+
+            i = end;
+        },
+        State::Normal | State::Fence => ()
+    }
+
+    let indent = i + line[i..].iter().take_while(|ch| ch.is_whitespace()).count();
+
+    while i < line.len() {
+        let ch    = line[i];
+        let first = i == indent;
+
+        if ch.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if first && matches!(syntax.attribute, Attribute::Directive(directive) if directive == ch) {
+            push(tokens, i..line.len(), Kind::Attribute);
+            break;
+        }
+
+        if first && syntax.tables && ch == '[' {
+            let end = line[i..].iter().rposition(|ch| *ch == ']').map_or(line.len(), |j| i + j + 1);
+
+            push(tokens, i..end, Kind::Type);
+            i = end;
+            continue;
+        }
+
+        if first && matches!(syntax.keys, Keys::Equals) && let Some(j) = line[i..].iter().position(|ch| *ch == '=') {
+            let end = i + line[i..i + j].iter().rposition(|ch| !ch.is_whitespace()).map_or(0, |k| k + 1);
+
+            push(tokens, i..end, Kind::Key);
+            i += j + 1;
+            continue;
+        }
+
+        let spaced = !syntax.spaced || i == 0 || line[i - 1].is_whitespace();
+
+        if spaced && syntax.line_comments.iter().any(|comment| starts_with(line, i, comment)) {
+            push(tokens, i..line.len(), Kind::Comment);
+            break;
+        }
+
+        if let Some((open, _)) = syntax.block_comment.filter(|(open, _)| starts_with(line, i, open)) {
+            let (end, depth) = block_comment(syntax, line, i + open.len(), 1);
+            push(tokens, i..end, Kind::Comment);
+
+            if depth > 0 {
+                return State::Comment(depth);
+            }
+
+            i = end;
+            continue;
+        }
+
+        if let Some((skip, quote)) = quote_at(syntax, line, i) {
+            let open          = &syntax.quotes[quote];
+            let (end, closed) = string_end(open, line, i + skip);
+
+            let key  = matches!(syntax.keys, Keys::Colon) && line[end..].iter().find(|ch| !ch.is_whitespace()) == Some(&':');
+            let kind = if key { Kind::Key } else { Kind::String };
+
+            push(tokens, i..end, kind);
+
+            if !closed && open.lines {
+                return State::String(quote as u8);
+            }
+
+            i = end;
+            continue;
+        }
+
+        if syntax.chars && ch == '\'' {
+            let end = if line.get(i + 1) == Some(&'\\') {
+                line[i + 2..].iter().position(|ch| *ch == '\'').map_or(line.len(), |j| i + 2 + j + 1)
+            } else if line.get(i + 2) == Some(&'\'') {
+                i + 3
+            } else {
+                // NOTE: a lifetime, or a loop label
+                let end = i + 1 + line[i + 1..].iter().take_while(|ch| is_word(**ch)).count();
+
+                push(tokens, i..end, Kind::Constant);
+                i = end;
+                continue;
+            };
+
+            push(tokens, i..end, Kind::String);
+            i = end;
+            continue;
+        }
+
+        if let Some(end) = attribute_end(syntax, line, i) {
+            push(tokens, i..end, Kind::Attribute);
+            i = end;
+            continue;
+        }
+
+        if syntax.variable == Some(ch) && let Some(end) = variable_end(line, i) {
+            push(tokens, i..end, Kind::Variable);
+            i = end;
+            continue;
+        }
+
+        if ch.is_ascii_digit() {
+            let mut end = i + 1;
+
+            // NOTE: `0x1f`, `1_000u32`, `1.5e3`, but not the `..` in `0..10`
+            while end < line.len() && (is_word(line[end]) || line[end] == '.' && line.get(end + 1).is_some_and(char::is_ascii_digit)) {
+                end += 1;
+            }
+
+            push(tokens, i..end, Kind::Number);
+            i = end;
+            continue;
+        }
+
+        if is_word(ch) {
+            let end  = i + line[i..].iter().take_while(|ch| is_word(**ch)).count();
+            let word = line[i..end].iter().collect::<String>();
+
+            i = match classify(syntax, line, &word, end) {
+                Some((kind, end)) => {
+                    push(tokens, i..end, kind);
+                    end
+                },
+                None => end
+            };
+
+            continue;
+        }
+
+        i += 1;
+    }
+
+    State::Normal
+}
+
+fn classify(syntax: &Syntax, line: &[char], word: &str, end: usize) -> Option<(Kind, usize)> {
+    let next = line[end..].iter().find(|ch| !ch.is_whitespace());
+
+    if syntax.keywords.contains(&word) {
+        return Some((Kind::Keyword, end));
+    }
+
+    if syntax.constants.contains(&word) {
+        return Some((Kind::Constant, end));
+    }
+
+    if syntax.types.contains(&word) {
+        return Some((Kind::Type, end));
+    }
+
+    if syntax.macros && line.get(end) == Some(&'!') && line.get(end + 1) != Some(&'=') {
+        return Some((Kind::Function, end + 1));
+    }
+
+    // NOTE: `name=value` in shell
+    if syntax.variable.is_some() && line.get(end) == Some(&'=') {
+        return Some((Kind::Variable, end));
+    }
+
+    if next == Some(&'(') {
+        return Some((Kind::Function, end));
+    }
+
+    if syntax.capitals && word.starts_with(|ch: char| ch.is_uppercase()) {
+        let constant = word.chars().count() > 1 && !word.chars().any(char::is_lowercase);
+
+        return Some((if constant { Kind::Constant } else { Kind::Type }, end));
+    }
+
+    None
+}
+
+/// where a block comment ends, from inside it `depth` deep, and how deep it still is there
+fn block_comment(syntax: &Syntax, line: &[char], from: usize, depth: u8) -> (usize, u8) {
+    let Some((open, close)) = syntax.block_comment else { return (from, 0); };
+
+    let mut i     = from;
+    let mut depth = depth;
+
+    while i < line.len() {
+        if starts_with(line, i, close) {
+            i     += close.len();
+            depth -= 1;
+
+            if depth == 0 {
+                return (i, 0);
+            }
+        } else if syntax.nested && starts_with(line, i, open) {
+            i     += open.len();
+            depth  = depth.saturating_add(1);
+        } else {
+            i += 1;
+        }
+    }
+
+    (line.len(), depth)
+}
+
+/// where the string in `quote` ends, from inside it, and whether it got closed
+fn string_end(quote: &Quote, line: &[char], from: usize) -> (usize, bool) {
+    let mut i = from;
+
+    while i < line.len() {
+        if quote.escapes && line[i] == '\\' {
+            i += 2;
+        } else if starts_with(line, i, quote.close) {
+            return (i + quote.close.len(), true);
+        } else {
+            i += 1;
+        }
+    }
+
+    (line.len(), false)
+}
+
+/// how long the opening of a string at `i` is, with its prefix, and which quote it is
+fn quote_at(syntax: &Syntax, line: &[char], i: usize) -> Option<(usize, usize)> {
+    if i > 0 && is_word(line[i - 1]) {
+        return None;
+    }
+
+    std::iter::once(&"").chain(syntax.prefixes).find_map(|prefix| {
+        if !starts_with(line, i, prefix) {
+            return None;
+        }
+
+        let at = i + prefix.len();
+
+        syntax.quotes
+            .iter()
+            .position(|quote| starts_with(line, at, quote.open))
+            .map(|quote| (prefix.len() + syntax.quotes[quote].open.len(), quote))
+    })
+}
+
+fn attribute_end(syntax: &Syntax, line: &[char], i: usize) -> Option<usize> {
+    match syntax.attribute {
+        Attribute::Bracketed(start) => {
+            if !starts_with(line, i, start) {
+                return None;
+            }
+
+            let open = i + start.len() + usize::from(line.get(i + start.len()) == Some(&'!'));
+
+            if line.get(open) != Some(&'[') {
+                return None;
+            }
+
+            let mut depth = 0;
+
+            for (j, ch) in line.iter().enumerate().skip(open) {
+                match ch {
+                    '[' => depth += 1,
+                    ']' => {
+                        depth -= 1;
+
+                        if depth == 0 {
+                            return Some(j + 1);
+                        }
+                    },
+                    _   => ()
+                }
+            }
+
+            Some(line.len())
+        },
+        Attribute::Decorator(start) if line[i] == start && line.get(i + 1).is_some_and(|ch| is_word(*ch)) => {
+            Some(i + 1 + line[i + 1..].iter().take_while(|ch| is_word(**ch) || **ch == '.').count())
+        },
+        _ => None
+    }
+}
+
+/// `$name`, `${name}` or `$?` and the like
+fn variable_end(line: &[char], i: usize) -> Option<usize> {
+    match line.get(i + 1)? {
+        '{'                                     => Some(line[i..].iter().position(|ch| *ch == '}').map_or(line.len(), |j| i + j + 1)),
+        ch if is_word(*ch)                      => Some(i + 1 + line[i + 1..].iter().take_while(|ch| is_word(**ch)).count()),
+        '@' | '#' | '?' | '$' | '!' | '*' | '-' => Some(i + 2),
+        _                                       => None
+    }
+}
+
+pub(super) fn is_word(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+pub(super) fn starts_with(line: &[char], i: usize, text: &str) -> bool {
+    let mut chars = line.iter().skip(i);
+
+    text.chars().all(|ch| chars.next() == Some(&ch))
+}
+
+pub(super) fn push(tokens: &mut Vec<Token>, range: std::ops::Range<usize>, kind: Kind) {
+    if !range.is_empty() {
+        tokens.push(Token { range, kind });
+    }
+}
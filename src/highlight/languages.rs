@@ -0,0 +1,263 @@
+// mochou-p/text-editor/src/highlight/languages.rs
+
+use std::path::Path;
+use super::{State, Token};
+use super::code::{self, Attribute, Keys, Quote, Syntax};
+use super::markup;
+
+
+pub struct Language {
+    pub name:     &'static str,
+    extensions:   &'static [&'static str],
+    // NOTE: whole file names, for the ones without an extension
+    names:        &'static [&'static str],
+    // NOTE: what a `#!` runs, a version after it is fine
+    interpreters: &'static [&'static str],
+    lexer:        Lexer
+}
+
+enum Lexer {
+    Code(&'static Syntax),
+    Markdown,
+    Yaml
+}
+
+const LANGUAGES: &[Language] = &[
+    Language { name: "Rust",     extensions: &["rs"],                          names: &[],                                                   interpreters: &[],                             lexer: Lexer::Code(&RUST)   },
+    Language { name: "TOML",     extensions: &["toml"],                        names: &["Cargo.lock"],                                       interpreters: &[],                             lexer: Lexer::Code(&TOML)   },
+    Language { name: "Markdown", extensions: &["md", "markdown"],              names: &[],                                                   interpreters: &[],                             lexer: Lexer::Markdown      },
+    Language { name: "Shell",    extensions: &["sh", "bash", "zsh"],           names: &[".bashrc", ".bash_profile", ".zshrc", ".profile"],  interpreters: &["sh", "bash", "zsh", "dash"], lexer: Lexer::Code(&SHELL)  },
+    Language { name: "JSON",     extensions: &["json"],                        names: &[],                                                   interpreters: &[],                             lexer: Lexer::Code(&JSON)   },
+    Language { name: "YAML",     extensions: &["yaml", "yml"],                 names: &[],                                                   interpreters: &[],                             lexer: Lexer::Yaml          },
+    Language { name: "C",        extensions: &["c", "h"],                      names: &[],                                                   interpreters: &[],                             lexer: Lexer::Code(&C)      },
+    Language { name: "Python",   extensions: &["py", "pyi"],                   names: &[],                                                   interpreters: &["python"],                     lexer: Lexer::Code(&PYTHON) }
+];
+
+impl Language {
+    pub fn detect(path: &Path, first_line: &str) -> Option<&'static Self> {
+        let name      = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        let extension = path.extension().and_then(|extension| extension.to_str()).map(str::to_ascii_lowercase);
+
+        let by_path = LANGUAGES.iter().find(|language| {
+            language.names.contains(&name)
+            ||
+            extension.as_deref().is_some_and(|extension| language.extensions.contains(&extension))
+        });
+
+        by_path.or_else(|| {
+            let program = interpreter(first_line)?;
+
+            LANGUAGES.iter().find(|language| {
+                language.interpreters.iter().any(|interpreter| {
+                    program
+                        .strip_prefix(interpreter)
+                        .is_some_and(|version| version.chars().all(|ch| ch.is_ascii_digit() || ch == '.'))
+                })
+            })
+        })
+    }
+
+    /// adds the tokens of `line` to `tokens`, returns what the next line starts in
+    pub fn lex(&self, line: &[char], state: State, tokens: &mut Vec<Token>) -> State {
+        match self.lexer {
+            Lexer::Code(syntax) => code::lex(syntax, line, 0, state, tokens),
+            Lexer::Markdown     => markup::markdown(line, state, tokens),
+            Lexer::Yaml         => markup::yaml(line, state, tokens)
+        }
+    }
+}
+
+/// the program `#!/usr/bin/env python3` or `#!/bin/sh -e` runs
+fn interpreter(first_line: &str) -> Option<&str> {
+    let mut words = first_line.strip_prefix("#!")?.split_whitespace();
+    let     path  = words.next()?;
+    let     name  = path.rsplit('/').next()?;
+
+    if name == "env" {
+        words.find(|word| !word.starts_with('-') && !word.contains('='))
+    } else {
+        Some(name)
+    }
+}
+
+const RUST: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    nested:        true,
+    quotes:        &[
+        Quote { open: "r#\"", close: "\"#", escapes: false, lines: true  },
+        Quote { open: "r\"",  close: "\"",  escapes: false, lines: true  },
+        Quote { open: "\"",   close: "\"",  escapes: true,  lines: true  }
+    ],
+    prefixes:      &["b", "c"],
+    chars:         true,
+    keywords:      &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "fn", "for",
+        "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self",
+        "static", "struct", "super", "trait", "type", "union", "unsafe", "use", "where", "while", "yield"
+    ],
+    types:         &[
+        "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128",
+        "isize", "f32", "f64"
+    ],
+    constants:     &["true", "false", "None", "Some", "Ok", "Err"],
+    capitals:      true,
+    macros:        true,
+    attribute:     Attribute::Bracketed("#"),
+    variable:      None,
+    keys:          Keys::None,
+    tables:        false,
+    spaced:        false
+};
+
+const C: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    nested:        false,
+    quotes:        &[
+        Quote { open: "\"", close: "\"", escapes: true, lines: false },
+        Quote { open: "'",  close: "'",  escapes: true, lines: false }
+    ],
+    prefixes:      &["L", "u8", "u", "U"],
+    chars:         false,
+    keywords:      &[
+        "break", "case", "const", "continue", "default", "do", "else", "enum", "extern", "for", "goto", "if",
+        "inline", "register", "restrict", "return", "sizeof", "static", "struct", "switch", "typedef", "union",
+        "volatile", "while"
+    ],
+    types:         &[
+        "void", "char", "short", "int", "long", "float", "double", "signed", "unsigned", "bool", "size_t",
+        "ssize_t", "int8_t", "int16_t", "int32_t", "int64_t", "uint8_t", "uint16_t", "uint32_t", "uint64_t",
+        "FILE"
+    ],
+    constants:     &["NULL", "true", "false"],
+    capitals:      false,
+    macros:        false,
+    attribute:     Attribute::Directive('#'),
+    variable:      None,
+    keys:          Keys::None,
+    tables:        false,
+    spaced:        false
+};
+
+const PYTHON: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comment: None,
+    nested:        false,
+    quotes:        &[
+        Quote { open: "\"\"\"", close: "\"\"\"", escapes: true, lines: true  },
+        Quote { open: "'''",    close: "'''",    escapes: true, lines: true  },
+        Quote { open: "\"",     close: "\"",     escapes: true, lines: false },
+        Quote { open: "'",      close: "'",      escapes: true, lines: false }
+    ],
+    prefixes:      &["rb", "br", "fr", "rf", "r", "b", "f", "u"],
+    chars:         false,
+    keywords:      &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif", "else",
+        "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "match", "case",
+        "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield", "self"
+    ],
+    types:         &["int", "float", "str", "bytes", "bool", "list", "dict", "set", "tuple", "object"],
+    constants:     &["True", "False", "None"],
+    capitals:      true,
+    macros:        false,
+    attribute:     Attribute::Decorator('@'),
+    variable:      None,
+    keys:          Keys::None,
+    tables:        false,
+    spaced:        false
+};
+
+const SHELL: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comment: None,
+    nested:        false,
+    quotes:        &[
+        Quote { open: "\"", close: "\"", escapes: true,  lines: true },
+        Quote { open: "'",  close: "'",  escapes: false, lines: true }
+    ],
+    prefixes:      &[],
+    chars:         false,
+    keywords:      &[
+        "if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case", "esac", "in",
+        "function", "return", "local", "export", "readonly", "declare", "break", "continue", "exit", "set",
+        "unset", "shift", "source"
+    ],
+    types:         &[],
+    constants:     &["true", "false"],
+    capitals:      false,
+    macros:        false,
+    attribute:     Attribute::None,
+    variable:      Some('$'),
+    keys:          Keys::None,
+    tables:        false,
+    spaced:        true
+};
+
+const JSON: Syntax = Syntax {
+    line_comments: &[],
+    block_comment: None,
+    nested:        false,
+    quotes:        &[
+        Quote { open: "\"", close: "\"", escapes: true, lines: false }
+    ],
+    prefixes:      &[],
+    chars:         false,
+    keywords:      &[],
+    types:         &[],
+    constants:     &["true", "false", "null"],
+    capitals:      false,
+    macros:        false,
+    attribute:     Attribute::None,
+    variable:      None,
+    keys:          Keys::Colon,
+    tables:        false,
+    spaced:        false
+};
+
+const TOML: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comment: None,
+    nested:        false,
+    quotes:        &[
+        Quote { open: "\"\"\"", close: "\"\"\"", escapes: true,  lines: true  },
+        Quote { open: "'''",    close: "'''",    escapes: false, lines: true  },
+        Quote { open: "\"",     close: "\"",     escapes: true,  lines: false },
+        Quote { open: "'",      close: "'",      escapes: false, lines: false }
+    ],
+    prefixes:      &[],
+    chars:         false,
+    keywords:      &[],
+    types:         &[],
+    constants:     &["true", "false", "inf", "nan"],
+    capitals:      false,
+    macros:        false,
+    attribute:     Attribute::None,
+    variable:      None,
+    keys:          Keys::Equals,
+    tables:        true,
+    spaced:        false
+};
+
+// NOTE: for what comes after the key and the dashes, `markup::yaml` does the rest
+pub const YAML_VALUES: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comment: None,
+    nested:        false,
+    quotes:        &[
+        Quote { open: "\"", close: "\"", escapes: true,  lines: false },
+        Quote { open: "'",  close: "'",  escapes: false, lines: false }
+    ],
+    prefixes:      &[],
+    chars:         false,
+    keywords:      &[],
+    types:         &[],
+    constants:     &["true", "false", "null", "yes", "no", "on", "off"],
+    capitals:      false,
+    macros:        false,
+    attribute:     Attribute::None,
+    variable:      None,
+    keys:          Keys::None,
+    tables:        false,
+    spaced:        true
+};
@@ -0,0 +1,176 @@
+// mochou-p/text-editor/src/highlight/markup.rs
+
+//! the languages that go by where things are on a line more than by words
+
+use super::{Kind, State, Token};
+use super::code::{self, is_word, push, starts_with};
+use super::languages::YAML_VALUES;
+
+
+pub fn markdown(line: &[char], state: State, tokens: &mut Vec<Token>) -> State {
+    let indent = line.iter().take_while(|ch| ch.is_whitespace()).count();
+    let fence  = starts_with(line, indent, "```") || starts_with(line, indent, "~~~");
+
+    if state == State::Fence || fence {
+        push(tokens, 0..line.len(), Kind::Code);
+
+        // NOTE: a fence opens a block, or closes the open one
+        return if (state == State::Fence) == fence { State::Normal } else { State::Fence };
+    }
+
+    let Some(first) = line.get(indent) else { return State::Normal; };
+
+    let hashes = line[indent..].iter().take_while(|ch| **ch == '#').count();
+
+    if (1..=6).contains(&hashes) && line.get(indent + hashes).is_none_or(|ch| ch.is_whitespace()) {
+        push(tokens, 0..line.len(), Kind::Heading);
+        return State::Normal;
+    }
+
+    if *first == '>' {
+        push(tokens, indent..line.len(), Kind::Comment);
+        return State::Normal;
+    }
+
+    // NOTE: `---`, `***` or `___`, maybe spaced out
+    let rule = line[indent..].iter().filter(|ch| !ch.is_whitespace()).collect::<Vec<&char>>();
+
+    if rule.len() >= 3 && matches!(first, '-' | '*' | '_') && rule.iter().all(|ch| *ch == first) {
+        push(tokens, indent..line.len(), Kind::Keyword);
+        return State::Normal;
+    }
+
+    let digits = line[indent..].iter().take_while(|ch| ch.is_ascii_digit()).count();
+    let marker = if matches!(first, '-' | '*' | '+') {
+        1
+    } else if digits > 0 && matches!(line.get(indent + digits), Some('.' | ')')) {
+        digits + 1
+    } else {
+        0
+    };
+
+    let mut i = indent;
+
+    if marker > 0 && line.get(indent + marker).is_none_or(|ch| ch.is_whitespace()) {
+        push(tokens, indent..indent + marker, Kind::Keyword);
+        i += marker;
+    }
+
+    inline(line, i, tokens);
+
+    State::Normal
+}
+
+/// `code`, *emphasis*, **strong** and [links](url)
+fn inline(line: &[char], from: usize, tokens: &mut Vec<Token>) {
+    let mut i = from;
+
+    while i < line.len() {
+        let ch = line[i];
+
+        match ch {
+            '`' => {
+                let run   = line[i..].iter().take_while(|ch| **ch == '`').count();
+                let ticks = "`".repeat(run);
+
+                match (i + run..line.len()).find(|j| starts_with(line, *j, &ticks)) {
+                    Some(j) => {
+                        push(tokens, i..j + run, Kind::Code);
+                        i = j + run;
+                    },
+                    None => i += run
+                }
+            },
+            '*' | '_' if ch == '*' || i == 0 || !is_word(line[i - 1]) => {
+                let run   = if line.get(i + 1) == Some(&ch) { 2 } else { 1 };
+                let delim = ch.to_string().repeat(run);
+
+                // NOTE: not empty, and not closed right after a space
+                let close = (i + run + 1..line.len())
+                    .find(|j| starts_with(line, *j, &delim) && !line[j - 1].is_whitespace());
+
+                match close {
+                    Some(j) if !line[i + run].is_whitespace() => {
+                        push(tokens, i..j + run, Kind::Emphasis);
+                        i = j + run;
+                    },
+                    _ => i += run
+                }
+            },
+            '[' => {
+                let link = line[i..].iter().position(|ch| *ch == ']')
+                    .map(|j| i + j + 1)
+                    .filter(|j| line.get(*j) == Some(&'('))
+                    .and_then(|j| line[j..].iter().position(|ch| *ch == ')').map(|k| j + k + 1));
+
+                match link {
+                    Some(end) => {
+                        push(tokens, i..end, Kind::Link);
+                        i = end;
+                    },
+                    None => i += 1
+                }
+            },
+            _ => i += 1
+        }
+    }
+}
+
+pub fn yaml(line: &[char], _state: State, tokens: &mut Vec<Token>) -> State {
+    let mut i = line.iter().take_while(|ch| ch.is_whitespace()).count();
+
+    if i == 0 && (starts_with(line, 0, "---") || starts_with(line, 0, "...")) {
+        push(tokens, 0..3, Kind::Keyword);
+        i = 3;
+    }
+
+    // NOTE: `- ` items, maybe nested on one line
+    while line.get(i) == Some(&'-') && line.get(i + 1).is_none_or(|ch| ch.is_whitespace()) {
+        push(tokens, i..i + 1, Kind::Keyword);
+        i += 1 + line[i + 1..].iter().take_while(|ch| ch.is_whitespace()).count();
+    }
+
+    if let Some(colon) = key_end(line, i) {
+        push(tokens, i..colon, Kind::Key);
+        i = colon + 1 + line[colon + 1..].iter().take_while(|ch| ch.is_whitespace()).count();
+    }
+
+    match line.get(i) {
+        // NOTE: anchors, aliases and tags
+        Some('&' | '*' | '!') => {
+            let end = i + line[i..].iter().take_while(|ch| !ch.is_whitespace()).count();
+
+            push(tokens, i..end, Kind::Attribute);
+            i = end;
+        },
+        // NOTE: block scalars
+        Some('|' | '>') => {
+            push(tokens, i..i + 1, Kind::Keyword);
+            i += 1;
+        },
+        _ => ()
+    }
+
+    code::lex(&YAML_VALUES, line, i, State::Normal, tokens);
+
+    State::Normal
+}
+
+/// where the `:` after a key at `from` is, skipping quotes and stopping at comments
+fn key_end(line: &[char], from: usize) -> Option<usize> {
+    let mut quote = None;
+
+    for (i, ch) in line.iter().enumerate().skip(from) {
+        match (quote, ch) {
+            (Some(open), _) if *ch == open                                     => quote = None,
+            (Some(_),    _)                                                    => (),
+            (None, '"' | '\'') if i == from                                    => quote = Some(*ch),
+            (None, '#') if i == from || line[i - 1].is_whitespace()            => return None,
+            (None, '{' | '[')                                                  => return None,
+            (None, ':') if line.get(i + 1).is_none_or(|ch| ch.is_whitespace()) => return (i > from).then_some(i),
+            _                                                                  => ()
+        }
+    }
+
+    None
+}
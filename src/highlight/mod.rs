@@ -0,0 +1,158 @@
+// mochou-p/text-editor/src/highlight/mod.rs
+
+//! syntax highlighting, a line at a time: every line starts in the `State` the one
+//! before it ended in, those are kept, and an edit lexes again from its line only
+//! until a line ends the way it did before
+
+mod code;
+mod languages;
+mod markup;
+
+use std::ops::Range;
+use std::path::Path;
+use crate::Buffer;
+
+pub use languages::Language;
+
+
+/// what a token is, each one has a theme scope of the same name
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    Comment,
+    String,
+    Number,
+    Keyword,
+    Type,
+    Function,
+    Constant,
+    Attribute,
+    Variable,
+    Key,
+    Heading,
+    Emphasis,
+    Code,
+    Link
+}
+
+/// where a line leaves off, for the next one to pick up
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum State {
+    #[default]
+    Normal,
+    // NOTE: how deep, they nest in some languages
+    Comment(u8),
+    // NOTE: which of the language's quotes is open
+    String(u8),
+    // NOTE: a markdown code block
+    Fence
+}
+
+/// chars `range` of a line are a `kind`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Token {
+    pub range: Range<usize>,
+    pub kind:  Kind
+}
+
+#[derive(Default)]
+pub struct Highlighter {
+    language: Option<&'static Language>,
+    // NOTE: what every line starts in, as far as it has been lexed
+    states:   Vec<State>,
+    // NOTE: lines from the first one on were edited, and the ones up to the second one
+    //       are not worth comparing with, the rest keep what they had before
+    dirty:    Option<(usize, usize)>
+}
+
+impl Highlighter {
+    /// by the extension or the name of `path`, or by a `#!` in `first_line`
+    pub fn new(path: &Path, first_line: &str) -> Self {
+        Self {
+            language: Language::detect(path, first_line),
+            states:   vec![State::Normal],
+            dirty:    None
+        }
+    }
+
+    pub fn language(&self) -> Option<&'static Language> {
+        self.language
+    }
+
+    /// line `y` had `removed` lines after it replaced by `added` ones
+    pub fn edited(&mut self, y: usize, removed: usize, added: usize) {
+        if y + 1 >= self.states.len() {
+            self.states.truncate(y + 1);
+            return;
+        }
+
+        let end  = (y + 1 + removed).min(self.states.len());
+        let fill = self.states[y];
+
+        self.states.splice(y + 1..end, std::iter::repeat_n(fill, added));
+
+        // NOTE: where a line was before this edit, the replaced ones going to its last new line
+        let shift = |line: usize| if line > y + removed { line + added - removed } else { line.min(y + added) };
+
+        // NOTE: the lines after an earlier `from` were never lexed from it,
+        //       so they are not worth comparing with either
+        self.dirty = Some(match self.dirty {
+            Some((from, until)) => (from.min(y), shift(from).max(shift(until)).max(y + added)),
+            None                => (y, y + added)
+        });
+    }
+
+    /// the tokens of line `y`, lexing whatever it takes to know how it starts
+    pub fn tokens(&mut self, buffer: &Buffer, y: usize) -> Vec<Token> {
+        let mut tokens = Vec::new();
+
+        let Some(language) = self.language else { return tokens; };
+
+        self.update(language, buffer, y);
+
+        let line = buffer.line(y as isize).as_str().chars().collect::<Vec<char>>();
+        language.lex(&line, self.states[y], &mut tokens);
+
+        tokens
+    }
+
+    // NOTE: only as far as `y`, the rest waits until it is needed
+    fn update(&mut self, language: &Language, buffer: &Buffer, y: usize) {
+        let mut scratch = Vec::new();
+        let mut lex     = |i: usize, state: State| {
+            let line = buffer.line(i as isize).as_str().chars().collect::<Vec<char>>();
+
+            scratch.clear();
+            language.lex(&line, state, &mut scratch)
+        };
+
+        if let Some((from, until)) = self.dirty.take() {
+            let mut i = from;
+
+            while i + 1 < self.states.len() {
+                if i >= y {
+                    self.dirty = Some((i, until));
+                    break;
+                }
+
+                let next = lex(i, self.states[i]);
+
+                // NOTE: converged, the rest start the way they did
+                if i >= until && self.states[i + 1] == next {
+                    break;
+                }
+
+                self.states[i + 1] = next;
+                i += 1;
+            }
+        }
+
+        let count = buffer.line_count() as usize;
+
+        self.states.truncate(count);
+
+        while self.states.len() <= y.min(count - 1) {
+            let i = self.states.len() - 1;
+            self.states.push(lex(i, self.states[i]));
+        }
+    }
+}
@@ -14,6 +14,12 @@ pub struct InsertSet<K: Hash + Eq + Clone> {
     set:     HashSet<K>
 }
 
+impl<K: Hash + Eq + Clone> Default for InsertSet<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<K: Hash + Eq + Clone> InsertSet<K> {
     pub fn new() -> Self {
         Self { indices: Vec::new(), set: HashSet::new() }
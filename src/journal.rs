@@ -0,0 +1,176 @@
+// mochou-p/text-editor/src/journal.rs
+
+//! copies of unsaved files, kept on disk to get them back after a crash
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::ffi::OsStr;
+use std::fs::{DirBuilder, OpenOptions};
+use std::hash::{Hash as _, Hasher as _};
+use std::io::Write as _;
+use std::os::unix::ffi::OsStrExt as _;
+use std::os::unix::fs::{DirBuilderExt as _, OpenOptionsExt as _};
+use std::path::{Path, PathBuf};
+use crate::utils::xdg;
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    // NOTE: the lines of an `Editing` buffer, joined by '\n'
+    Text,
+    // NOTE: the bytes of a `Hex` buffer
+    Bytes
+}
+
+/// an unsaved file left behind by an editor that is gone
+pub struct Entry {
+    pub kind:    Kind,
+    pub path:    PathBuf,
+    pub content: Vec<u8>,
+    // NOTE: the journal file itself
+    file:        PathBuf
+}
+
+/// writes unsaved files out as they change, removes them once saved or thrown away
+pub struct Journal {
+    dir:     Option<PathBuf>,
+    // NOTE: how many edits in every file journaled was when written
+    written: HashMap<PathBuf, u64>
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Text  => "text",
+            Self::Bytes => "bytes"
+        }
+    }
+}
+
+impl Entry {
+    /// removes it from the journal, for good
+    pub fn discard(&self) {
+        let _ = std::fs::remove_file(&self.file);
+    }
+}
+
+impl Journal {
+    pub fn new() -> Self {
+        Self {
+            dir:     xdg::state_dir().map(|dir| dir.join("recovery")),
+            written: HashMap::new()
+        }
+    }
+
+    /// how many edits in every file journaled was, the ones still there need no content
+    pub fn written(&self) -> &HashMap<PathBuf, u64> {
+        &self.written
+    }
+
+    /// brings the journal up to date with `unsaved`, best effort,
+    /// files without content are left as they were written
+    pub fn write(&mut self, unsaved: Vec<(Kind, PathBuf, u64, Option<Vec<u8>>)>) {
+        let Some(dir) = self.dir.as_ref() else { return; };
+
+        // NOTE: private, the buffers can hold anything
+        if !unsaved.is_empty() && DirBuilder::new().recursive(true).mode(0o700).create(dir).is_err() {
+            return;
+        }
+
+        let mut stale = self.written.keys().cloned().collect::<Vec<PathBuf>>();
+
+        for (kind, path, edits, content) in unsaved {
+            stale.retain(|other| *other != path);
+
+            let Some(content) = content else { continue; };
+
+            let mut bytes = format!("{}\n", kind.name()).into_bytes();
+            bytes.extend_from_slice(path.as_os_str().as_bytes());
+            bytes.push(b'\n');
+            bytes.extend_from_slice(&content);
+
+            if Self::write_file(&Self::file(dir, &path), &bytes).is_ok() {
+                self.written.insert(path, edits);
+            }
+        }
+
+        for path in stale {
+            let _ = std::fs::remove_file(Self::file(dir, &path));
+            self.written.remove(&path);
+        }
+    }
+
+    /// nothing is left unsaved that should be kept
+    pub fn clear(&mut self) {
+        self.write(Vec::new());
+    }
+
+    /// entries of editors that are no longer running
+    pub fn recovered(&self) -> Vec<Entry> {
+        let Some(dir)    = self.dir.as_ref()      else { return Vec::new(); };
+        let Ok(read_dir) = std::fs::read_dir(dir) else { return Vec::new(); };
+
+        let mut entries = read_dir
+            .filter_map(Result::ok)
+            .filter(|entry| Self::owner(&entry.path()).is_some_and(|pid| !alive(pid)))
+            .filter_map(|entry| Self::parse(entry.path()))
+            .collect::<Vec<Entry>>();
+
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        entries
+    }
+
+    // NOTE: named after the path and the process, so editors running side by side keep apart
+    fn file(dir: &Path, path: &Path) -> PathBuf {
+        dir.join(format!("{:016x}-{}", hash(path.as_os_str().as_bytes()), std::process::id()))
+    }
+
+    // NOTE: through a temporary file so a crash halfway leaves the last one whole,
+    //       only the user gets to read either
+    fn write_file(file: &Path, bytes: &[u8]) -> std::io::Result<()> {
+        let temp = file.with_extension("tmp");
+
+        let _ = std::fs::remove_file(&temp);
+
+        let mut handle = OpenOptions::new().write(true).create_new(true).mode(0o600).open(&temp)?;
+        handle.write_all(bytes)?;
+        handle.sync_all()?;
+
+        std::fs::rename(&temp, file)
+    }
+
+    fn owner(file: &Path) -> Option<i32> {
+        file.file_name()?.to_str()?.rsplit_once('-')?.1.parse().ok()
+    }
+
+    fn parse(file: PathBuf) -> Option<Entry> {
+        let bytes = std::fs::read(&file).ok()?;
+
+        let mut parts = bytes.splitn(3, |byte| *byte == b'\n');
+
+        let kind = match parts.next()? {
+            b"text"  => Kind::Text,
+            b"bytes" => Kind::Bytes,
+            _        => return None
+        };
+
+        let path    = PathBuf::from(OsStr::from_bytes(parts.next()?));
+        let content = parts.next()?.to_vec();
+
+        Some(Entry { kind, path, content, file })
+    }
+}
+
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// whether a process with `pid` is still around
+// NOTE: not allowed to signal it still means it is there
+fn alive(pid: i32) -> bool {
+    pid == std::process::id() as i32
+        || unsafe { libc::kill(pid, 0) == 0 }
+        || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
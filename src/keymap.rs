@@ -0,0 +1,441 @@
+// mochou-p/text-editor/src/keymap.rs
+
+//! which keys run which commands, the defaults from `COMMANDS`,
+//! changed by `$XDG_CONFIG_HOME/text-editor/keymap`, one binding per line:
+//!
+//! ```text
+//! # a comment
+//! Ctrl+W        = file.save
+//! Ctrl+K Ctrl+U = history.undo
+//! Ctrl+S        = none
+//! ```
+//!
+//! a chord is strokes split by spaces, a stroke is modifiers and a key joined by `+`,
+//! `none` takes a binding away, one from the file replaces the defaults it clashes with
+
+use std::path::Path;
+use termion::event::{Event, Key};
+use crate::command::{Command, COMMANDS};
+use crate::utils::{self, xdg};
+
+
+/// where bindings apply, every command belongs to one
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    // NOTE: from any view but a prompt, before the view sees the key
+    Global,
+    Editing,
+    Browsing,
+    Hex
+}
+
+/// one key press, as it arrives
+#[derive(Clone, PartialEq, Eq)]
+pub enum Stroke {
+    Key(Key),
+    // NOTE: modified keys termion does not parse
+    Raw(Vec<u8>)
+}
+
+pub enum Lookup {
+    Run(&'static Command),
+    // NOTE: the start of a chord, as far as it got
+    Pending(String),
+    // NOTE: a chord that went nowhere, as far as it was bound
+    Unbound(String),
+    // NOTE: not a binding, the view gets the event
+    Pass
+}
+
+#[derive(Default)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+    // NOTE: strokes of an unfinished chord
+    pending:  Vec<Stroke>
+}
+
+struct Binding {
+    keys:    Vec<Stroke>,
+    // NOTE: the way a user would write it
+    label:   String,
+    command: &'static Command,
+    // NOTE: in the user file, `None` if built in
+    line:    Option<usize>
+}
+
+impl Stroke {
+    fn of(event: &Event) -> Option<Self> {
+        match event {
+            Event::Key(key)                                                => Some(Self::Key(*key)),
+            Event::Unsupported(bytes) if utils::alt_click(bytes).is_none() => Some(Self::Raw(bytes.clone())),
+            _                                                              => None
+        }
+    }
+
+    /// `text` like `Ctrl+Shift+Left`, with the label it gets shown as
+    fn parse(text: &str) -> Result<(Self, String), String> {
+        let (modifiers, name) = match text.strip_suffix("++") {
+            Some(modifiers)      => (modifiers, "+"),
+            None if text == "+"  => ("", "+"),
+            None                 => text.rsplit_once('+').unwrap_or(("", text))
+        };
+
+        let (mut ctrl, mut alt, mut shift) = (false, false, false);
+
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            let flag = match modifier.to_ascii_lowercase().as_str() {
+                "ctrl"  => &mut ctrl,
+                "alt"   => &mut alt,
+                "shift" => &mut shift,
+                _       => return Err(format!("unknown modifier `{modifier}` in `{text}`"))
+            };
+
+            if *flag {
+                return Err(format!("`{modifier}` is in `{text}` twice"));
+            }
+
+            *flag = true;
+        }
+
+        let unsupported = || Err(format!("terminals do not tell `{text}` apart from other keys"));
+
+        // NOTE: xterm style, `ESC [ 1 ; m X`, where m - 1 has a bit per modifier
+        let m = 1 + shift as u8 + 2 * alt as u8 + 4 * ctrl as u8;
+
+        let (stroke, name) = match name.to_ascii_lowercase().as_str() {
+            "enter" | "return" => (match (ctrl, alt, shift) {
+                (false, false, false) => Key::Char('\n'),
+                (false, true,  false) => Key::Alt('\r'),
+                _                     => return unsupported()
+            }.into(), "Enter"),
+            "tab" => (match (ctrl, alt, shift) {
+                (false, false, false) => Key::Char('\t'),
+                (false, false, true ) => Key::BackTab,
+                (false, true,  false) => Key::Alt('\t'),
+                _                     => return unsupported()
+            }.into(), "Tab"),
+            "space" => (match (ctrl, alt, shift) {
+                (false, false, false) => Key::Char(' '),
+                (true,  false, false) => Key::Null,
+                (false, true,  false) => Key::Alt(' '),
+                _                     => return unsupported()
+            }.into(), "Space"),
+            "backspace" => (match (ctrl, alt, shift) {
+                (false, false, false) => Key::Backspace,
+                // NOTE: most terminals send ^H for it
+                (true,  false, false) => Key::Ctrl('h'),
+                (false, true,  false) => Key::Alt('\x7F'),
+                _                     => return unsupported()
+            }.into(), "Backspace"),
+            "esc" | "escape" => {
+                if m != 1 {
+                    return unsupported();
+                }
+
+                (Key::Esc.into(), "Esc")
+            },
+            "up" | "down" | "right" | "left" | "home" | "end" => {
+                let (final_byte, name) = match name.to_ascii_lowercase().as_str() {
+                    "up"    => (b'A', "Up"),
+                    "down"  => (b'B', "Down"),
+                    "right" => (b'C', "Right"),
+                    "left"  => (b'D', "Left"),
+                    "home"  => (b'H', "Home"),
+                    _       => (b'F', "End")
+                };
+
+                let key = match (m, name) {
+                    (1, "Up")    => Some(Key::Up),
+                    (1, "Down")  => Some(Key::Down),
+                    (1, "Right") => Some(Key::Right),
+                    (1, "Left")  => Some(Key::Left),
+                    (1, "Home")  => Some(Key::Home),
+                    (1, "End")   => Some(Key::End),
+                    (2, "Up")    => Some(Key::ShiftUp),
+                    (2, "Down")  => Some(Key::ShiftDown),
+                    (2, "Right") => Some(Key::ShiftRight),
+                    (2, "Left")  => Some(Key::ShiftLeft),
+                    (3, "Up")    => Some(Key::AltUp),
+                    (3, "Down")  => Some(Key::AltDown),
+                    (3, "Right") => Some(Key::AltRight),
+                    (3, "Left")  => Some(Key::AltLeft),
+                    (5, "Up")    => Some(Key::CtrlUp),
+                    (5, "Down")  => Some(Key::CtrlDown),
+                    (5, "Right") => Some(Key::CtrlRight),
+                    (5, "Left")  => Some(Key::CtrlLeft),
+                    (5, "Home")  => Some(Key::CtrlHome),
+                    (5, "End")   => Some(Key::CtrlEnd),
+                    _            => None
+                };
+
+                let stroke = key.map_or_else(
+                    || Self::Raw([b"\x1B[1;".as_slice(), m.to_string().as_bytes(), &[final_byte]].concat()),
+                    Self::Key
+                );
+
+                (stroke, name)
+            },
+            "insert" | "delete" | "pageup" | "pagedown" => {
+                let (number, key, name) = match name.to_ascii_lowercase().as_str() {
+                    "insert"   => (2, Key::Insert,   "Insert"),
+                    "delete"   => (3, Key::Delete,   "Delete"),
+                    "pageup"   => (5, Key::PageUp,   "PageUp"),
+                    _          => (6, Key::PageDown, "PageDown")
+                };
+
+                let stroke = if m == 1 { Self::Key(key) } else { Self::Raw(format!("\x1B[{number};{m}~").into_bytes()) };
+
+                (stroke, name)
+            },
+            lower if lower.starts_with('f') && matches!(lower[1..].parse::<u8>(), Ok(1..=12)) => {
+                // NOTE: every terminal says these its own way
+                if m != 1 {
+                    return unsupported();
+                }
+
+                let n = lower[1..].parse::<u8>().unwrap();
+
+                return Ok((Key::F(n).into(), format!("F{n}")));
+            },
+            _ => {
+                let mut chars = name.chars();
+                let (Some(ch), None) = (chars.next(), chars.next()) else {
+                    return Err(format!("unknown key `{name}` in `{text}`"));
+                };
+
+                let ch = match (shift, ch.is_ascii_alphabetic()) {
+                    (true,  true ) => ch.to_ascii_uppercase(),
+                    (true,  false) => return Err(format!("`{text}` is just `{ch}` with the shifted symbol written out")),
+                    (false, _    ) => ch
+                };
+
+                let key = match (ctrl, alt) {
+                    (false, false)                                => Key::Char(ch),
+                    (false, true )                                => Key::Alt(ch),
+                    // NOTE: ^I is tab, ^J and ^M are enter
+                    _ if !ch.is_ascii_alphabetic() || shift
+                        || matches!(ch.to_ascii_lowercase(), 'i' | 'j' | 'm') => return unsupported(),
+                    (true,  false)                                => Key::Ctrl(ch.to_ascii_lowercase()),
+                    (true,  true )                                => Key::Alt((ch.to_ascii_lowercase() as u8 - b'a' + 1) as char)
+                };
+
+                let name = if ctrl || alt { ch.to_ascii_uppercase() } else { ch }.to_string();
+
+                let label = [ctrl.then_some("Ctrl"), alt.then_some("Alt"), (shift && (ctrl || alt)).then_some("Shift")]
+                    .into_iter()
+                    .flatten()
+                    .chain([name.as_str()])
+                    .collect::<Vec<&str>>()
+                    .join("+");
+
+                return Ok((key.into(), label));
+            }
+        };
+
+        let label = [ctrl.then_some("Ctrl"), alt.then_some("Alt"), shift.then_some("Shift"), Some(name)]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<&str>>()
+            .join("+");
+
+        Ok((stroke, label))
+    }
+}
+
+impl From<Key> for Stroke {
+    fn from(key: Key) -> Self {
+        Self::Key(key)
+    }
+}
+
+impl Binding {
+    fn new(text: &str, command: &'static Command, line: Option<usize>) -> Result<Self, String> {
+        let (keys, labels) = parse_keys(text)?;
+
+        Ok(Self { keys, label: labels.join(" "), command, line })
+    }
+
+    fn context(&self) -> Context {
+        self.command.run.context()
+    }
+
+    /// shares a key press with `other` where both apply, so one of them could never run
+    fn clashes(&self, other: &Self) -> bool {
+        let contexts = self.context() == other.context() || self.context() == Context::Global || other.context() == Context::Global;
+
+        contexts && (self.keys.starts_with(&other.keys) || other.keys.starts_with(&self.keys))
+    }
+
+    /// the first `strokes` of the label
+    fn label_to(&self, strokes: usize) -> String {
+        self.label.split(' ').take(strokes).collect::<Vec<&str>>().join(" ")
+    }
+
+    fn origin(&self) -> String {
+        self.line.map_or_else(|| String::from("by default"), |line| format!("on line {line}"))
+    }
+}
+
+impl Keymap {
+    /// the defaults, changed by the user file, with what was wrong in either
+    pub fn load() -> (Self, Vec<String>) {
+        let mut keymap = Self::default();
+        let mut errors = Vec::new();
+
+        for command in COMMANDS {
+            for keys in command.keys {
+                if let Err(error) = Binding::new(keys, command, None).and_then(|binding| keymap.add(binding)) {
+                    errors.push(format!("{}: {error}", command.id));
+                }
+            }
+        }
+
+        if let Some(path) = xdg::config_dir().map(|dir| dir.join("keymap")) {
+            keymap.load_file(&path, &mut errors);
+        }
+
+        (keymap, errors)
+    }
+
+    fn load_file(&mut self, path: &Path, errors: &mut Vec<String>) {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text)                                                    => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return,
+            Err(error)                                                  => {
+                errors.push(format!("keymap: {error}"));
+                return;
+            }
+        };
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Err(error) = self.load_line(line, i + 1) {
+                errors.push(format!("keymap:{}: {error}", i + 1));
+            }
+        }
+    }
+
+    fn load_line(&mut self, line: &str, number: usize) -> Result<(), String> {
+        // NOTE: the last `=`, so `=` itself can be bound
+        let Some((keys, id)) = line.rsplit_once('=') else {
+            return Err(String::from("expected `keys = command`"));
+        };
+
+        let (keys, id) = (keys.trim(), id.trim());
+
+        if id == "none" {
+            let (strokes, labels) = parse_keys(keys)?;
+            let count             = self.bindings.len();
+
+            self.bindings.retain(|binding| binding.keys != strokes);
+
+            if self.bindings.len() == count {
+                return Err(format!("`{}` is not bound to anything", labels.join(" ")));
+            }
+
+            return Ok(());
+        }
+
+        let Some(command) = Command::find(id) else {
+            return Err(format!("unknown command `{id}`"));
+        };
+
+        self.add(Binding::new(keys, command, Some(number))?)
+    }
+
+    /// refuses `binding` if it clashes with one from the same place, drops any other it clashes with
+    fn add(&mut self, binding: Binding) -> Result<(), String> {
+        let same_place = |other: &&Binding| other.line.is_some() == binding.line.is_some();
+
+        if let Some(other) = self.bindings.iter().filter(same_place).find(|other| other.clashes(&binding)) {
+            return Err(if other.keys == binding.keys {
+                format!("`{}` is already bound to {} {}", binding.label, other.command.id, other.origin())
+            } else {
+                format!("`{}` clashes with `{}`, bound to {} {}", binding.label, other.label, other.command.id, other.origin())
+            });
+        }
+
+        self.bindings.retain(|other| !other.clashes(&binding));
+        self.bindings.push(binding);
+
+        Ok(())
+    }
+
+    /// takes the next key press, for the bindings in `contexts`
+    pub fn feed(&mut self, contexts: &[Context], event: &Event) -> Lookup {
+        let Some(stroke) = Stroke::of(event) else {
+            self.pending.clear();
+            return Lookup::Pass;
+        };
+
+        self.pending.push(stroke);
+
+        let mut longer = None;
+
+        for binding in self.bindings.iter().filter(|binding| contexts.contains(&binding.context())) {
+            if binding.keys == self.pending {
+                self.pending.clear();
+                return Lookup::Run(binding.command);
+            }
+
+            if longer.is_none() && binding.keys.starts_with(&self.pending) {
+                longer = Some(binding);
+            }
+        }
+
+        if let Some(binding) = longer {
+            return Lookup::Pending(binding.label_to(self.pending.len()));
+        }
+
+        let pending = std::mem::take(&mut self.pending);
+
+        if pending.len() == 1 {
+            return Lookup::Pass;
+        }
+
+        // NOTE: it was pending a stroke ago, so something starts like that
+        let label = self.bindings
+            .iter()
+            .find(|binding| binding.keys.starts_with(&pending[..pending.len() - 1]))
+            .map_or_else(String::new, |binding| binding.label_to(pending.len() - 1));
+
+        Lookup::Unbound(label)
+    }
+
+    /// the single key press binding for `event` in `context`, ignoring chords
+    pub fn find(&self, context: Context, event: &Event) -> Option<&'static Command> {
+        let stroke = Stroke::of(event)?;
+
+        self.bindings
+            .iter()
+            .find(|binding| binding.context() == context && binding.keys == [stroke.clone()])
+            .map(|binding| binding.command)
+    }
+
+    /// how to run `command`, preferring the user file
+    pub fn label(&self, command: &Command) -> Option<&str> {
+        self.bindings
+            .iter()
+            .filter(|binding| binding.command.id == command.id)
+            .min_by_key(|binding| binding.line.is_none())
+            .map(|binding| binding.label.as_str())
+    }
+}
+
+/// `text` like `Ctrl+K Ctrl+C`, with a label for every stroke
+fn parse_keys(text: &str) -> Result<(Vec<Stroke>, Vec<String>), String> {
+    if text.is_empty() {
+        return Err(String::from("no keys given"));
+    }
+
+    text
+        .split_whitespace()
+        .map(Stroke::parse)
+        .collect::<Result<Vec<(Stroke, String)>, String>>()
+        .map(|strokes| strokes.into_iter().unzip())
+}
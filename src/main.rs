@@ -1,31 +1,70 @@
 // mochou-p/text-editor/src/main.rs
 
+mod args;
+mod buffer;
+mod clipboard;
+mod command;
 mod config;
+mod highlight;
 mod insert_set;
 mod ivec2;
+mod journal;
+mod keymap;
+mod message;
 mod utils;
 mod view;
+mod watcher;
 
 use std::collections::HashMap;
 use std::io::{self, Stdout, Write as _};
-use std::panic::{set_hook, take_hook, catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::panic::{set_hook, take_hook, catch_unwind, resume_unwind, AssertUnwindSafe};
 use std::sync::OnceLock;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
 use termion::event::{Event, MouseEvent, MouseButton};
 use termion::input::{MouseTerminal, TermRead as _};
 use termion::raw::{RawTerminal, IntoRawMode as _};
 use betterm::{clear, color, cursor, screen};
-use config::Theme;
-use view::{View, Browsing, Editing, Files};
+use args::Args;
+use clipboard::Registers;
+use command::{Command, Run};
+use config::{Config, PreferenceMask, Theme};
+use journal::{Journal, Kind};
+use keymap::{Context, Keymap, Lookup};
+use message::Message;
+use view::{View, Browsing, Conflict, Editing, Exiting, Files, Hex, Palette, Searching, Status, Themes};
+use watcher::Watcher;
 
-pub use {insert_set::InsertSet, ivec2::Ivec2};
+pub use {buffer::Buffer, insert_set::InsertSet, ivec2::Ivec2};
 
 
 static PANIC_LOCATION: OnceLock<String> = OnceLock::new();
 static PANIC_PAYLOAD:  OnceLock<String> = OnceLock::new();
 
+// NOTE: how often messages expire and unsaved files get journaled
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
 fn main() {
+    // NOTE: before the terminal goes raw
+    let args = match args::parse(std::env::args_os().skip(1)) {
+        Ok(args::Command::Run(args)) => args,
+        Ok(args::Command::Help)      => {
+            println!("{}", args::USAGE);
+            return;
+        },
+        Ok(args::Command::Version)   => {
+            println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+            return;
+        },
+        Err(error)                   => {
+            eprintln!("{}: {error}\n\n{}", env!("CARGO_PKG_NAME"), args::USAGE);
+            std::process::exit(2);
+        }
+    };
+
     let was_ok = {
-        Editor::new().run()
+        Editor::new().run(&args)
     };
 
     if !was_ok {
@@ -40,36 +79,61 @@ fn main() {
     }
 }
 
-struct Editor {
-    exit:   bool,
-    stdout: MouseTerminal<RawTerminal<Stdout>>,
-    theme:  Theme,
-    cursor: Option<(isize, isize)>,
-    view:   String,
-    views:  HashMap<String, Box<dyn View>>
+/// what wakes up the main loop
+enum Signal {
+    Input  (Event),
+    // NOTE: something happened next to an open file
+    Changed(PathBuf),
+    // NOTE: for background work that has something new to show
+    Redraw,
+    // NOTE: time to journal unsaved files and expire the message
+    Tick
 }
 
-#[derive(Default, Clone)]
-struct Cursor {
-    last_x: isize,
-    x:      isize,
-    y:      isize
+struct Editor {
+    exit:        bool,
+    stdout:      MouseTerminal<RawTerminal<Stdout>>,
+    theme:       Theme,
+    preferences: PreferenceMask,
+    tab_width:   usize,
+    cursor:      Option<(isize, isize)>,
+    registers:   Registers,
+    keymap:      Keymap,
+    message:     Option<Message>,
+    watcher:     Option<Watcher>,
+    journal:     Journal,
+    sender:      Sender<Signal>,
+    receiver:    Receiver<Signal>,
+    view:        String,
+    views:       HashMap<String, Box<dyn View>>
 }
 
 impl Editor {
     fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let config             = Config::default();
+
         Self {
-            exit:   false,
+            exit:        false,
             // NOTE: lock?
-            stdout: MouseTerminal::from(io::stdout().into_raw_mode().unwrap()),
-            theme:  Theme::default(),
-            cursor: None,
-            view:   Browsing::name(),
-            views:  HashMap::new()
+            stdout:      MouseTerminal::from(io::stdout().into_raw_mode().unwrap()),
+            theme:       config.theme,
+            preferences: config.preferences,
+            tab_width:   config.tab_width,
+            cursor:      None,
+            registers:   Registers::default(),
+            keymap:      Keymap::default(),
+            message:     None,
+            watcher:     Watcher::new(sender.clone()),
+            journal:     Journal::new(),
+            sender,
+            receiver,
+            view:        Browsing::name(),
+            views:       HashMap::new()
         }
     }
 
-    fn initialise(&mut self) {
+    fn initialise(&mut self, args: &Args) {
         write!(
             self.stdout,
             "{}{}{}",
@@ -81,11 +145,89 @@ impl Editor {
         let editing = Editing::new();
         self.views.insert(Editing::name(), Box::new(editing));
 
-        let browsing = Browsing::new(self);
+        let hex = Hex::new();
+        self.views.insert(Hex::name(), Box::new(hex));
+
+        let status = Status::new(self);
+        self.views.insert(Status::name(), Box::new(status));
+
+        let browsing = Browsing::new(self, args.dir.clone());
         self.views.insert(Browsing::name(), Box::new(browsing));
 
         let files = Files::new(self);
         self.views.insert(Files::name(), Box::new(files));
+
+        let searching = Searching::new(self);
+        self.views.insert(Searching::name(), Box::new(searching));
+
+        let exiting = Exiting::new();
+        self.views.insert(Exiting::name(), Box::new(exiting));
+
+        let conflict = Conflict::new();
+        self.views.insert(Conflict::name(), Box::new(conflict));
+
+        let palette = Palette::new();
+        self.views.insert(Palette::name(), Box::new(palette));
+
+        let themes = Themes::new();
+        self.views.insert(Themes::name(), Box::new(themes));
+
+        for (path, position) in &args.files {
+            self.view::<Editing, ()>(|editor, view| view.open_from_args(editor, path.clone(), *position, args.readonly));
+        }
+
+        if !args.files.is_empty() {
+            let hex   = self.view::<Hex, bool>(|_, view| view.is_shown());
+            self.view = if hex { Hex::name() } else { Editing::name() };
+        }
+
+        for entry in self.journal.recovered() {
+            self.view::<Conflict, ()>(|editor, view| view.recover(editor, entry));
+        }
+
+        // NOTE: last, so opening the files does not hide what was wrong
+        if let Some(message) = Message::errors(&self.load_config()) {
+            self.message = Some(message);
+        }
+    }
+
+    /// the config files and the keymap, over what was there, with what was wrong in them
+    fn load_config(&mut self) -> Vec<String> {
+        let root                    = self.view::<Browsing, _>(|_, view| view.root().to_path_buf());
+        let (config, mut errors)    = Config::load(&root);
+        let (keymap, keymap_errors) = Keymap::load();
+
+        self.theme       = config.theme;
+        self.preferences = config.preferences;
+        self.tab_width   = config.tab_width;
+        self.keymap      = keymap;
+
+        errors.extend(keymap_errors);
+        errors
+    }
+
+    fn reload_config(&mut self) {
+        let errors = self.load_config();
+
+        self.message = Some(Message::errors(&errors).unwrap_or_else(|| Message::info("reloaded the config")));
+    }
+
+    /// keeps `name` in the user config, then loads it all again for the colours over it
+    fn save_theme(&mut self, name: &str) {
+        if let Err(error) = Config::save_theme(name) {
+            self.message = Some(Message::error(error));
+            return;
+        }
+
+        let errors = self.load_config();
+
+        self.message = Some(Message::errors(&errors).unwrap_or_else(|| {
+            if self.theme.name == name {
+                Message::info(format!("saved the theme `{name}` to the config"))
+            } else {
+                Message::warning(format!("saved the theme `{name}`, but the project config picks `{}`", self.theme.name))
+            }
+        }));
     }
 
     fn shutdown(&mut self) {
@@ -100,7 +242,7 @@ impl Editor {
         self.stdout.flush().unwrap();
     }
 
-    fn run(mut self) -> bool {
+    fn run(mut self, args: &Args) -> bool {
         set_hook(Box::new(|panic_info| {
             if let Some(location) = panic_info.location() {
                 let _ = PANIC_LOCATION.set(
@@ -127,10 +269,17 @@ impl Editor {
         }));
 
         let result = catch_unwind(AssertUnwindSafe(|| {
-            self.initialise();
+            self.initialise(args);
             self.inner_run();
         }));
 
+        // NOTE: one last time after a crash, best effort, the state may well be broken
+        if result.is_ok() {
+            self.journal.clear();
+        } else {
+            let _ = catch_unwind(AssertUnwindSafe(|| self.write_journal()));
+        }
+
         let _ = take_hook();
         self.shutdown();
 
@@ -138,57 +287,93 @@ impl Editor {
     }
 
     fn try_update_focus(&mut self, event: &mut Event) {
+        // NOTE: only focuses, the view maps the coordinates itself
+        if let Event::Unsupported(bytes) = event && let Some((x, y)) = utils::alt_click(bytes) {
+            let mut click = Event::Mouse(MouseEvent::Press(MouseButton::Left, x, y));
+            self.try_update_focus(&mut click);
+            return;
+        }
+
         let Event::Mouse(MouseEvent::Press(MouseButton::Left, x, y)) = event else {
             return;
         };
 
-        *x -= 1;
-        *y -= 1;
+        if self.views[&self.view].is_modal() {
+            return;
+        }
 
-        for name in self.views.keys() {
-            let view = &self.views[name];
+        if let Some((name, view_x, view_y)) = view_at(&self.views, *x - 1, *y - 1) {
+            *x        = view_x;
+            *y        = view_y;
+            self.view = name;
+        }
+    }
 
-            let xy1 = view.position();
-            let xy2 = xy1 + view.size() - Ivec2::ONE;
+    fn handle_event(&mut self, event: Event) {
+        let name = self.view.clone();
 
-            if *x >= xy1.x as u16 && *y >= xy1.y as u16 && *x <= xy2.x as u16 && *y <= xy2.y as u16 {
-                *x        -= xy1.x as u16;
-                *y        -= xy1.y as u16;
-                self.view  = name.clone();
-                return;
-            }
+        self.with_view(&name, |editor, view| view.handle_event(editor, event));
+    }
+
+    fn run_command(&mut self, command: &'static Command) {
+        match command.run {
+            Run::Global  (run) => { run(self); },
+            Run::Editing (_  ) => { self.view::<Editing,  ()>(|editor, view| view.run_command(editor, command)); },
+            Run::Browsing(run) => { self.view::<Browsing, ()>(|editor, view| run(view, editor)); },
+            Run::Hex     (run) => { self.view::<Hex,      ()>(|editor, view| run(view, editor)); }
         }
     }
 
-    fn handle_event(&mut self, event: Event) {
-        let     name = self.view.clone();
-        let mut view = self.views.remove(&name).unwrap();
+    /// runs `f` on a view taken out of `views`, putting it back even if `f` panics,
+    /// so the journal still gets to see it
+    fn with_view<R>(&mut self, name: &str, f: impl FnOnce(&mut Self, &mut Box<dyn View>) -> R) -> R {
+        let mut view = self.views.remove(name).unwrap();
 
-        view.handle_event(self, event);
+        let result = catch_unwind(AssertUnwindSafe(|| f(self, &mut view)));
 
-        self.views.insert(name, view);
+        self.views.insert(String::from(name), view);
+
+        result.unwrap_or_else(|payload| resume_unwind(payload))
     }
 
-    fn reprint_views(&mut self, keys: &[String], buffer: &mut String) {
-        for key in keys {
-            let mut view = self.views.remove(key).unwrap();
-
-            for i in 0..view.size().y {
-                buffer.clear();
-                view.print_line(self, buffer, i as usize, (i + view.scroll().y) as usize);
-
-                // TODO: cut printed width of String to size.x here somehow (+background fill)
-                write!(
-                    self.stdout,
-                    "{}{buffer}",
-                    cursor::MoveToColumnAndRow(
-                        (view.position().x + 1)     as u16,
-                        (view.position().y + 1 + i) as u16
-                    )
-                ).unwrap();
-            }
+    // NOTE: only files edited since the last time get their content built and written
+    fn write_journal(&mut self) {
+        let written = self.journal.written().clone();
+
+        let editing = self.view::<Editing, _>(|_, view| view.unsaved(&written));
+        let hex     = self.view::<Hex,     _>(|_, view| view.unsaved(&written));
+
+        let unsaved = editing
+            .into_iter()
+            .map(|(path, edits, content)| (Kind::Text, path, edits, content))
+            .chain(hex.into_iter().map(|(path, edits, content)| (Kind::Bytes, path, edits, content)))
+            .collect();
+
+        self.journal.write(unsaved);
+    }
 
-            self.views.insert(String::from(key), view);
+    fn reprint_views(&mut self, keys: &[String], buffer: &mut String) {
+        // NOTE: the focused view goes last, so its cursor wins
+        let focused = keys.iter().filter(|key| **key == self.view);
+        let keys    = keys.iter().filter(|key| **key != self.view).chain(focused).cloned().collect::<Vec<String>>();
+
+        for key in &keys {
+            self.with_view(key, |editor, view| {
+                for i in 0..view.size().y {
+                    buffer.clear();
+                    view.print_line(editor, buffer, i as usize, (i + view.scroll().y) as usize);
+
+                    // TODO: cut printed width of String to size.x here somehow (+background fill)
+                    write!(
+                        editor.stdout,
+                        "{}{buffer}",
+                        cursor::MoveToColumnAndRow(
+                            (view.position().x + 1)     as u16,
+                            (view.position().y + 1 + i) as u16
+                        )
+                    ).unwrap();
+                }
+            });
         }
 
         if let Some((x, y)) = self.cursor.take() {
@@ -211,14 +396,66 @@ impl Editor {
 
         self.reprint_views(&keys, &mut buffer);
 
-        let stdin  = io::stdin();
-        let handle = stdin.lock();
+        let sender = self.sender.clone();
+
+        std::thread::spawn(move || {
+            for event in io::stdin().lock().events() {
+                let Ok(event) = event else { break; };
+
+                if sender.send(Signal::Input(event)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let sender = self.sender.clone();
 
-        for event in handle.events() {
-            let mut event = event.unwrap();
+        std::thread::spawn(move || {
+            loop {
+                std::thread::sleep(TICK_INTERVAL);
 
-            self.try_update_focus(&mut event);
-            self.handle_event(event);
+                if sender.send(Signal::Tick).is_err() {
+                    break;
+                }
+            }
+        });
+
+        while let Ok(signal) = self.receiver.recv() {
+            match signal {
+                Signal::Input(mut event) => {
+                    self.try_update_focus(&mut event);
+
+                    // NOTE: prompts get every key, global ones too
+                    let focused  = &self.views[&self.view];
+                    let contexts = if focused.is_modal() {
+                        Vec::new()
+                    } else {
+                        [Some(Context::Global), focused.context()].into_iter().flatten().collect()
+                    };
+
+                    match self.keymap.feed(&contexts, &event) {
+                        Lookup::Run(command)  => { self.run_command(command); },
+                        Lookup::Pending(keys) => { self.message = Some(Message::info(format!("{keys} ..."))); },
+                        Lookup::Unbound(keys) => { self.message = Some(Message::warning(format!("nothing is bound to {keys} and that key"))); },
+                        Lookup::Pass          => { self.handle_event(event); }
+                    }
+                },
+                Signal::Changed(path) => {
+                    self.view::<Editing, ()>(|editor, view| view.disk_changed(editor, &path));
+                    self.view::<Hex,     ()>(|editor, view| view.disk_changed(editor, &path));
+                },
+                Signal::Redraw => (),
+                Signal::Tick   => {
+                    self.write_journal();
+
+                    // NOTE: only worth a redraw once the message is gone
+                    if !self.message.as_ref().is_some_and(Message::is_expired) {
+                        continue;
+                    }
+
+                    self.message = None;
+                }
+            }
 
             if self.exit {
                 break;
@@ -228,22 +465,59 @@ impl Editor {
         }
     }
 
-    fn view<T: View + 'static, R>(&mut self, f: impl Fn(&mut Self, &mut T) -> R) -> R {
-        let name = T::name();
+    fn view<T: View + 'static, R>(&mut self, f: impl FnOnce(&mut Self, &mut T) -> R) -> R {
+        self.with_view(&T::name(), |editor, view| {
+            let t = view
+                .any()
+                .downcast_mut::<T>()
+                .unwrap();
+
+            f(editor, t)
+        })
+    }
+}
+
+/// the view under `(x, y)`, counted from 0, and the point within it,
+/// closed views take no space so they are never under anything
+fn view_at(views: &HashMap<String, Box<dyn View>>, x: u16, y: u16) -> Option<(String, u16, u16)> {
+    let (x, y) = (x as isize, y as isize);
 
-        let mut view = self.views
-            .remove(&name)
-            .unwrap();
+    views.iter().find_map(|(name, view)| {
+        let position = view.position();
+        let size     = view.size();
 
-        let t = view
-            .any()
-            .downcast_mut::<T>()
-            .unwrap();
+        if size.x <= 0 || size.y <= 0 {
+            return None;
+        }
 
-        let result = f(self, t);
+        let (dx, dy) = (x - position.x, y - position.y);
 
-        self.views.insert(name, view);
+        (dx >= 0 && dy >= 0 && dx < size.x && dy < size.y).then(|| (name.clone(), dx as u16, dy as u16))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::view::{View, Conflict, Editing, Exiting, Palette, Themes};
+    use super::view_at;
+
+
+    #[test]
+    fn clicks_skip_closed_overlays() {
+        let views = HashMap::<String, Box<dyn View>>::from([
+            (Editing ::name(), Box::new(Editing::with_text("ab\ncd", (0, 0), 10)) as Box<dyn View>),
+            (Exiting ::name(), Box::new(Exiting ::new())),
+            (Conflict::name(), Box::new(Conflict::new())),
+            (Palette ::name(), Box::new(Palette ::new())),
+            (Themes  ::name(), Box::new(Themes  ::new()))
+        ]);
+
+        for (x, y) in [(0, 0), (5, 3), (79, 9)] {
+            assert_eq!(view_at(&views, x, y), Some((Editing::name(), x, y)), "at ({x}, {y})");
+        }
 
-        result
+        assert_eq!(view_at(&views, 80, 0), None);
+        assert!(views.values().all(|view| !view.is_modal()));
     }
 }
@@ -0,0 +1,61 @@
+// mochou-p/text-editor/src/message.rs
+
+use std::time::{Duration, Instant};
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Level {
+    Info,
+    Warning,
+    Error
+}
+
+/// a line of feedback for the user, in the status bar until it expires
+pub struct Message {
+    pub level: Level,
+    pub text:  String,
+    shown:     Instant
+}
+
+impl Level {
+    // NOTE: the worse it is, the longer it stays
+    fn lifetime(self) -> Duration {
+        match self {
+            Self::Info    => Duration::from_secs( 4),
+            Self::Warning => Duration::from_secs( 8),
+            Self::Error   => Duration::from_secs(12)
+        }
+    }
+}
+
+impl Message {
+    pub fn info(text: impl Into<String>) -> Self {
+        Self::new(Level::Info, text.into())
+    }
+
+    pub fn warning(text: impl Into<String>) -> Self {
+        Self::new(Level::Warning, text.into())
+    }
+
+    pub fn error(text: impl Into<String>) -> Self {
+        Self::new(Level::Error, text.into())
+    }
+
+    /// the first of `errors` and how many more there are, the rest are likely the same mistake
+    pub fn errors(errors: &[String]) -> Option<Self> {
+        let first = errors.first()?;
+
+        Some(match errors.len() {
+            1     => Self::error(first.clone()),
+            count => Self::error(format!("{first} (and {} more)", count - 1))
+        })
+    }
+
+    fn new(level: Level, text: String) -> Self {
+        Self { level, text, shown: Instant::now() }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.shown.elapsed() >= self.level.lifetime()
+    }
+}
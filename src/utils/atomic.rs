@@ -0,0 +1,140 @@
+// mochou-p/text-editor/src/utils/atomic.rs
+
+//! saving without ever leaving a half written file behind
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write as _};
+use std::os::unix::fs::{MetadataExt as _, OpenOptionsExt as _, PermissionsExt as _};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+
+/// how `write` got the bytes there
+pub enum Written {
+    Renamed,
+    // NOTE: truncated and written over, for the reason given,
+    //       a crash in the middle leaves it cut short
+    InPlace(&'static str)
+}
+
+/// writes `bytes` to a temporary file next to `path`, syncs it and renames it over,
+/// keeping the mode and owner of the old file, and writing through symlinks
+// NOTE: falls back to writing in place when the directory is not writable,
+//       or when the owner could not be kept, as a rename would change it,
+//       and being a new file, the rename also leaves any other hard links with the old text
+pub fn write(path: &Path, bytes: &[u8]) -> io::Result<Written> {
+    let target   = resolve(path)?;
+    let metadata = match fs::metadata(&target) {
+        Ok(metadata)                                          => Some(metadata),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+        Err(error)                                            => return Err(error)
+    };
+
+    // NOTE: the rename would go through, but the file was not meant to change
+    if metadata.is_some() && !writable(&target) {
+        return Err(io::Error::from(io::ErrorKind::PermissionDenied));
+    }
+
+    let temp = temp_path(&target);
+
+    // NOTE: private until it gets the mode of the old file, a new one gets the default the umask leaves
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+
+    if metadata.is_some() {
+        options.mode(0o600);
+    }
+
+    let mut file = match options.open(&temp) {
+        Ok(file)                                                      => file,
+        Err(error) if error.kind() == io::ErrorKind::PermissionDenied => return in_place(&target, bytes, "the directory is not writable"),
+        Err(error)                                                    => return Err(error)
+    };
+
+    let result = (|| {
+        if let Some(metadata) = metadata.as_ref()
+            && std::os::unix::fs::fchown(&file, Some(metadata.uid()), Some(metadata.gid())).is_err()
+        {
+            return Ok(false);
+        }
+
+        file.write_all(bytes)?;
+
+        if let Some(metadata) = metadata.as_ref() {
+            file.set_permissions(fs::Permissions::from_mode(metadata.mode() & 0o7777))?;
+        }
+
+        file.sync_all()?;
+
+        fs::rename(&temp, &target)?;
+
+        Ok(true)
+    })();
+
+    match result {
+        Ok(true) => {
+            // NOTE: so the rename itself survives a crash, not every filesystem allows it
+            if let Some(dir) = target.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                let _ = File::open(dir).and_then(|dir| dir.sync_all());
+            }
+
+            Ok(Written::Renamed)
+        },
+        Ok(false) => {
+            let _ = fs::remove_file(&temp);
+            in_place(&target, bytes, "its owner could not be kept")
+        },
+        Err(error) => {
+            let _ = fs::remove_file(&temp);
+            Err(error)
+        }
+    }
+}
+
+/// follows symlinks, so the link stays and its target gets written
+fn resolve(path: &Path) -> io::Result<PathBuf> {
+    let mut path = path.to_path_buf();
+
+    // NOTE: a limit, for links that point at each other
+    for _ in 0..40 {
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let link = fs::read_link(&path)?;
+
+                path = match path.parent() {
+                    Some(parent) => parent.join(link),
+                    None         => link
+                };
+            },
+            Ok(_)                                                 => return Ok(path),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(path),
+            Err(error)                                            => return Err(error)
+        }
+    }
+
+    Err(io::Error::other("too many levels of symbolic links"))
+}
+
+fn writable(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let Ok(cstring) = std::ffi::CString::new(path.as_os_str().as_bytes()) else { return false; };
+
+    unsafe { libc::access(cstring.as_ptr(), libc::W_OK) == 0 }
+}
+
+fn temp_path(target: &Path) -> PathBuf {
+    let name  = target.file_name().map_or_else(Default::default, |name| name.to_string_lossy());
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.subsec_nanos());
+
+    target.with_file_name(format!(".{name}.{}.{nanos}.tmp", std::process::id()))
+}
+
+fn in_place(target: &Path, bytes: &[u8], reason: &'static str) -> io::Result<Written> {
+    let mut file = OpenOptions::new().write(true).create(true).truncate(true).open(target)?;
+
+    file.write_all(bytes)?;
+    file.sync_all()?;
+
+    Ok(Written::InPlace(reason))
+}
@@ -0,0 +1,25 @@
+// mochou-p/text-editor/src/utils/base64.rs
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+
+pub fn encode(bytes: &[u8]) -> String {
+    let mut string = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - i * 8));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                string.push(ALPHABET[(n >> (18 - i * 6)) as usize & 63] as char);
+            } else {
+                string.push('=');
+            }
+        }
+    }
+
+    string
+}
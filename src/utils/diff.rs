@@ -0,0 +1,113 @@
+// mochou-p/text-editor/src/utils/diff.rs
+
+//! line diffs, to show and to apply the changes between two versions of a file
+
+use std::iter::repeat_n;
+use std::ops::Range;
+
+
+// NOTE: past this many table cells, the changed middle counts as one big change
+const MAX_CELLS: usize = 4_000_000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Op {
+    Same,
+    Removed,
+    Added
+}
+
+/// lines `old` of the old version, replaced by lines `new` of the new one
+pub struct Hunk {
+    pub old: Range<usize>,
+    pub new: Range<usize>
+}
+
+/// one op per line of either side, in order
+pub fn diff<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Op> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut ops = vec![Op::Same; prefix];
+
+    if a.is_empty() || b.is_empty() || a.len() * b.len() > MAX_CELLS {
+        ops.extend(repeat_n(Op::Removed, a.len()));
+        ops.extend(repeat_n(Op::Added,   b.len()));
+    } else {
+        // NOTE: longest common subsequence of every pair of suffixes
+        let     width = b.len() + 1;
+        let mut table = vec![0_u32; (a.len() + 1) * width];
+
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                table[i * width + j] = if a[i] == b[j] {
+                    table[(i + 1) * width + j + 1] + 1
+                } else {
+                    table[(i + 1) * width + j].max(table[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+
+        while i < a.len() && j < b.len() {
+            if a[i] == b[j] {
+                ops.push(Op::Same);
+                i += 1;
+                j += 1;
+            } else if table[(i + 1) * width + j] >= table[i * width + j + 1] {
+                ops.push(Op::Removed);
+                i += 1;
+            } else {
+                ops.push(Op::Added);
+                j += 1;
+            }
+        }
+
+        ops.extend(repeat_n(Op::Removed, a.len() - i));
+        ops.extend(repeat_n(Op::Added,   b.len() - j));
+    }
+
+    ops.extend(repeat_n(Op::Same, suffix));
+    ops
+}
+
+/// the runs of changed lines in `ops`
+pub fn hunks(ops: &[Op]) -> Vec<Hunk> {
+    let mut hunks = Vec::new();
+    let mut old   = 0;
+    let mut new   = 0;
+    let mut k     = 0;
+
+    while k < ops.len() {
+        if ops[k] == Op::Same {
+            old += 1;
+            new += 1;
+            k   += 1;
+            continue;
+        }
+
+        let start = (old, new);
+
+        while k < ops.len() && ops[k] != Op::Same {
+            match ops[k] {
+                Op::Removed => { old += 1; },
+                Op::Added   => { new += 1; },
+                Op::Same    => ()
+            }
+
+            k += 1;
+        }
+
+        hunks.push(Hunk { old: start.0..old, new: start.1..new });
+    }
+
+    hunks
+}
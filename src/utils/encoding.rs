@@ -0,0 +1,136 @@
+// mochou-p/text-editor/src/utils/encoding.rs
+
+//! the encodings files can be opened and saved in
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Windows1252
+}
+
+// NOTE: 0x80..=0x9F, the undefined ones keep their latin-1 control so every byte round trips
+const WINDOWS_1252: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}'
+];
+
+impl Encoding {
+    pub const ALL: [Self; 4] = [Self::Utf8, Self::Utf16Le, Self::Utf16Be, Self::Windows1252];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Utf8        => "UTF-8",
+            Self::Utf16Le     => "UTF-16LE",
+            Self::Utf16Be     => "UTF-16BE",
+            Self::Windows1252 => "Windows-1252"
+        }
+    }
+
+    fn bom(self) -> &'static [u8] {
+        match self {
+            Self::Utf8        => &[0xEF, 0xBB, 0xBF],
+            Self::Utf16Le     => &[0xFF, 0xFE],
+            Self::Utf16Be     => &[0xFE, 0xFF],
+            Self::Windows1252 => &[]
+        }
+    }
+
+    /// goes by the bom first, then by whether it is valid utf-8
+    pub fn detect(bytes: &[u8]) -> Self {
+        if let Some(encoding) = [Self::Utf8, Self::Utf16Le, Self::Utf16Be].into_iter().find(|encoding| bytes.starts_with(encoding.bom())) {
+            return encoding;
+        }
+
+        if std::str::from_utf8(bytes).is_ok() {
+            Self::Utf8
+        } else {
+            Self::Windows1252
+        }
+    }
+
+    /// returns the text without a bom, whether there was one, and whether anything got replaced
+    pub fn decode(self, bytes: &[u8]) -> (String, bool, bool) {
+        let bom   = !self.bom().is_empty() && bytes.starts_with(self.bom());
+        let bytes = if bom { &bytes[self.bom().len()..] } else { bytes };
+
+        let (text, lossy) = match self {
+            Self::Utf8 => match std::str::from_utf8(bytes) {
+                Ok(text) => (String::from(text), false),
+                Err(_)   => (String::from_utf8_lossy(bytes).into_owned(), true)
+            },
+            Self::Utf16Le | Self::Utf16Be => {
+                let units = bytes.chunks_exact(2).map(|pair| {
+                    if self == Self::Utf16Le {
+                        u16::from_le_bytes([pair[0], pair[1]])
+                    } else {
+                        u16::from_be_bytes([pair[0], pair[1]])
+                    }
+                });
+
+                let mut lossy = bytes.len() % 2 == 1;
+
+                let text = char::decode_utf16(units)
+                    .map(|unit| unit.unwrap_or_else(|_| { lossy = true; char::REPLACEMENT_CHARACTER }))
+                    .collect::<String>();
+
+                (text, lossy)
+            },
+            Self::Windows1252 => {
+                let text = bytes
+                    .iter()
+                    .map(|byte| match byte {
+                        0x80..=0x9F => WINDOWS_1252[*byte as usize - 0x80],
+                        _           => *byte as char
+                    })
+                    .collect::<String>();
+
+                (text, false)
+            }
+        };
+
+        (text, bom, lossy)
+    }
+
+    /// fails with the first char the encoding has no bytes for
+    pub fn encode(self, text: &str, bom: bool) -> Result<Vec<u8>, char> {
+        let mut bytes = if bom { self.bom().to_vec() } else { Vec::new() };
+
+        match self {
+            Self::Utf8    => bytes.extend_from_slice(text.as_bytes()),
+            Self::Utf16Le => bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes)),
+            Self::Utf16Be => bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes)),
+            Self::Windows1252 => {
+                for ch in text.chars() {
+                    let byte = match ch as u32 {
+                        0x00..=0x7F | 0xA0..=0xFF => ch as u8,
+                        _ => WINDOWS_1252
+                            .iter()
+                            .position(|other| *other == ch)
+                            .map(|i| 0x80 + i as u8)
+                            .ok_or(ch)?
+                    };
+
+                    bytes.push(byte);
+                }
+            }
+        }
+
+        Ok(bytes)
+    }
+}
+
+/// nul bytes or lots of control bytes early on,
+/// only meaningful for bytes without a utf-16 bom
+pub fn is_binary(bytes: &[u8]) -> bool {
+    let head     = &bytes[..bytes.len().min(8192)];
+    let controls = head
+        .iter()
+        .filter(|byte| byte.is_ascii_control() && !matches!(byte, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B))
+        .count();
+
+    head.contains(&0) || controls * 10 > head.len()
+}
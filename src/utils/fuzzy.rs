@@ -0,0 +1,33 @@
+// mochou-p/text-editor/src/utils/fuzzy.rs
+
+//! loose matching, for finding things by a few of their letters
+
+
+/// how well `pattern` matches `text`, higher is better,
+/// `None` unless every char of it shows up in `text` in order, ignoring case and spaces
+// NOTE: greedy, it takes the first fit of each char, which is close enough for short names
+pub fn score(pattern: &str, text: &str) -> Option<i32> {
+    let text = text.chars().collect::<Vec<char>>();
+
+    let mut score = 0;
+    let mut next  = 0;
+
+    for ch in pattern.chars().filter(|ch| !ch.is_whitespace()) {
+        let found = (next..text.len()).find(|i| text[*i].to_lowercase().eq(ch.to_lowercase()))?;
+
+        score += 1;
+
+        // NOTE: the first letters of words, and runs of letters, count the most
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 8;
+        }
+        if found > 0 && found == next && next != 0 {
+            score += 5;
+        }
+
+        score -= (found - next).min(8) as i32;
+        next   = found + 1;
+    }
+
+    Some(score)
+}
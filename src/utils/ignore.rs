@@ -0,0 +1,141 @@
+// mochou-p/text-editor/src/utils/ignore.rs
+
+//! the common subset of `.gitignore`: comments, `!` negation, `/` anchoring,
+//! trailing `/` for directories only, and `*`, `**`, `?`, `[...]` globs
+
+use std::path::{Path, PathBuf};
+
+
+#[derive(Clone)]
+struct Rule {
+    base:     PathBuf,
+    pattern:  Vec<char>,
+    negated:  bool,
+    dir_only: bool,
+    anchored: bool
+}
+
+#[derive(Clone, Default)]
+pub struct Ignore {
+    rules: Vec<Rule>
+}
+
+impl Ignore {
+    /// these rules plus the ones from `dir/.gitignore`
+    pub fn within(&self, dir: &Path) -> Self {
+        let mut ignore = self.clone();
+
+        let Ok(text) = std::fs::read_to_string(dir.join(".gitignore")) else { return ignore; };
+
+        for line in text.lines() {
+            let line = line.trim_end();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(rest) => (true,  rest),
+                None       => (false, line)
+            };
+
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(rest) => (true,  rest),
+                None       => (false, line)
+            };
+
+            let anchored = line.contains('/');
+            let line     = line.strip_prefix('/').unwrap_or(line);
+
+            ignore.rules.push(Rule {
+                base:    dir.to_path_buf(),
+                pattern: line.chars().collect(),
+                negated,
+                dir_only,
+                anchored
+            });
+        }
+
+        ignore
+    }
+
+    /// the last rule that matches decides
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut ignored = false;
+
+        for rule in &self.rules {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+
+            let Ok(relative) = path.strip_prefix(&rule.base) else { continue; };
+
+            let text = if rule.anchored {
+                relative.to_string_lossy().chars().collect::<Vec<char>>()
+            } else {
+                relative.file_name().map_or_else(Vec::new, |name| name.to_string_lossy().chars().collect())
+            };
+
+            if glob(&rule.pattern, &text) {
+                ignored = !rule.negated;
+            }
+        }
+
+        ignored
+    }
+}
+
+fn glob(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+
+            if let Some(rest) = rest.strip_prefix(&['/']) {
+                // NOTE: zero or more whole directories
+                glob(rest, text)
+                ||
+                (0..text.len()).any(|i| text[i] == '/' && glob(rest, &text[i + 1..]))
+            } else {
+                (0..=text.len()).any(|i| glob(rest, &text[i..]))
+            }
+        },
+        Some('*') => {
+            (0..=text.len())
+                .take_while(|i| *i == 0 || text[i - 1] != '/')
+                .any(|i| glob(&pattern[1..], &text[i..]))
+        },
+        Some('?') => {
+            text.first().is_some_and(|ch| *ch != '/') && glob(&pattern[1..], &text[1..])
+        },
+        Some('[') if pattern.contains(&']') => {
+            let end     = pattern.iter().skip(2).position(|ch| *ch == ']').map_or(1, |i| i + 2);
+            let class   = &pattern[1..end];
+            let negated = matches!(class.first(), Some('!' | '^'));
+            let class   = if negated { &class[1..] } else { class };
+
+            let Some(ch) = text.first() else { return false; };
+
+            let mut found = false;
+            let mut i     = 0;
+
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    found |= class[i] <= *ch && *ch <= class[i + 2];
+                    i     += 3;
+                } else {
+                    found |= class[i] == *ch;
+                    i     += 1;
+                }
+            }
+
+            found != negated && glob(&pattern[end + 1..], &text[1..])
+        },
+        Some('\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && glob(&pattern[2..], &text[1..])
+        },
+        Some(ch) => {
+            text.first() == Some(ch) && glob(&pattern[1..], &text[1..])
+        }
+    }
+}
@@ -1,12 +1,43 @@
 // mochou-p/text-editor/src/utils/mod.rs
 
+pub mod atomic;
+pub mod base64;
+pub mod diff;
+pub mod encoding;
+pub mod fuzzy;
+pub mod ignore;
+pub mod regex;
     mod to_with;
     mod utf8;
 pub mod word;
+pub mod xdg;
 
-pub use {to_with::ToWith, utf8::{Utf8, Utf8Mut}};
+pub use {to_with::ToWith, utf8::Utf8};
 
 
 pub fn is_alphanumericx(ch: char) -> bool {
     ch == '_' || ch.is_alphanumeric()
 }
+
+/// one cell stand-in for chars the terminal would act on instead of draw
+pub fn printable(ch: char) -> char {
+    match ch {
+        '\t'                 => ' ',
+        _ if ch.is_control() => '·',
+        _                    => ch
+    }
+}
+
+/// SGR mouse report of an alt + left click, which termion leaves unparsed
+pub fn alt_click(bytes: &[u8]) -> Option<(u16, u16)> {
+    let report = bytes.strip_prefix(b"\x1B[<")?.strip_suffix(b"M")?;
+    let report = std::str::from_utf8(report).ok()?;
+
+    let mut numbers = report.split(';').map(str::parse::<u16>);
+
+    let (Some(Ok(8)), Some(Ok(x)), Some(Ok(y))) = (numbers.next(), numbers.next(), numbers.next()) else {
+        return None;
+    };
+
+    Some((x, y))
+}
@@ -0,0 +1,617 @@
+// mochou-p/text-editor/src/utils/regex.rs
+
+//! a small backtracking regex engine, matches within a single line
+//!
+//! supports literals, `.`, classes (`[a-z]`, `[^...]`, `\d \w \s` and negations),
+//! anchors (`^ $ \b \B`), groups (`(...)`, `(?:...)`), alternation
+//! and greedy or lazy quantifiers (`* + ? {n} {n,} {n,m}`)
+
+use super::is_alphanumericx;
+
+
+// NOTE: the pattern gets retyped into search prompts, so nothing in it may blow up,
+//       `Memo` takes a bit per instruction per char of the line, 8 MiB at most
+const MAX_REPEAT:  usize = 1000;
+const MAX_PROGRAM: usize = 2048;
+const MAX_DEPTH:   usize = 64;
+const MAX_MEMO:    usize = 1 << 26;
+
+/// start and end char index of the whole match (0) and every group (1..)
+pub type Captures = Vec<Option<(usize, usize)>>;
+
+pub struct Regex {
+    program:     Vec<Inst>,
+    groups:      usize,
+    ignore_case: bool
+}
+
+/// the states that already failed on a line, a bit each, kept around to match many lines with
+#[derive(Default)]
+pub struct Memo {
+    bits:    Vec<u64>,
+    width:   usize,
+    // NOTE: words the current attempt set bits in, for clearing only those after a match
+    touched: Vec<usize>
+}
+
+enum Inst {
+    Char    (char),
+    Any,
+    Class   (Class),
+    Start,
+    End,
+    Boundary(bool),
+    Save    (usize),
+    Split   (usize, usize),
+    Jump    (usize),
+    Match
+}
+
+#[derive(Clone)]
+struct Class {
+    items:   Vec<ClassItem>,
+    negated: bool
+}
+
+#[derive(Clone, Copy)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word (bool),
+    Space(bool)
+}
+
+enum Node {
+    Char     (char),
+    Any,
+    Class    (Class),
+    Start,
+    End,
+    Boundary (bool),
+    Group    (Box<Node>, Option<usize>),
+    Concat   (Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat   { node: Box<Node>, min: usize, max: Option<usize>, greedy: bool }
+}
+
+struct Parser {
+    chars:  Vec<char>,
+    i:      usize,
+    groups: usize,
+    // NOTE: how many groups it is inside of
+    depth:  usize
+}
+
+enum Job {
+    Thread  { pc: usize, pos: usize },
+    Restore { slot: usize, old: Option<usize> }
+}
+
+impl Regex {
+    pub fn new(pattern: &str, ignore_case: bool) -> Result<Self, String> {
+        let mut parser = Parser { chars: pattern.chars().collect(), i: 0, groups: 0, depth: 0 };
+        let     node   = parser.alternation()?;
+
+        if parser.i < parser.chars.len() {
+            return Err(String::from("unmatched `)`"));
+        }
+
+        let mut program = Vec::new();
+        compile(&node, &mut program)?;
+        program.push(Inst::Match);
+
+        Ok(Self { program, groups: parser.groups, ignore_case })
+    }
+
+    pub fn literal(text: &str, ignore_case: bool) -> Self {
+        let mut program = text.chars().map(Inst::Char).collect::<Vec<Inst>>();
+        program.push(Inst::Match);
+
+        Self { program, groups: 0, ignore_case }
+    }
+
+    /// a memo with room for lines up to `longest` chars, as far as `MAX_MEMO` goes
+    pub fn memo(&self, longest: usize) -> Memo {
+        let mut memo = Memo::default();
+        memo.fit(self.program.len(), longest.min(MAX_MEMO / self.program.len() - 1));
+        memo
+    }
+
+    /// every non-overlapping match, left to right,
+    /// `None` if the line is too long to keep a memo of with this pattern
+    pub fn find_all(&self, chars: &[char], memo: &mut Memo) -> Option<Vec<Captures>> {
+        if !memo.fit(self.program.len(), chars.len()) {
+            return None;
+        }
+
+        let mut matches = Vec::new();
+        let mut start   = 0;
+
+        while start <= chars.len() {
+            let Some(captures) = self.find_from(chars, start, memo) else { break; };
+            let (from, to)     = captures[0].unwrap();
+
+            start = if to == from { to + 1 } else { to };
+            matches.push(captures);
+        }
+
+        Some(matches)
+    }
+
+    fn find_from(&self, chars: &[char], start: usize, memo: &mut Memo) -> Option<Captures> {
+        // NOTE: a state that failed for an earlier start fails for later ones too,
+        //       so only what the attempt that matched went through gets forgotten
+        let result = (start..=chars.len()).find_map(|from| {
+            memo.touched.clear();
+            self.run(chars, from, memo)
+        });
+
+        if result.is_some() {
+            memo.forget_touched();
+        }
+
+        result
+    }
+
+    fn run(&self, chars: &[char], start: usize, memo: &mut Memo) -> Option<Captures> {
+        let mut slots = vec![None; (self.groups + 1) * 2];
+        let mut stack = vec![Job::Thread { pc: 0, pos: start }];
+
+        while let Some(job) = stack.pop() {
+            let (mut pc, mut pos) = match job {
+                Job::Thread  { pc, pos  } => (pc, pos),
+                Job::Restore { slot, old } => { slots[slot] = old; continue; }
+            };
+
+            loop {
+                if !memo.visit(pc, pos) {
+                    break;
+                }
+
+                match &self.program[pc] {
+                    Inst::Char(ch) => {
+                        if pos < chars.len() && self.eq(chars[pos], *ch) {
+                            pc  += 1;
+                            pos += 1;
+                        } else {
+                            break;
+                        }
+                    },
+                    Inst::Any => {
+                        if pos < chars.len() {
+                            pc  += 1;
+                            pos += 1;
+                        } else {
+                            break;
+                        }
+                    },
+                    Inst::Class(class) => {
+                        if pos < chars.len() && class.matches(chars[pos], self.ignore_case) {
+                            pc  += 1;
+                            pos += 1;
+                        } else {
+                            break;
+                        }
+                    },
+                    Inst::Start => {
+                        if pos != 0 { break; }
+                        pc += 1;
+                    },
+                    Inst::End => {
+                        if pos != chars.len() { break; }
+                        pc += 1;
+                    },
+                    Inst::Boundary(wanted) => {
+                        let before = pos > 0 && is_alphanumericx(chars[pos - 1]);
+                        let after  = pos < chars.len() && is_alphanumericx(chars[pos]);
+
+                        if (before != after) != *wanted { break; }
+                        pc += 1;
+                    },
+                    Inst::Save(slot) => {
+                        stack.push(Job::Restore { slot: *slot, old: slots[*slot] });
+                        slots[*slot] = Some(pos);
+                        pc += 1;
+                    },
+                    Inst::Split(first, second) => {
+                        stack.push(Job::Thread { pc: *second, pos });
+                        pc = *first;
+                    },
+                    Inst::Jump(to) => {
+                        pc = *to;
+                    },
+                    Inst::Match => {
+                        slots[0] = Some(start);
+                        slots[1] = Some(pos);
+
+                        return Some(
+                            slots
+                                .chunks(2)
+                                .map(|pair| pair[0].zip(pair[1]))
+                                .collect()
+                        );
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    fn eq(&self, a: char, b: char) -> bool {
+        a == b || (self.ignore_case && a.to_lowercase().eq(b.to_lowercase()))
+    }
+}
+
+impl Class {
+    fn matches(&self, ch: char, ignore_case: bool) -> bool {
+        let hit = |ch: char| self.items.iter().any(|item| item.matches(ch));
+
+        let found = hit(ch) || (
+            ignore_case
+            &&
+            (ch.to_lowercase().any(hit) || ch.to_uppercase().any(hit))
+        );
+
+        found != self.negated
+    }
+}
+
+impl ClassItem {
+    fn matches(self, ch: char) -> bool {
+        match self {
+            Self::Range(from, to) => from <= ch && ch <= to,
+            Self::Digit(yes)      => ch.is_ascii_digit()   == yes,
+            Self::Word (yes)      => is_alphanumericx(ch)  == yes,
+            Self::Space(yes)      => ch.is_whitespace()    == yes
+        }
+    }
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.i).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek();
+        self.i += 1;
+        ch
+    }
+
+    fn alternation(&mut self) -> Result<Node, String> {
+        let mut alternatives = vec![self.concat()?];
+
+        while self.peek() == Some('|') {
+            self.i += 1;
+            alternatives.push(self.concat()?);
+        }
+
+        Ok(if alternatives.len() == 1 {
+            alternatives.pop().unwrap()
+        } else {
+            Node::Alternate(alternatives)
+        })
+    }
+
+    fn concat(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+
+        while let Some(ch) = self.peek() {
+            if ch == '|' || ch == ')' {
+                break;
+            }
+
+            nodes.push(self.repeat()?);
+        }
+
+        Ok(Node::Concat(nodes))
+    }
+
+    fn repeat(&mut self) -> Result<Node, String> {
+        let mut node    = self.atom()?;
+        let mut stacked = 0;
+
+        loop {
+            let (min, max) = match self.peek() {
+                Some('*') => { self.i += 1; (0, None   ) },
+                Some('+') => { self.i += 1; (1, None   ) },
+                Some('?') => { self.i += 1; (0, Some(1)) },
+                Some('{') => {
+                    let Some(bounds) = self.bounds() else { break; };
+                    bounds
+                },
+                _ => break
+            };
+
+            if max.is_some_and(|max| max < min) {
+                return Err(String::from("quantifier range is backwards"));
+            }
+
+            if min.max(max.unwrap_or(0)) > MAX_REPEAT {
+                return Err(format!("quantifier bound is over {MAX_REPEAT}"));
+            }
+
+            stacked += 1;
+
+            // NOTE: each one wraps the last, as deep as groups would
+            if stacked > MAX_DEPTH {
+                return Err(String::from("too many quantifiers in a row"));
+            }
+
+            let greedy = self.peek() != Some('?');
+            if !greedy {
+                self.i += 1;
+            }
+
+            node = Node::Repeat { node: Box::new(node), min, max, greedy };
+        }
+
+        Ok(node)
+    }
+
+    /// `{n}`, `{n,}` or `{n,m}`, anything else is a literal `{`
+    fn bounds(&mut self) -> Option<(usize, Option<usize>)> {
+        let start = self.i;
+        let end   = start + self.chars[start..].iter().position(|ch| *ch == '}')?;
+        let body  = self.chars[start + 1..end].iter().collect::<String>();
+
+        let bounds = match body.split_once(',') {
+            None             => { let n = body.parse().ok()?; (n, Some(n)) },
+            Some((min, ""))  => (min.parse().ok()?, None),
+            Some((min, max)) => (min.parse().ok()?, Some(max.parse().ok()?))
+        };
+
+        self.i = end + 1;
+        Some(bounds)
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let Some(ch) = self.next() else {
+            return Err(String::from("unexpected end"));
+        };
+
+        Ok(match ch {
+            '(' => {
+                self.depth += 1;
+
+                if self.depth > MAX_DEPTH {
+                    return Err(format!("groups nest over {MAX_DEPTH} deep"));
+                }
+
+                let index = if self.chars[self.i..].starts_with(&['?', ':']) {
+                    self.i += 2;
+                    None
+                } else {
+                    self.groups += 1;
+                    Some(self.groups)
+                };
+
+                let node = self.alternation()?;
+
+                if self.next() != Some(')') {
+                    return Err(String::from("unclosed `(`"));
+                }
+
+                self.depth -= 1;
+
+                Node::Group(Box::new(node), index)
+            },
+            '[' => Node::Class(self.class()?),
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '*' | '+' | '?' => return Err(format!("nothing to repeat before `{ch}`")),
+            '\\' => match self.next() {
+                Some('b') => Node::Boundary(true),
+                Some('B') => Node::Boundary(false),
+                Some(ch)  => match Self::escape(ch) {
+                    Ok(item) => Node::Class(Class { items: vec![item], negated: false }),
+                    Err(ch)  => Node::Char(ch)
+                },
+                None      => return Err(String::from("trailing `\\`"))
+            },
+            ch => Node::Char(ch)
+        })
+    }
+
+    fn class(&mut self) -> Result<Class, String> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.i += 1;
+        }
+
+        let mut items = Vec::new();
+        let mut first = true;
+
+        loop {
+            let Some(ch) = self.next() else {
+                return Err(String::from("unclosed `[`"));
+            };
+
+            if ch == ']' && !first {
+                break;
+            }
+            first = false;
+
+            let from = if ch == '\\' {
+                let Some(escaped) = self.next() else {
+                    return Err(String::from("unclosed `[`"));
+                };
+
+                match Self::escape(escaped) {
+                    Ok(item) => { items.push(item); continue; },
+                    Err(ch)  => ch
+                }
+            } else {
+                ch
+            };
+
+            let is_range = self.peek() == Some('-') && self.chars.get(self.i + 1).is_some_and(|ch| *ch != ']');
+
+            if is_range {
+                self.i += 1;
+
+                let to = match self.next() {
+                    Some('\\') => self.next().ok_or_else(|| String::from("unclosed `[`"))?,
+                    Some(to)   => to,
+                    None       => return Err(String::from("unclosed `[`"))
+                };
+
+                if to < from {
+                    return Err(format!("class range `{from}-{to}` is backwards"));
+                }
+
+                items.push(ClassItem::Range(from, to));
+            } else {
+                items.push(ClassItem::Range(from, from));
+            }
+        }
+
+        Ok(Class { items, negated })
+    }
+
+    /// shorthand classes, or the literal char an escape stands for
+    fn escape(ch: char) -> Result<ClassItem, char> {
+        match ch {
+            'd' => Ok(ClassItem::Digit(true )),
+            'D' => Ok(ClassItem::Digit(false)),
+            'w' => Ok(ClassItem::Word (true )),
+            'W' => Ok(ClassItem::Word (false)),
+            's' => Ok(ClassItem::Space(true )),
+            'S' => Ok(ClassItem::Space(false)),
+            't' => Err('\t'),
+            'n' => Err('\n'),
+            'r' => Err('\r'),
+            ch  => Err(ch)
+        }
+    }
+}
+
+impl Memo {
+    /// cleared for a line of `len` chars, grown if it has to be, false if it would go over `MAX_MEMO`
+    fn fit(&mut self, program: usize, len: usize) -> bool {
+        let bits = program * (len + 1);
+
+        if bits > MAX_MEMO {
+            return false;
+        }
+
+        let words = bits.div_ceil(64);
+
+        if self.bits.len() < words {
+            self.bits.resize(words, 0);
+        }
+
+        self.bits[..words].fill(0);
+        self.width = len + 1;
+
+        true
+    }
+
+    /// returns whether the state is new
+    fn visit(&mut self, pc: usize, pos: usize) -> bool {
+        let i    = pc * self.width + pos;
+        let word = &mut self.bits[i / 64];
+        let bit  = 1 << (i % 64);
+
+        if *word & bit != 0 {
+            return false;
+        }
+
+        *word |= bit;
+        self.touched.push(i / 64);
+
+        true
+    }
+
+    fn forget_touched(&mut self) {
+        for word in self.touched.drain(..) {
+            self.bits[word] = 0;
+        }
+    }
+}
+
+fn compile(node: &Node, program: &mut Vec<Inst>) -> Result<(), String> {
+    // NOTE: checked as it goes, before nested repeats unroll into millions
+    if program.len() > MAX_PROGRAM {
+        return Err(String::from("pattern is too big"));
+    }
+
+    match node {
+        Node::Char(ch)        => program.push(Inst::Char(*ch)),
+        Node::Any             => program.push(Inst::Any),
+        Node::Class(class)    => program.push(Inst::Class(class.clone())),
+        Node::Start           => program.push(Inst::Start),
+        Node::End             => program.push(Inst::End),
+        Node::Boundary(yes)   => program.push(Inst::Boundary(*yes)),
+        Node::Concat(nodes)   => for node in nodes { compile(node, program)?; },
+        Node::Group(node, index) => {
+            if let Some(index) = index {
+                program.push(Inst::Save(index * 2));
+                compile(node, program)?;
+                program.push(Inst::Save(index * 2 + 1));
+            } else {
+                compile(node, program)?;
+            }
+        },
+        Node::Alternate(nodes) => {
+            let mut jumps = Vec::new();
+
+            for (i, node) in nodes.iter().enumerate() {
+                if i == nodes.len() - 1 {
+                    compile(node, program)?;
+                    break;
+                }
+
+                let split = program.len();
+                program.push(Inst::Jump(0));
+
+                compile(node, program)?;
+
+                jumps.push(program.len());
+                program.push(Inst::Jump(0));
+
+                program[split] = Inst::Split(split + 1, program.len());
+            }
+
+            for jump in jumps {
+                program[jump] = Inst::Jump(program.len());
+            }
+        },
+        Node::Repeat { node, min, max, greedy } => {
+            let order = |body: usize, exit: usize| {
+                if *greedy { Inst::Split(body, exit) } else { Inst::Split(exit, body) }
+            };
+
+            for _ in 0..*min {
+                compile(node, program)?;
+            }
+
+            if let Some(max) = max {
+                let mut splits = Vec::new();
+
+                for _ in *min..*max {
+                    splits.push(program.len());
+                    program.push(Inst::Jump(0));
+                    compile(node, program)?;
+                }
+
+                let exit = program.len();
+                for split in splits {
+                    program[split] = order(split + 1, exit);
+                }
+            } else {
+                let split = program.len();
+                program.push(Inst::Jump(0));
+
+                compile(node, program)?;
+                program.push(Inst::Jump(split));
+
+                program[split] = order(split + 1, program.len());
+            }
+        }
+    }
+
+    Ok(())
+}
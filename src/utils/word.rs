@@ -4,39 +4,31 @@ use super::Utf8;
 
 
 pub fn to_left(string: &str, mut i: isize, f: impl Fn(char) -> bool) -> Option<isize> {
-    let mut index = None;
+    let head = &string[..string.utf8_index(i) as usize];
 
-    loop {
+    for ch in head.chars().rev() {
         i -= 1;
 
         if i == 0 {
             break;
         }
 
-        if f(string.chars().nth(i as usize).unwrap()) {
-            index = Some(i);
-            break;
+        if f(ch) {
+            return Some(i);
         }
     }
 
-    index
+    None
 }
 
 pub fn to_right(string: &str, mut i: isize, f: impl Fn(char) -> bool) -> Option<isize> {
-    let mut index = None;
-
-    loop {
+    for ch in string.chars().skip(i as usize + 1) {
         i += 1;
 
-        if i == string.utf8_len() {
-            break;
-        }
-
-        if f(string.chars().nth(i as usize).unwrap()) {
-            index = Some(i);
-            break;
+        if f(ch) {
+            return Some(i);
         }
     }
 
-    index
+    None
 }
@@ -0,0 +1,24 @@
+// mochou-p/text-editor/src/utils/xdg.rs
+
+//! where files of ours go, after the xdg base directory spec
+
+use std::path::PathBuf;
+
+
+/// `$XDG_CONFIG_HOME/text-editor`, or `~/.config/text-editor`
+pub fn config_dir() -> Option<PathBuf> {
+    base("XDG_CONFIG_HOME", ".config").map(|dir| dir.join(env!("CARGO_PKG_NAME")))
+}
+
+/// `$XDG_STATE_HOME/text-editor`, or `~/.local/state/text-editor`
+pub fn state_dir() -> Option<PathBuf> {
+    base("XDG_STATE_HOME", ".local/state").map(|dir| dir.join(env!("CARGO_PKG_NAME")))
+}
+
+// NOTE: relative paths in the variables are invalid, as per the spec
+fn base(variable: &str, fallback: &str) -> Option<PathBuf> {
+    std::env::var_os(variable)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))
+}
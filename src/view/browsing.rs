@@ -2,15 +2,20 @@
 
 use std::collections::HashMap;
 use std::ffi::CString;
-use std::path::PathBuf;
-use termion::event::{Event, Key, MouseEvent, MouseButton};
+use std::path::{Path, PathBuf};
+use termion::event::{Event, MouseEvent, MouseButton};
 use super::{View, ViewData};
 use super::editing::Editing;
+use super::hex::Hex;
+use super::searching::Searching;
 use crate::Editor;
+use crate::keymap::Context;
 
 
 pub struct Browsing {
     view_data:   ViewData,
+    // NOTE: where it started, paths elsewhere are shown relative to it
+    root:        PathBuf,
     current_dir: PathBuf,
     focused:     usize,
     focuses:     HashMap<PathBuf, usize>,
@@ -42,22 +47,29 @@ impl From<PathBuf> for BrowserEntry {
 }
 
 impl Browsing {
-    pub fn new(editor: &mut Editor) -> Self {
+    /// starts in `dir`, or in the working directory
+    pub fn new(editor: &mut Editor, dir: Option<PathBuf>) -> Self {
         let view_data             = ViewData::left_of::<Editing>(editor, 24);
-        let current_dir           = std::env::current_dir().unwrap();
+        let current_dir           = dir.unwrap_or_else(|| std::env::current_dir().unwrap());
         let (parent, dirs, files) = Self::load(&current_dir);
         let focused               = 0;
         let focuses               = HashMap::from([(current_dir.clone(), focused)]);
+        let root                  = current_dir.clone();
 
-        Self { view_data, current_dir, focused, focuses, parent, dirs, files }
+        Self { view_data, root, current_dir, focused, focuses, parent, dirs, files }
     }
 
-    fn load(path: &PathBuf) -> (Option<BrowserEntry>, Vec<BrowserEntry>, Vec<BrowserEntry>) {
-        let parent = if let Some(parent) = path.parent() {
-            Some(BrowserEntry::from(parent.to_path_buf()))
-        } else {
-            None
-        };
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// `path` under the root, or all of it if it is somewhere else
+    pub fn relative(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.root).unwrap_or(path).to_path_buf()
+    }
+
+    fn load(path: &Path) -> (Option<BrowserEntry>, Vec<BrowserEntry>, Vec<BrowserEntry>) {
+        let parent = path.parent().map(|parent| BrowserEntry::from(parent.to_path_buf()));
 
         let mut dirs  = Vec::new();
         let mut files = Vec::new();
@@ -78,23 +90,27 @@ impl Browsing {
         (parent, dirs, files)
     }
 
+    pub fn current_dir(&self) -> &PathBuf {
+        &self.current_dir
+    }
+
     fn entry_count(&self) -> usize {
         self.parent.is_some() as usize + self.dirs.len() + self.files.len()
     }
 
-    fn up(&mut self) {
+    pub fn up(&mut self) {
         if self.focused != 0 {
             self.focused -= 1;
         }
     }
 
-    fn down(&mut self) {
+    pub fn down(&mut self) {
         if self.focused != self.dirs.len() + self.files.len() - self.parent.is_none() as usize {
             self.focused += 1;
         }
     }
 
-    fn go_out(&mut self) {
+    pub fn go_out(&mut self) {
         let Some(parent) = self.parent.take() else { return; };
 
         let old_dir = self.current_dir.clone();
@@ -116,7 +132,7 @@ impl Browsing {
             );
     }
 
-    fn go_in(&mut self, editor: &mut Editor) {
+    pub fn go_in(&mut self, editor: &mut Editor) {
         let mut i = self.focused;
 
         if self.parent.is_some() {
@@ -141,6 +157,16 @@ impl Browsing {
         }
     }
 
+    /// opens the project search rooted here
+    pub fn search_here(&self, editor: &mut Editor) {
+        let root = self.current_dir.clone();
+
+        editor.view::<Editing, ()>(|editor, editing| {
+            editor.view::<Hex,       ()>(|_, view| view.hide(editing.view_data_mut()));
+            editor.view::<Searching, ()>(|editor, view| view.open(editor, editing.view_data_mut(), root));
+        });
+    }
+
     fn print_entry(
         &self,
         editor:    &Editor,
@@ -148,8 +174,7 @@ impl Browsing {
         focused:   bool,
         entry:     &BrowserEntry,
         is_parent: bool,
-        prefix:    &str,
-        suffix:    &str
+        is_dir:    bool
     ) {
         buffer.push_str(
             if focused {
@@ -178,6 +203,12 @@ impl Browsing {
             entry.path.file_name().unwrap().to_string_lossy().to_string()
         };
 
+        let (prefix, suffix) = if is_dir {
+            (&editor.theme.ansi.blue, "/")
+        } else {
+            (&editor.theme.foreground.normal, "")
+        };
+
         let text         = format!("{path}{suffix}");
         let visible_text = &text[..(width as usize).min(text.len())];
 
@@ -188,11 +219,11 @@ impl Browsing {
     }
 
     fn print_dir(&self, editor: &Editor, buffer: &mut String, focused: bool, i: usize) {
-        self.print_entry(editor, buffer, focused, &self.dirs[i], false, &editor.theme.ansi.blue, "/");
+        self.print_entry(editor, buffer, focused, &self.dirs[i], false, true);
     }
 
     fn print_file(&self, editor: &Editor, buffer: &mut String, focused: bool, i: usize) {
-        self.print_entry(editor, buffer, focused, &self.files[i], false, &editor.theme.foreground.normal, "");
+        self.print_entry(editor, buffer, focused, &self.files[i], false, false);
     }
 
     fn print_empty(&mut self, editor: &Editor, buffer: &mut String) {
@@ -210,10 +241,18 @@ impl View for Browsing {
     fn view_data    (&    self) -> &        ViewData      { &    self.view_data      }
     fn view_data_mut(&mut self) -> &mut     ViewData      { &mut self.view_data      }
 
+    fn mode(&self) -> &'static str {
+        "BROWSE"
+    }
+
+    fn context(&self) -> Option<Context> {
+        Some(Context::Browsing)
+    }
+
     fn print_line(&mut self, editor: &mut Editor, buffer: &mut String, _loop_i: usize, mut scrolled_i: usize) {
         if let Some(parent) = self.parent.as_ref() {
             if scrolled_i == 0 {
-                self.print_entry(editor, buffer, self.focused == 0, parent, true, &editor.theme.ansi.blue, "/");
+                self.print_entry(editor, buffer, self.focused == 0, parent, true, true);
                 return;
             } else {
                 scrolled_i -= 1;
@@ -236,24 +275,15 @@ impl View for Browsing {
         self.print_empty(editor, buffer);
     }
 
-    fn handle_event(&mut self, editor: &mut Editor, event: Event) {
-        match event {
-            Event::Key(key) => match key {
-                Key::Up                      => { self.up()          },
-                Key::Down                    => { self.down()        },
-                Key::Left  | Key::Backspace  => { self.go_out()      },
-                Key::Right | Key::Char('\n') => { self.go_in(editor) },
-                _                            => ()
-            },
-            Event::Mouse(MouseEvent::Press(mouse_button, _x, y)) => {
-                if matches!(mouse_button, MouseButton::Left) {
-                    let y = y as usize;
-                    if y < self.entry_count() {
-                        self.focused = y as usize;
-                    }
-                }
-            },
-            _ => ()
+    fn handle_event(&mut self, _editor: &mut Editor, event: Event) {
+        // NOTE: keys are commands, see `Keymap`
+        let Event::Mouse(MouseEvent::Press(MouseButton::Left, _x, y)) = event else {
+            return;
+        };
+
+        let y = y as usize;
+        if y < self.entry_count() {
+            self.focused = y;
         }
     }
 }
@@ -0,0 +1,347 @@
+// mochou-p/text-editor/src/view/conflict.rs
+
+use std::path::PathBuf;
+use termion::event::{Event, Key, MouseEvent, MouseButton};
+use super::{View, ViewData};
+use super::editing::Editing;
+use super::files::Files;
+use super::hex::Hex;
+use crate::{Editor, Ivec2};
+use crate::journal::{Entry, Kind};
+use crate::utils::diff::{self, Op};
+
+
+// NOTE: unchanged lines kept around each change in the diff
+const CONTEXT: usize = 2;
+
+// NOTE: the lines above the diff
+const HEADER: usize = 5;
+
+/// asks what to do with files that changed on disk while they had unsaved changes,
+/// and with unsaved files recovered after a crash,
+/// one at a time, drawn over the middle of the screen
+pub struct Conflict {
+    view_data: ViewData,
+    pending:   Vec<Question>,
+    // NOTE: `None` for the separator between far apart changes
+    diff:      Option<Vec<Option<(Op, String)>>>,
+    // NOTE: gets the focus back once everything is answered
+    previous:  String
+}
+
+enum Question {
+    Changed  (PathBuf),
+    Recovered(Entry)
+}
+
+impl Question {
+    fn path(&self) -> &PathBuf {
+        match self {
+            Self::Changed  (path)  => path,
+            Self::Recovered(entry) => &entry.path
+        }
+    }
+}
+
+impl Conflict {
+    pub fn new() -> Self {
+        Self {
+            view_data: ViewData::default(),
+            pending:   Vec::new(),
+            diff:      None,
+            previous:  String::new()
+        }
+    }
+
+    pub fn push(&mut self, editor: &mut Editor, path: PathBuf) {
+        if !self.pending.iter().any(|question| matches!(question, Question::Changed(other) if *other == path)) {
+            self.pending.push(Question::Changed(path));
+        }
+
+        self.ask(editor);
+    }
+
+    pub fn recover(&mut self, editor: &mut Editor, entry: Entry) {
+        self.pending.push(Question::Recovered(entry));
+        self.ask(editor);
+    }
+
+    fn ask(&mut self, editor: &mut Editor) {
+        if editor.view != Self::name() {
+            self.previous = editor.view.clone();
+            editor.view   = Self::name();
+        }
+
+        self.layout();
+    }
+
+    fn header(&self) -> [String; 3] {
+        let more = match self.pending.len() {
+            0 | 1 => String::new(),
+            count => format!(" ({} more)", count - 1)
+        };
+
+        let hide = if self.diff.is_some() { "hide " } else { "" };
+
+        let (what, keys, legend) = match &self.pending[0] {
+            Question::Changed  (_) => ("changed on disk",              "[r]eload  [k]eep mine",           "- mine  + on disk"     ),
+            Question::Recovered(_) => ("was not saved before a crash", "[r]estore  [x] discard  [l]ater", "- recovered  + on disk")
+        };
+
+        [
+            format!("{} {what}{more}", self.pending[0].path().display()),
+            format!("{keys}  {hide}[d]iff"),
+            String::from(if self.diff.is_some() { legend } else { "" })
+        ]
+    }
+
+    /// centered, as big as the text, or most of the screen with a diff
+    fn layout(&mut self) {
+        let screen = Ivec2::from(termion::terminal_size().unwrap());
+
+        let (width, height) = if self.diff.is_some() {
+            (screen.x - 8, screen.y - 4)
+        } else {
+            let width = self.header().iter().map(|line| line.chars().count()).max().unwrap_or(0);
+
+            (width as isize + 4, HEADER as isize - 1)
+        };
+
+        let width  = width .clamp(1, screen.x);
+        let height = height.clamp(1, screen.y);
+
+        self.view_data.size     = Ivec2 { x: width, y: height };
+        self.view_data.position = Ivec2 { x: (screen.x - width) / 2, y: (screen.y - height) / 2 };
+        self.view_data.scroll   = Ivec2::ZERO;
+    }
+
+    fn toggle_diff(&mut self, editor: &mut Editor) {
+        if self.diff.take().is_none() {
+            let (mine, disk) = match &self.pending[0] {
+                Question::Changed(path) if editor.view::<Hex, bool>(|_, view| view.has(path)) => {
+                    let Some((mine, disk)) = editor.view::<Hex, _>(|_, view| view.disk_diff(path)) else { return; };
+                    (Self::hex_lines(&mine), Self::hex_lines(&disk))
+                },
+                Question::Changed(path) => {
+                    let Some(lines) = editor.view::<Editing, _>(|_, view| view.disk_diff(path)) else { return; };
+                    lines
+                },
+                Question::Recovered(entry) => match entry.kind {
+                    Kind::Text  => (Self::text_lines(&entry.content), Editing::disk_lines(&entry.path)),
+                    Kind::Bytes => (
+                        Self::hex_lines(&entry.content),
+                        Self::hex_lines(&std::fs::read(&entry.path).unwrap_or_default())
+                    )
+                }
+            };
+
+            self.diff = Some(Self::context_lines(&mine, &disk));
+        }
+
+        self.layout();
+    }
+
+    /// the changed lines, with a little of what is around them
+    fn context_lines(mine: &[String], disk: &[String]) -> Vec<Option<(Op, String)>> {
+        let ops = diff::diff(mine, disk);
+
+        // NOTE: which side each op takes its line from
+        let mut lines = Vec::with_capacity(ops.len());
+        let (mut i, mut j) = (0, 0);
+
+        for op in &ops {
+            match op {
+                Op::Same    => { lines.push(&mine[i]); i += 1; j += 1; },
+                Op::Removed => { lines.push(&mine[i]); i += 1;         },
+                Op::Added   => { lines.push(&disk[j]);         j += 1; }
+            }
+        }
+
+        let near = |k: usize| {
+            ops[k.saturating_sub(CONTEXT)..(k + CONTEXT + 1).min(ops.len())]
+                .iter()
+                .any(|op| *op != Op::Same)
+        };
+
+        let mut shown = Vec::new();
+        let mut gap   = false;
+
+        for (k, op) in ops.iter().enumerate() {
+            if near(k) {
+                if gap && !shown.is_empty() {
+                    shown.push(None);
+                }
+
+                shown.push(Some((*op, lines[k].clone())));
+                gap = false;
+            } else {
+                gap = true;
+            }
+        }
+
+        shown
+    }
+
+    fn text_lines(content: &[u8]) -> Vec<String> {
+        String::from_utf8_lossy(content).split('\n').map(String::from).collect()
+    }
+
+    // NOTE: rows of 16, the same bytes line up on both sides unless some got inserted
+    fn hex_lines(bytes: &[u8]) -> Vec<String> {
+        bytes
+            .chunks(16)
+            .enumerate()
+            .map(|(i, row)| {
+                let hex = row.iter().map(|byte| format!("{byte:02x}")).collect::<Vec<String>>().join(" ");
+                format!("{:08x}  {hex}", i * 16)
+            })
+            .collect()
+    }
+
+    fn scroll_diff(&mut self, direction: isize) {
+        let Some(diff) = self.diff.as_ref() else { return; };
+
+        let room = self.size().y - HEADER as isize;
+        let max  = (diff.len() as isize - room).max(0);
+
+        self.scroll_mut().y = (self.scroll().y + direction).clamp(0, max);
+    }
+
+    // NOTE: the file is in `Hex` or in `Editing`, never both
+    fn reload(editor: &mut Editor, question: Question) {
+        let Question::Changed(path) = question else { return; };
+
+        if editor.view::<Hex, bool>(|_, view| view.has(&path)) {
+            editor.view::<Hex, ()>(|editor, view| view.reload(editor, &path));
+        } else {
+            editor.view::<Editing, ()>(|editor, view| view.reload(editor, &path));
+        }
+    }
+
+    fn keep_mine(editor: &mut Editor, question: Question) {
+        let Question::Changed(path) = question else { return; };
+
+        editor.view::<Hex,     ()>(|_, view| view.keep_mine(&path));
+        editor.view::<Editing, ()>(|_, view| view.keep_mine(&path));
+    }
+
+    // NOTE: the entry stays in the journal if the file could not be opened
+    fn restore(editor: &mut Editor, question: Question) {
+        let Question::Recovered(entry) = question else { return; };
+
+        let restored = match entry.kind {
+            Kind::Text => {
+                let lines = Self::text_lines(&entry.content);
+                editor.view::<Editing, bool>(|editor, view| view.restore(editor, &entry.path, &lines))
+            },
+            Kind::Bytes => editor.view::<Editing, bool>(|editor, editing| {
+                let bytes    = entry.content.clone();
+                let restored = editor.view::<Hex, bool>(|editor, view| {
+                    view.restore(editor, editing.view_data_mut(), &entry.path, bytes)
+                });
+
+                if restored {
+                    editor.view::<Files, ()>(|_, view| view.add_file(entry.path.clone()));
+                }
+
+                restored
+            })
+        };
+
+        if restored {
+            entry.discard();
+        }
+    }
+
+    fn discard(_editor: &mut Editor, question: Question) {
+        let Question::Recovered(entry) = question else { return; };
+
+        entry.discard();
+    }
+
+    /// moves on to the next question, or gives the focus back
+    fn answer(&mut self, editor: &mut Editor, f: fn(&mut Editor, Question)) {
+        let question = self.pending.remove(0);
+        f(editor, question);
+
+        self.diff = None;
+
+        if self.pending.is_empty() {
+            self.view_data.size = Ivec2::ZERO;
+            editor.view         = self.previous.clone();
+        } else {
+            self.layout();
+        }
+    }
+}
+
+impl View for Conflict {
+    fn any          (&mut self) -> &mut dyn std::any::Any { self                     }
+    fn name         (         ) ->          String        { String::from("conflict") }
+    fn view_data    (&    self) -> &        ViewData      { &    self.view_data      }
+    fn view_data_mut(&mut self) -> &mut     ViewData      { &mut self.view_data      }
+
+    fn mode(&self) -> &'static str {
+        "PROMPT"
+    }
+
+    // NOTE: only while asking, a closed one must not hold on to a stray click
+    fn is_modal(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    fn print_line(&mut self, editor: &mut Editor, buffer: &mut String, loop_i: usize, _scrolled_i: usize) {
+        let theme = &editor.theme;
+        let width = self.size().x as usize;
+
+        let (color, line) = match loop_i {
+            1..=3 => {
+                let color = if loop_i == 1 { &theme.ansi.yellow } else { &theme.foreground.active };
+                (color, self.header()[loop_i - 1].clone())
+            },
+            _ if loop_i >= HEADER => {
+                let k = loop_i - HEADER + self.scroll().y as usize;
+
+                match self.diff.as_ref().and_then(|diff| diff.get(k)) {
+                    Some(Some((Op::Same,    line))) => (&theme.foreground.normal, format!("  {line}")),
+                    Some(Some((Op::Removed, line))) => (&theme.ansi.red,          format!("- {line}")),
+                    Some(Some((Op::Added,   line))) => (&theme.ansi.green,        format!("+ {line}")),
+                    Some(None)                      => (&theme.foreground.normal, String::from("  ...")),
+                    None                            => (&theme.foreground.normal, String::new())
+                }
+            },
+            _ => (&theme.foreground.normal, String::new())
+        };
+
+        let line = format!("  {line}").chars().map(crate::utils::printable).take(width).collect::<String>();
+
+        buffer.push_str(&format!(
+            "{}{color}{line}{}",
+            theme.backgrounds.secondary.normal,
+            " ".repeat(width - line.chars().count())
+        ));
+    }
+
+    fn handle_event(&mut self, editor: &mut Editor, event: Event) {
+        match event {
+            Event::Key(key) => match (&self.pending[0], key) {
+                (Question::Changed  (_), Key::Char('r')           ) => { self.answer(editor, Self::reload   ); },
+                (Question::Changed  (_), Key::Char('k') | Key::Esc) => { self.answer(editor, Self::keep_mine); },
+                (Question::Recovered(_), Key::Char('r')           ) => { self.answer(editor, Self::restore  ); },
+                (Question::Recovered(_), Key::Char('x')           ) => { self.answer(editor, Self::discard  ); },
+                // NOTE: left in the journal, asked about again next time
+                (Question::Recovered(_), Key::Char('l') | Key::Esc) => { self.answer(editor, |_, _| ()      ); },
+                (_,                      Key::Char('d')           ) => { self.toggle_diff(editor); },
+                (_,                      Key::Up                  ) => { self.scroll_diff(-1    ); },
+                (_,                      Key::Down                ) => { self.scroll_diff( 1    ); },
+                _                                                   => ()
+            },
+            Event::Mouse(MouseEvent::Press(mouse_button, _x, _y)) => match mouse_button {
+                MouseButton::WheelUp   => { self.scroll_diff(-1); },
+                MouseButton::WheelDown => { self.scroll_diff( 1); },
+                _                      => ()
+            },
+            _ => ()
+        }
+    }
+}
@@ -0,0 +1,125 @@
+// mochou-p/text-editor/src/view/editing/actions/clipboard.rs
+
+use super::super::File;
+use super::super::history::StepKind;
+use crate::Editor;
+use crate::clipboard::{self, Clip};
+use crate::utils::ToWith;
+
+
+impl super::super::Editing {
+    pub fn copy(&mut self, editor: &mut Editor) {
+        let Some(file) = self.file.as_ref() else { return; };
+        let      clip  = self.files[file].clip();
+
+        Self::yank(editor, clip);
+    }
+
+    pub fn cut(&mut self, editor: &mut Editor) {
+        let Some(file) = self.file.as_ref() else { return; };
+        let      file  = self.files.get_mut(file).unwrap();
+        let      clip  = file.clip();
+
+        file.begin(StepKind::Other);
+
+        let mut last_y = None;
+
+        for i in file.cursor_order().into_iter().rev() {
+            if !clip.linewise {
+                file.erase_selection_at(i);
+            } else if last_y != Some(file.cursors[i].y) {
+                last_y = Some(file.cursors[i].y);
+                file.erase_line_at(i);
+            }
+        }
+
+        file.end();
+
+        Self::yank(editor, clip);
+        self.snap_to_cursor();
+    }
+
+    pub fn paste(&mut self, editor: &mut Editor) {
+        let Some(file) = self.file.as_ref() else { return; };
+        let Some(clip) = editor.registers.load() else { return; };
+        let      file  = self.files.get_mut(file).unwrap();
+
+        let order  = file.cursor_order();
+        let lines  = clip.text.split('\n').collect::<Vec<&str>>();
+        let spread = order.len() > 1 && lines.len() == order.len();
+
+        file.begin(StepKind::Other);
+
+        for (n, i) in order.into_iter().enumerate().rev() {
+            let text = if spread { lines[n] } else { clip.text.as_str() };
+
+            file.erase_selection_at(i);
+
+            let mut cursor = file.cursors[i];
+
+            if clip.linewise {
+                (_, cursor.y) = file.insert(0, cursor.y, &format!("{text}\n"));
+            } else {
+                cursor.move_to(file.insert(cursor.x, cursor.y, text));
+            }
+
+            file.cursors[i] = cursor;
+        }
+
+        file.end();
+        self.snap_to_cursor();
+    }
+
+    fn yank(editor: &mut Editor, clip: Clip) {
+        if editor.registers.store(clip.clone()) {
+            clipboard::osc52(&mut editor.stdout, &clip.text);
+        }
+    }
+}
+
+impl File {
+    /// the selections, or whole lines when nothing is selected
+    fn clip(&self) -> Clip {
+        let linewise = self.cursors.iter().all(|cursor| cursor.selection().is_none());
+
+        let mut pieces = Vec::new();
+        let mut last_y = None;
+
+        for i in self.cursor_order() {
+            let cursor = self.cursors[i];
+
+            if linewise {
+                if last_y != Some(cursor.y) {
+                    last_y = Some(cursor.y);
+                    pieces.push(String::from(self.buffer.line(cursor.y).as_str()));
+                }
+            } else if let Some((start, end)) = cursor.selection() {
+                pieces.push(self.buffer.slice(start.0, start.1, end.0, end.1));
+            }
+        }
+
+        Clip { text: pieces.join("\n"), linewise }
+    }
+
+    fn erase_line_at(&mut self, i: usize) {
+        let mut cursor = self.cursors[i];
+        let     len    = self.buffer.line(cursor.y).len();
+
+        if cursor.y != self.buffer.line_count() - 1 {
+            self.remove(0, cursor.y, 0, cursor.y + 1);
+        } else if cursor.y != 0 {
+            let above = self.buffer.line(cursor.y - 1).len();
+
+            self.remove(above, cursor.y - 1, len, cursor.y);
+            cursor.y -= 1;
+        } else {
+            self.remove(0, cursor.y, len, cursor.y);
+        }
+
+        cursor.x
+            .to_max_with(cursor.last_x)
+            .to_min_with(self.buffer.line(cursor.y).len());
+
+        self.cursors[i] = cursor;
+    }
+}
@@ -1,196 +1,453 @@
 // mochou-p/text-editor/src/view/editing/actions/cursor.rs
 
-use crate::utils::{self, ToWith, Utf8, word};
+use super::super::cursor::Cursor;
+use crate::Buffer;
+use crate::config::PreferenceMask;
+use crate::view::View;
+use crate::utils::{self, ToWith, word};
 
 
 impl super::super::Editing {
-    pub fn line_start(&mut self) {
-        let Some(file) = self.file.as_ref() else { return; };
-        let      file  = self.files.get_mut(file).unwrap();
-
-        for cursor in &mut file.cursors {
+    pub fn line_start(&mut self, select: bool) {
+        self.move_cursors(select, |_, cursor| {
             cursor.x      = 0;
             cursor.last_x = 0;
-        }
-
-        self.snap_to_cursor();
+        });
     }
 
-    pub fn line_end(&mut self) {
-        let Some(file) = self.file.as_ref() else { return; };
-        let      file  = self.files.get_mut(file).unwrap();
-
-        for cursor in &mut file.cursors {
-            cursor.x      = file.lines[cursor.y as usize].utf8_len();
+    pub fn line_end(&mut self, select: bool) {
+        self.move_cursors(select, |buffer, cursor| {
+            cursor.x      = buffer.line(cursor.y).len();
             cursor.last_x = isize::MAX;
-        }
-
-        self.snap_to_cursor();
+        });
     }
 
-    pub fn file_start(&mut self) {
-        let Some(file) = self.file.as_ref() else { return; };
-        let      file  = self.files.get_mut(file).unwrap();
-
-        for cursor in &mut file.cursors {
+    pub fn file_start(&mut self, select: bool) {
+        self.move_cursors(select, |buffer, cursor| {
             if cursor.y != 0 {
                 cursor.y            = 0;
 
                 cursor.x
                     .to_max_with(cursor.last_x)
-                    .to_min_with(file.lines[cursor.y as usize].utf8_len());
+                    .to_min_with(buffer.line(cursor.y).len());
             }
-        }
-
-        self.snap_to_cursor();
+        });
     }
 
-    pub fn file_end(&mut self) {
-        let Some(file) = self.file.as_ref() else { return; };
-        let      file  = self.files.get_mut(file).unwrap();
-        let line_count = file.lines.len() as isize;
+    pub fn file_end(&mut self, select: bool) {
+        self.move_cursors(select, |buffer, cursor| {
+            let line_count = buffer.line_count();
 
-        for cursor in &mut file.cursors {
             if cursor.y != line_count - 1 {
                 cursor.y = line_count - 1;
 
                 cursor.x
                     .to_max_with(cursor.last_x)
-                    .to_min_with(file.lines[cursor.y as usize].utf8_len());
+                    .to_min_with(buffer.line(cursor.y).len());
             }
+        });
+    }
+
+    pub fn up(&mut self, select: bool, preferences: PreferenceMask) {
+        let to_start = preferences.has(PreferenceMask::CURSOR_MOVE_UP_GOES_TO_START_OF_FILE_AT_FIRST_LINE);
+        let scrolls  = preferences.has(PreferenceMask::CURSOR_MOVE_UP_SCROLLS_AT_SCREEN_BOUNDARY);
+        let top      = self.scroll().y;
+
+        self.move_cursors(select, |buffer, cursor| {
+            // NOTE: going to the start of the first line does not scroll
+            if !scrolls && cursor.y == top && cursor.y != 0 {
+                return;
+            }
+
+            up(buffer, cursor, to_start);
+        });
+    }
+
+    pub fn down(&mut self, select: bool, preferences: PreferenceMask) {
+        let to_end  = preferences.has(PreferenceMask::CURSOR_MOVE_DOWN_GOES_TO_END_OF_FILE_AT_LAST_LINE);
+        let scrolls = preferences.has(PreferenceMask::CURSOR_MOVE_DOWN_SCROLLS_AT_SCREEN_BOUNDARY);
+        let bottom  = self.scroll().y + self.text_height() - 1;
+
+        self.move_cursors(select, |buffer, cursor| {
+            if !scrolls && cursor.y == bottom && cursor.y != buffer.line_count() - 1 {
+                return;
+            }
+
+            down(buffer, cursor, to_end);
+        });
+    }
+
+    pub fn left(&mut self, select: bool, preferences: PreferenceMask) {
+        if !select && self.collapse_selections(false) {
+            return;
         }
 
+        let wraps = preferences.has(PreferenceMask::CURSOR_MOVE_LEFT_WRAPS_AT_LINE_BOUNDARY);
+
+        self.move_cursors(select, |buffer, cursor| left(buffer, cursor, wraps));
+    }
+
+    pub fn right(&mut self, select: bool, preferences: PreferenceMask) {
+        if !select && self.collapse_selections(true) {
+            return;
+        }
+
+        let wraps = preferences.has(PreferenceMask::CURSOR_MOVE_RIGHT_WRAPS_AT_LINE_BOUNDARY);
+
+        self.move_cursors(select, |buffer, cursor| right(buffer, cursor, wraps));
+    }
+
+    pub fn prev_word(&mut self, select: bool, preferences: PreferenceMask) {
+        let wraps = preferences.has(PreferenceMask::CURSOR_MOVE_TO_PREVIOUS_WORD_WRAPS_AT_LINE_BOUNDARY);
+
+        self.move_cursors(select, |buffer, cursor| {
+            if cursor.x == 0 {
+                left(buffer, cursor, wraps);
+                return;
+            }
+
+            let line  = buffer.line(cursor.y);
+            let start = line.char_at(cursor.x - 1).unwrap();
+
+            let end = if utils::is_alphanumericx(start) {
+                word::to_left(line.as_str(), cursor.x, |ch| !utils::is_alphanumericx(ch))
+            } else {
+                word::to_left(line.as_str(), cursor.x, |ch| ch != start)
+            };
+
+            cursor.x      = end.map(|i| i+1).unwrap_or(0);
+            cursor.last_x = cursor.x;
+        });
+    }
+
+    pub fn next_word(&mut self, select: bool, preferences: PreferenceMask) {
+        let wraps = preferences.has(PreferenceMask::CURSOR_MOVE_TO_NEXT_WORD_WRAPS_AT_LINE_BOUNDARY);
+
+        self.move_cursors(select, |buffer, cursor| {
+            let line = buffer.line(cursor.y);
+
+            if cursor.x == line.len() {
+                right(buffer, cursor, wraps);
+                return;
+            }
+
+            let start = line.char_at(cursor.x).unwrap();
+
+            let end = if utils::is_alphanumericx(start) {
+                word::to_right(line.as_str(), cursor.x, |ch| !utils::is_alphanumericx(ch))
+            } else {
+                word::to_right(line.as_str(), cursor.x, |ch| ch != start)
+            };
+
+            cursor.x      = end.unwrap_or(line.len());
+            cursor.last_x = cursor.x;
+        });
+    }
+
+    pub fn add_cursor_above(&mut self) {
+        self.add_cursor_beside(false);
+    }
+
+    pub fn add_cursor_below(&mut self) {
+        self.add_cursor_beside(true);
+    }
+
+    /// adds a cursor at a view-relative spot, like `warp_cursor` but keeping the others
+    pub fn add_cursor_at(&mut self, x: u16, y: u16) {
+        let scroll         = self.scroll();
+        let Some(ref file) = self.file.as_ref().cloned() else { return; };
+        let      file      = self.files.get_mut(file).unwrap();
+
+        let y = (y as isize + scroll.y).min(file.buffer.line_count() - 1);
+
+        file.cursors.insert(0, Cursor {
+            last_x: x as isize + scroll.x,
+            x:      (x as isize + scroll.x).min(file.buffer.line(y).len()),
+            y,
+            anchor: None
+        });
+
         self.snap_to_cursor();
     }
 
-    pub fn up(&mut self) {
+    /// selects the word under the primary cursor,
+    /// or adds a cursor on the next occurrence of its selection
+    pub fn add_next_occurrence(&mut self) {
         let Some(file) = self.file.as_ref() else { return; };
         let      file  = self.files.get_mut(file).unwrap();
 
-        for cursor in &mut file.cursors {
-            if cursor.y == 0 {
-                cursor.x      = 0;
-                cursor.last_x = 0;
-            } else {
-                cursor.y -= 1;
-                cursor.x
-                    .to_max_with(cursor.last_x)
-                    .to_min_with(file.lines[cursor.y as usize].utf8_len());
+        let mut primary = file.cursors[0];
+
+        let Some((start, end)) = primary.selection() else {
+            let line = file.buffer.line(primary.y);
+
+            let mut from = primary.x;
+            let mut to   = primary.x;
+
+            while from > 0 && line.char_at(from - 1).is_some_and(utils::is_alphanumericx) {
+                from -= 1;
+            }
+            while line.char_at(to).is_some_and(utils::is_alphanumericx) {
+                to += 1;
             }
+
+            if from != to {
+                primary.anchor = Some((from, primary.y));
+                primary.move_to((to, primary.y));
+
+                file.cursors[0] = primary;
+            }
+
+            self.snap_to_cursor();
+            return;
+        };
+
+        let needle = file.buffer.slice(start.0, start.1, end.0, end.1);
+
+        let Some((found_start, found_end)) = file.buffer.find(&needle, end.0, end.1) else { return; };
+
+        if file.cursors.iter().any(|cursor| cursor.selection() == Some((found_start, found_end))) {
+            return;
         }
 
+        let mut cursor = Cursor { anchor: Some(found_start), ..Cursor::default() };
+        cursor.move_to(found_end);
+
+        file.cursors.insert(0, cursor);
+
         self.snap_to_cursor();
     }
 
-    pub fn down(&mut self) {
-        let Some(file) = self.file.as_ref() else { return; };
+    /// drops every cursor but the primary one, and its selection,
+    /// returns whether there was anything to drop
+    pub fn collapse_cursors(&mut self) -> bool {
+        let Some(file) = self.file.as_ref() else { return false; };
         let      file  = self.files.get_mut(file).unwrap();
 
-        for cursor in &mut file.cursors {
-            if cursor.y == (file.lines.len() - 1) as isize {
-                cursor.x      = file.lines[cursor.y as usize].utf8_len();
-                cursor.last_x = cursor.x;
-            } else {
-                cursor.y += 1;
-                cursor.x
-                    .to_max_with(cursor.last_x)
-                    .to_min_with(file.lines[cursor.y as usize].utf8_len());
-            }
+        if file.cursors.len() == 1 && file.cursors[0].anchor.is_none() {
+            return false;
         }
 
+        file.cursors.truncate(1);
+        file.cursors[0].anchor = None;
+
         self.snap_to_cursor();
+        true
     }
 
-    pub fn left(&mut self) {
+    fn add_cursor_beside(&mut self, below: bool) {
         let Some(file) = self.file.as_ref() else { return; };
         let      file  = self.files.get_mut(file).unwrap();
 
-        for cursor in &mut file.cursors {
-            if cursor.x == 0 {
-                if cursor.y != 0 {
-                    cursor.y -= 1;
-                    cursor.x  = file.lines[cursor.y as usize].utf8_len();
-                }
-            } else {
-                cursor.x -= 1;
-            }
+        let edge = if below {
+            file.cursors.iter().max_by_key(|cursor| (cursor.y, cursor.x))
+        } else {
+            file.cursors.iter().min_by_key(|cursor| (cursor.y, cursor.x))
+        };
 
-            cursor.last_x = cursor.x;
+        let mut cursor = *edge.unwrap();
+
+        if cursor.y == if below { file.buffer.line_count() - 1 } else { 0 } {
+            return;
         }
 
+        cursor.anchor = None;
+
+        // NOTE: not on an edge line, so the preferences do not come into it
+        if below {
+            down(&file.buffer, &mut cursor, false);
+        } else {
+            up(&file.buffer, &mut cursor, false);
+        }
+
+        file.cursors.insert(0, cursor);
+
         self.snap_to_cursor();
     }
 
-    pub fn right(&mut self) {
+    /// runs `movement` on every cursor, extending or dropping selections
+    fn move_cursors(&mut self, select: bool, movement: impl Fn(&Buffer, &mut Cursor)) {
         let Some(file) = self.file.as_ref() else { return; };
         let      file  = self.files.get_mut(file).unwrap();
 
         for cursor in &mut file.cursors {
-            if cursor.x == file.lines[cursor.y as usize].utf8_len() {
-                if cursor.y != (file.lines.len() - 1) as isize {
-                    cursor.x  = 0;
-                    cursor.y += 1;
-                }
-            } else {
-                cursor.x += 1;
-            }
-
-            cursor.last_x = cursor.x;
+            cursor.select(select);
+            movement(&file.buffer, cursor);
+            cursor.settle();
         }
 
         self.snap_to_cursor();
     }
 
-    pub fn prev_word(&mut self) {
-        let Some(file) = self.file.as_ref() else { return; };
+    /// moves selecting cursors to one end of their selection
+    fn collapse_selections(&mut self, to_end: bool) -> bool {
+        let Some(file) = self.file.as_ref() else { return false; };
         let      file  = self.files.get_mut(file).unwrap();
 
+        let mut any = false;
+
         for cursor in &mut file.cursors {
-            if cursor.x == 0 {
-                self.left();
-                return;
-            }
+            let Some((start, end)) = cursor.selection() else { continue; };
 
-            let line  = &file.lines[cursor.y as usize];
-            let start = line.chars().nth((cursor.x - 1) as usize).unwrap();
+            cursor.anchor = None;
+            cursor.move_to(if to_end { end } else { start });
 
-            let end = if utils::is_alphanumericx(start) {
-                word::to_left(line, cursor.x, |ch| !utils::is_alphanumericx(ch))
-            } else {
-                word::to_left(line, cursor.x, |ch| ch != start)
-            };
+            any = true;
+        }
 
-            cursor.x      = end.map(|i| i+1).unwrap_or(0);
+        if any {
+            self.snap_to_cursor();
+        }
+
+        any
+    }
+}
+
+/// `to_start` goes to the start of the first line
+fn up(buffer: &Buffer, cursor: &mut Cursor, to_start: bool) {
+    if cursor.y == 0 {
+        if to_start {
+            cursor.x      = 0;
+            cursor.last_x = 0;
+        }
+    } else {
+        cursor.y -= 1;
+        cursor.x
+            .to_max_with(cursor.last_x)
+            .to_min_with(buffer.line(cursor.y).len());
+    }
+}
+
+/// `to_end` goes to the end of the last line
+fn down(buffer: &Buffer, cursor: &mut Cursor, to_end: bool) {
+    if cursor.y == buffer.line_count() - 1 {
+        if to_end {
+            cursor.x      = buffer.line(cursor.y).len();
             cursor.last_x = cursor.x;
         }
+    } else {
+        cursor.y += 1;
+        cursor.x
+            .to_max_with(cursor.last_x)
+            .to_min_with(buffer.line(cursor.y).len());
+    }
+}
 
-        self.snap_to_cursor();
+/// `wraps` goes from the start of a line to the end of the one above
+fn left(buffer: &Buffer, cursor: &mut Cursor, wraps: bool) {
+    if cursor.x == 0 {
+        if wraps && cursor.y != 0 {
+            cursor.y -= 1;
+            cursor.x  = buffer.line(cursor.y).len();
+        }
+    } else {
+        cursor.x -= 1;
     }
 
-    pub fn next_word(&mut self) {
-        let Some(file) = self.file.as_ref() else { return; };
-        let      file  = self.files.get_mut(file).unwrap();
+    cursor.last_x = cursor.x;
+}
 
-        for cursor in &mut file.cursors {
-            let line = &file.lines[cursor.y as usize];
+/// `wraps` goes from the end of a line to the start of the one below
+fn right(buffer: &Buffer, cursor: &mut Cursor, wraps: bool) {
+    if cursor.x == buffer.line(cursor.y).len() {
+        if wraps && cursor.y != buffer.line_count() - 1 {
+            cursor.x  = 0;
+            cursor.y += 1;
+        }
+    } else {
+        cursor.x += 1;
+    }
 
-            if cursor.x == line.utf8_len() {
-                self.right();
-                return;
-            }
+    cursor.last_x = cursor.x;
+}
 
-            let start = line.chars().nth(cursor.x as usize).unwrap();
+#[cfg(test)]
+mod tests {
+    use crate::config::PreferenceMask;
+    use crate::view::{Editing, View as _};
+    use super::super::super::with;
 
-            let end = if utils::is_alphanumericx(start) {
-                word::to_right(line, cursor.x, |ch| !utils::is_alphanumericx(ch))
-            } else {
-                word::to_right(line, cursor.x, |ch| ch != start)
-            };
 
-            cursor.x      = end.unwrap_or(file.lines[cursor.y as usize].utf8_len());
-            cursor.last_x = cursor.x;
+    #[test]
+    fn left_wraps_at_line_boundary() {
+        for (on, expected) in [(true, (2, 0)), (false, (0, 1))] {
+            let mut view = Editing::with_text("ab\ncd", (0, 1), 10);
+            view.left(false, with(PreferenceMask::CURSOR_MOVE_LEFT_WRAPS_AT_LINE_BOUNDARY, on));
+
+            assert_eq!(view.cursor(), expected, "on: {on}");
         }
+    }
 
-        self.snap_to_cursor();
+    #[test]
+    fn right_wraps_at_line_boundary() {
+        for (on, expected) in [(true, (0, 1)), (false, (2, 0))] {
+            let mut view = Editing::with_text("ab\ncd", (2, 0), 10);
+            view.right(false, with(PreferenceMask::CURSOR_MOVE_RIGHT_WRAPS_AT_LINE_BOUNDARY, on));
+
+            assert_eq!(view.cursor(), expected, "on: {on}");
+        }
+    }
+
+    #[test]
+    fn prev_word_wraps_at_line_boundary() {
+        for (on, expected) in [(true, (2, 0)), (false, (0, 1))] {
+            let mut view = Editing::with_text("ab\ncd", (0, 1), 10);
+            view.prev_word(false, with(PreferenceMask::CURSOR_MOVE_TO_PREVIOUS_WORD_WRAPS_AT_LINE_BOUNDARY, on));
+
+            assert_eq!(view.cursor(), expected, "on: {on}");
+        }
+    }
+
+    #[test]
+    fn next_word_wraps_at_line_boundary() {
+        for (on, expected) in [(true, (0, 1)), (false, (2, 0))] {
+            let mut view = Editing::with_text("ab\ncd", (2, 0), 10);
+            view.next_word(false, with(PreferenceMask::CURSOR_MOVE_TO_NEXT_WORD_WRAPS_AT_LINE_BOUNDARY, on));
+
+            assert_eq!(view.cursor(), expected, "on: {on}");
+        }
+    }
+
+    #[test]
+    fn up_scrolls_at_screen_boundary() {
+        for (on, expected, scroll) in [(true, (0, 1), 1), (false, (0, 2), 2)] {
+            let mut view = Editing::with_text("a\nb\nc\nd", (0, 2), 2);
+            view.scroll_mut().y = 2;
+            view.up(false, with(PreferenceMask::CURSOR_MOVE_UP_SCROLLS_AT_SCREEN_BOUNDARY, on));
+
+            assert_eq!(view.cursor(),   expected, "on: {on}");
+            assert_eq!(view.scroll().y, scroll,   "on: {on}");
+        }
+    }
+
+    #[test]
+    fn down_scrolls_at_screen_boundary() {
+        for (on, expected, scroll) in [(true, (0, 2), 1), (false, (0, 1), 0)] {
+            let mut view = Editing::with_text("a\nb\nc\nd", (0, 1), 2);
+            view.down(false, with(PreferenceMask::CURSOR_MOVE_DOWN_SCROLLS_AT_SCREEN_BOUNDARY, on));
+
+            assert_eq!(view.cursor(),   expected, "on: {on}");
+            assert_eq!(view.scroll().y, scroll,   "on: {on}");
+        }
+    }
+
+    #[test]
+    fn up_goes_to_start_of_file_at_first_line() {
+        for (on, expected) in [(true, (0, 0)), (false, (2, 0))] {
+            let mut view = Editing::with_text("abc", (2, 0), 10);
+            view.up(false, with(PreferenceMask::CURSOR_MOVE_UP_GOES_TO_START_OF_FILE_AT_FIRST_LINE, on));
+
+            assert_eq!(view.cursor(), expected, "on: {on}");
+        }
+    }
+
+    #[test]
+    fn down_goes_to_end_of_file_at_last_line() {
+        for (on, expected) in [(true, (3, 0)), (false, (1, 0))] {
+            let mut view = Editing::with_text("abc", (1, 0), 10);
+            view.down(false, with(PreferenceMask::CURSOR_MOVE_DOWN_GOES_TO_END_OF_FILE_AT_LAST_LINE, on));
+
+            assert_eq!(view.cursor(), expected, "on: {on}");
+        }
     }
 }
@@ -1,7 +1,9 @@
 // mochou-p/text-editor/src/view/editing/actions/editor.rs
 
 impl super::super::Editing {
-    pub fn exit(&mut self, editor: &mut crate::Editor) {
-        editor.exit = true;
+    /// backs out of multiple cursors and selections
+    // NOTE: quitting is on ctrl + q, esc was too easy to hit
+    pub fn escape(&mut self) {
+        self.collapse_cursors();
     }
 }
@@ -1,32 +1,278 @@
 // mochou-p/text-editor/src/view/editing/actions/file.rs
 
-use std::io::Write as _;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
+use super::super::File;
+use super::super::history::{Edit, StepKind};
 use crate::Editor;
+use crate::message::Message;
+use crate::utils::{self, atomic::Written, diff, encoding::Encoding};
+use crate::view::{Conflict, Files};
+use crate::watcher::Stamp;
 
 
 impl super::super::Editing {
+    /// writes the open file out, it stays unsaved if that fails
     pub fn save(&mut self, editor: &mut Editor) {
-        let Some(file) = self.file.as_ref() else { return; };
+        let Some(path) = self.file.clone() else { return; };
+
+        let file = self.files.get_mut(&path).unwrap();
+
+        if file.clean {
+            return;
+        }
+
+        if file.changed_on_disk(&path) {
+            file.clobber   = true;
+            editor.message = Some(Message::warning(format!(
+                "{} changed on disk since it was loaded, Ctrl+S again overwrites it",
+                path.display()
+            )));
+
+            return;
+        }
+
+        editor.message = Some(match self.save_file(&path) {
+            Ok((len, Written::Renamed))      => Message::info(format!("saved {}, {len} bytes", path.display())),
+            Ok((len, Written::InPlace(why))) => Message::warning(format!("saved {}, {len} bytes, but in place as {why}", path.display())),
+            Err(error)                       => Message::error(error)
+        });
+    }
+
+    /// returns whether every file got saved
+    pub fn save_all(&mut self, editor: &mut Editor) -> bool {
+        let mut saved    = 0;
+        let mut failed   = None;
+        let mut in_place = None;
+
+        for path in self.dirty_files() {
+            match self.save_file(&path) {
+                Ok((_, Written::Renamed))      => { saved += 1; },
+                Ok((_, Written::InPlace(why))) => { saved += 1; in_place = Some((path, why)); },
+                Err(error)                     => { failed = Some(error); }
+            }
+        }
+
+        if let Some(error) = failed {
+            editor.message = Some(Message::error(error));
+            return false;
+        }
+
+        if let Some((path, why)) = in_place {
+            editor.message = Some(Message::warning(format!("saved {saved} files, {} in place as {why}", path.display())));
+        } else if saved != 0 {
+            editor.message = Some(Message::info(format!("saved {saved} files")));
+        }
+
+        true
+    }
+
+    pub fn dirty_files(&self) -> Vec<PathBuf> {
+        let mut paths = self.files
+            .iter()
+            .filter(|(_, file)| !file.clean)
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<PathBuf>>();
 
-        if self.files[file].clean {
+        paths.sort();
+        paths
+    }
+
+    /// returns how many bytes got written, and how
+    fn save_file(&mut self, path: &PathBuf) -> Result<(usize, Written), String> {
+        let file = self.files.get_mut(path).unwrap();
+
+        if file.changed_on_disk(path) {
+            return Err(format!("{} changed on disk, Ctrl+S in it to overwrite", path.display()));
+        }
+
+        let bytes = file.format
+            .write(&file.buffer)
+            .map_err(|error| format!("could not save {}: {error}", path.display()))?;
+
+        let written = utils::atomic::write(path, &bytes)
+            .map_err(|error| format!("could not save {}: {error}", path.display()))?;
+
+        file.clean   = true;
+        file.clobber = false;
+        file.disk    = Stamp::of(path);
+        file.history.mark_saved();
+
+        Ok((bytes.len(), written))
+    }
+
+    /// reloads a clean file, asks about one with unsaved changes
+    pub fn disk_changed(&mut self, editor: &mut Editor, path: &PathBuf) {
+        let Some(file) = self.files.get_mut(path) else { return; };
+
+        // NOTE: our own saves, and events about the same change
+        let stamp = Stamp::of(path);
+        if stamp == file.disk {
             return;
         }
 
-        // NOTE: 7 -> '\a' -> BEL
-        write!(editor.stdout, "{}", 7 as char).unwrap();
+        if stamp.is_none() {
+            file.disk      = None;
+            editor.message = Some(Message::warning(format!("{} got deleted on disk", path.display())));
+            return;
+        }
 
-        let writee = if
-            self.files[file].lines.len() == 1
-            &&
-            self.files[file].lines[0].is_empty()
-        {
-            String::new()
+        if file.clean {
+            self.reload(editor, path);
         } else {
-            self.files[file].lines.join("\n")
+            editor.view::<Conflict, ()>(|editor, view| view.push(editor, path.clone()));
+        }
+    }
+
+    /// takes the version on disk, keeping the cursors close to where they were
+    pub fn reload(&mut self, editor: &mut Editor, path: &PathBuf) {
+        let Some(file) = self.files.get_mut(path) else { return; };
+
+        let (fresh, trouble) = match Self::read_file(path, Some(file.format.encoding)) {
+            Ok(read)   => read,
+            Err(error) => {
+                editor.message = Some(Message::error(error));
+                return;
+            }
         };
 
-        std::fs::write(file, writee).unwrap();
-        self.files.get_mut(file).unwrap().clean = true;
+        file.set_lines(&fresh.lines());
+
+        file.format   = fresh.format;
+        file.readonly = fresh.readonly;
+        file.disk     = fresh.disk;
+        file.clean    = true;
+        file.clobber  = false;
+        file.history.mark_saved();
+
+        editor.message = Some(match trouble {
+            Some(trouble) => Self::trouble_message(trouble, file),
+            None          => Message::info(format!("reloaded {}, it changed on disk", path.display()))
+        });
+
+        if self.file.as_ref() == Some(path) {
+            self.search = None;
+            self.snap_to_cursor();
+        }
+    }
+
+    /// puts `lines` recovered after a crash over the file on disk, as an unsaved change
+    pub fn restore(&mut self, editor: &mut Editor, path: &PathBuf, lines: &[String]) -> bool {
+        if !self.switch_to(editor, path, false) {
+            return false;
+        }
+
+        editor.view::<Files, ()>(|_, view| view.add_file(path.clone()));
+
+        self.files.get_mut(path).unwrap().set_lines(lines);
+        self.snap_to_cursor();
+
+        editor.message = Some(Message::info(format!("restored {}, not saved yet", path.display())));
+        true
+    }
+
+    /// unsaved files and how many edits in they are, with their buffers unless `journaled` already has them
+    pub fn unsaved(&self, journaled: &HashMap<PathBuf, u64>) -> Vec<(PathBuf, u64, Option<Vec<u8>>)> {
+        self.dirty_files()
+            .into_iter()
+            .map(|path| {
+                let file    = &self.files[&path];
+                let edits   = file.buffer.edits();
+                let content = (journaled.get(&path) != Some(&edits)).then(|| file.buffer.join("\n").into_bytes());
+                (path, edits, content)
+            })
+            .collect()
+    }
+
+    /// the lines of `path` on disk, empty if it is not there
+    pub fn disk_lines(path: &PathBuf) -> Vec<String> {
+        Self::read_file(path, None).map_or_else(|_| Vec::new(), |(file, _)| file.lines())
+    }
+
+    /// leaves the buffer as it is, saving it will not warn about the change on disk
+    pub fn keep_mine(&mut self, path: &PathBuf) {
+        let Some(file) = self.files.get_mut(path) else { return; };
+
+        file.disk = Stamp::of(path);
+    }
+
+    /// the lines of the buffer, and of the file on disk
+    pub fn disk_diff(&self, path: &PathBuf) -> Option<(Vec<String>, Vec<String>)> {
+        let file       = self.files.get(path)?;
+        let (fresh, _) = Self::read_file(path, Some(file.format.encoding)).ok()?;
+
+        Some((file.lines(), fresh.lines()))
+    }
+
+    /// switches between lf and crlf, written out on the next save, and undone like an edit
+    pub fn toggle_line_ending(&mut self) {
+        let Some(file) = self.file.as_ref() else { return; };
+        let      file  = self.files.get_mut(file).unwrap();
+        file.begin(StepKind::Other);
+
+        Edit::Ending.apply(&mut file.buffer, &mut file.highlight, &mut file.format);
+        file.history.record(Edit::Ending);
+
+        file.end();
+        file.clean = file.history.is_saved();
+    }
+
+    /// reads the file again from disk, in the encoding after the current one
+    pub fn reopen_in_next_encoding(&mut self, editor: &mut Editor) {
+        let Some(path) = self.file.clone() else { return; };
+        let      file  = &self.files[&path];
+
+        if !file.clean {
+            editor.message = Some(Message::warning("unsaved changes, save them before reopening"));
+            return;
+        }
+
+        let i        = Encoding::ALL.iter().position(|encoding| *encoding == file.format.encoding).unwrap();
+        let encoding = Encoding::ALL[(i + 1) % Encoding::ALL.len()];
+        let (x, y)   = (file.cursors[0].x, file.cursors[0].y);
+
+        match Self::read_file(&path, Some(encoding)) {
+            Ok((file, trouble)) => {
+                editor.message = Some(match trouble {
+                    Some(trouble) => Self::trouble_message(trouble, &file),
+                    None          => Message::info(format!("reopened as {}", encoding.name()))
+                });
+
+                self.files.insert(path, file);
+                self.search = None;
+                self.go_to(x, y);
+            },
+            Err(error) => {
+                editor.message = Some(Message::error(error));
+            }
+        }
+    }
+}
+
+impl File {
+    fn changed_on_disk(&self, path: &Path) -> bool {
+        !self.clobber && Stamp::of(path) != self.disk
+    }
+
+    /// makes the buffer `new` with as few edits as it takes, in one undo step
+    fn set_lines(&mut self, new: &[String]) {
+        let ops = diff::diff(&self.lines(), new);
+
+        // NOTE: back to front, so the line numbers of earlier hunks stay valid
+        self.begin(StepKind::Other);
+
+        for hunk in diff::hunks(&ops).into_iter().rev() {
+            self.replace_lines(hunk.old, &new[hunk.new]);
+        }
+
+        self.end();
+    }
+
+    fn lines(&self) -> Vec<String> {
+        self.buffer
+            .lines()
+            .map(|line| String::from(line.as_str()))
+            .collect()
     }
 }
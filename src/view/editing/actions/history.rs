@@ -0,0 +1,27 @@
+// mochou-p/text-editor/src/view/editing/actions/history.rs
+
+impl super::super::Editing {
+    pub fn undo(&mut self) {
+        let Some(file) = self.file.as_ref() else { return; };
+        let      file  = self.files.get_mut(file).unwrap();
+
+        let Some(cursors) = file.history.undo(&mut file.buffer, &mut file.highlight, &mut file.format) else { return; };
+
+        file.cursors = cursors;
+        file.clean   = file.history.is_saved();
+
+        self.snap_to_cursor();
+    }
+
+    pub fn redo(&mut self) {
+        let Some(file) = self.file.as_ref() else { return; };
+        let      file  = self.files.get_mut(file).unwrap();
+
+        let Some(cursors) = file.history.redo(&mut file.buffer, &mut file.highlight, &mut file.format) else { return; };
+
+        file.cursors = cursors;
+        file.clean   = file.history.is_saved();
+
+        self.snap_to_cursor();
+    }
+}
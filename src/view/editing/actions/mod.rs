@@ -1,7 +1,10 @@
 // mochou-p/text-editor/src/view/editing/actions/mod.rs
 
+pub mod clipboard;
 pub mod cursor;
 pub mod editor;
 pub mod file;
+pub mod history;
+pub mod search;
 pub mod typing;
 pub mod view;
@@ -0,0 +1,236 @@
+// mochou-p/text-editor/src/view/editing/actions/search.rs
+
+use termion::event::{Event, Key};
+use super::super::history::StepKind;
+use super::super::search::{Field, Search};
+use crate::Editor;
+use crate::message::Message;
+use crate::view::View;
+
+
+impl super::super::Editing {
+    /// opens the search prompt, seeded with a one-line selection if there is one
+    pub fn find(&mut self) {
+        let Some(file) = self.file.as_ref() else { return; };
+        let      file  = &self.files[file];
+
+        let mut query = self.query.clone();
+
+        if let Some((start, end)) = file.cursors[0].selection() && start.1 == end.1 {
+            query.text = file.buffer.slice(start.0, start.1, end.0, end.1);
+        }
+
+        let mut search = Search::new(query, file.cursors.clone(), self.scroll());
+        search.update(&file.buffer);
+
+        self.search = Some(search);
+        self.select_match();
+    }
+
+    /// returns whether the prompt took the event,
+    /// anything it does not know closes it and goes on to the editor
+    pub fn search_event(&mut self, editor: &mut Editor, event: &Event) -> bool {
+        match event {
+            Event::Key(key) => match key {
+                Key::Esc         => { self.cancel_search (      ); },
+                Key::Ctrl('f')   => { self.accept_search (editor); },
+                Key::Char('\n')  => { self.enter_search  (      ); },
+                Key::Char('\t')  => { self.switch_field  (      ); },
+                Key::Down        => { self.step_match    (true  ); },
+                Key::Up          => { self.step_match    (false ); },
+                Key::Alt('a')    => { self.replace_all   (editor); },
+                Key::Alt('c')    => { self.edit_search(|search| search.query.case_sensitive ^= true); },
+                Key::Alt('w')    => { self.edit_search(|search| search.query.whole_word     ^= true); },
+                Key::Alt('r')    => { self.edit_search(|search| search.query.regex          ^= true); },
+                Key::Alt('s')    => { self.edit_search(|search| search.in_scope ^= !search.scope.is_empty()); },
+                Key::Alt('p')    => {
+                    if let Some(search) = self.search.as_mut() {
+                        search.query.preserve_case ^= true;
+                    }
+                },
+                Key::Backspace   => { self.edit_field(None     ); },
+                Key::Char(ch)    => { self.edit_field(Some(*ch)); },
+                _                => { self.accept_search(editor); return false; }
+            },
+            // NOTE: shift + enter, only sent by terminals with modifyOtherKeys or the kitty protocol
+            Event::Unsupported(bytes) if matches!(bytes.as_slice(), b"\x1B[27;2;13~" | b"\x1B[13;2u") => {
+                self.step_match(false);
+            },
+            _ => { self.accept_search(editor); return false; }
+        }
+
+        true
+    }
+
+    fn edit_search(&mut self, f: impl FnOnce(&mut Search)) {
+        let Some(file)   = self.file.as_ref()   else { return; };
+        let Some(search) = self.search.as_mut() else { return; };
+
+        f(search);
+        search.update(&self.files[file].buffer);
+
+        self.select_match();
+    }
+
+    /// types into or erases from the focused field
+    fn edit_field(&mut self, ch: Option<char>) {
+        let Some(search) = self.search.as_mut() else { return; };
+
+        if search.field == Field::Replace {
+            match ch {
+                Some(ch) => search.query.replace.push(ch),
+                None     => { search.query.replace.pop(); }
+            }
+
+            return;
+        }
+
+        self.edit_search(|search| match ch {
+            Some(ch) => search.query.text.push(ch),
+            None     => { search.query.text.pop(); }
+        });
+    }
+
+    /// moves between the find and replace fields, showing the latter
+    fn switch_field(&mut self) {
+        let Some(search) = self.search.as_mut() else { return; };
+
+        search.replacing = true;
+        search.field     = if search.field == Field::Find { Field::Replace } else { Field::Find };
+
+        self.snap_to_cursor();
+    }
+
+    fn enter_search(&mut self) {
+        let Some(search) = self.search.as_ref() else { return; };
+
+        if search.field == Field::Replace {
+            self.replace_current();
+        } else {
+            self.step_match(true);
+        }
+    }
+
+    fn step_match(&mut self, forward: bool) {
+        let Some(search) = self.search.as_mut() else { return; };
+
+        search.step(forward);
+        self.select_match();
+    }
+
+    fn replace_current(&mut self) {
+        let Some(i) = self.search.as_ref().and_then(|search| search.current) else { return; };
+        self.replace_matches(&[i]);
+    }
+
+    fn replace_all(&mut self, editor: &mut Editor) {
+        let Some(search) = self.search.as_ref() else { return; };
+
+        if self.is_readonly() {
+            editor.message = Some(Message::warning("read-only, nothing replaced"));
+            return;
+        }
+
+        let indices  = (0..search.matches.len()).collect::<Vec<usize>>();
+        let replaced = self.replace_matches(&indices);
+
+        editor.message = Some(Message::info(format!("replaced {replaced} of {} matches", indices.len())));
+    }
+
+    /// replaces them all in one undo step, then carries on from where the cursors ended up,
+    /// returns how many actually changed
+    fn replace_matches(&mut self, indices: &[usize]) -> usize {
+        let Some(search) = self.search.as_mut() else { return 0; };
+        let Some(file)   = self.file.as_ref()   else { return 0; };
+        let      file    = self.files.get_mut(file).unwrap();
+
+        if file.readonly {
+            return 0;
+        }
+
+        let mut replaced = 0;
+        let mut lines    = Vec::new();
+
+        let replacements = search.replacements(&file.buffer, indices);
+
+        file.begin(StepKind::Other);
+
+        // NOTE: back to front, so the positions of earlier ones stay valid
+        for ((start, end), text) in replacements.into_iter().rev() {
+            // NOTE: no-op edits would mark the file as changed
+            if file.buffer.slice(start.0, start.1, end.0, end.1) == text {
+                continue;
+            }
+
+            file.remove(start.0, start.1, end.0, end.1);
+            let replaced_end = file.insert(start.0, start.1, &text);
+            replaced        += 1;
+
+            // NOTE: going back to front, the ones on a line come one after another
+            if lines.last() != Some(&start.1) {
+                lines.push(start.1);
+            }
+
+            for cursor in &mut search.scope {
+                cursor.shift_removed (start, end         );
+                cursor.shift_inserted(start, replaced_end);
+            }
+        }
+
+        file.end();
+
+        for cursor in &mut file.cursors {
+            cursor.settle();
+        }
+
+        // NOTE: replacements never span lines, so only the ones replaced in need searching again
+        lines.reverse();
+
+        search.origin = file.cursors.clone();
+        search.scroll = self.view_data.scroll;
+        search.rescan(&file.buffer, &lines);
+
+        self.select_match();
+        replaced
+    }
+
+    /// closes the prompt, leaving the match selected
+    fn accept_search(&mut self, editor: &mut Editor) {
+        let Some(search) = self.search.take() else { return; };
+
+        if let Some(i) = search.current {
+            editor.message = Some(Message::info(format!("match {} of {}", i + 1, search.matches.len())));
+        }
+
+        self.query = search.query;
+    }
+
+    /// closes the prompt, going back to where it was opened
+    fn cancel_search(&mut self) {
+        let Some(search) = self.search.take() else { return; };
+        let Some(file)   = self.file.as_ref()  else { return; };
+
+        self.files.get_mut(file).unwrap().cursors = search.origin;
+        *self.scroll_mut()                        = search.scroll;
+        self.query                                = search.query;
+    }
+
+    /// selects the current match, or goes back to the origin if there is none
+    fn select_match(&mut self) {
+        let Some(search) = self.search.as_ref() else { return; };
+        let Some(file)   = self.file.as_ref()   else { return; };
+        let      file    = self.files.get_mut(file).unwrap();
+
+        let Some((start, end)) = search.current.map(|i| search.matches[i]) else {
+            file.cursors          = search.origin.clone();
+            self.view_data.scroll = search.scroll;
+            return;
+        };
+
+        file.cursors.truncate(1);
+        file.cursors[0].anchor = Some(start);
+        file.cursors[0].move_to(end);
+
+        self.snap_to_cursor();
+    }
+}
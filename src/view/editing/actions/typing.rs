@@ -1,200 +1,380 @@
 // mochou-p/text-editor/src/view/editing/actions/typing.rs
 
-use crate::utils::{self, Utf8, Utf8Mut, word};
+use super::super::File;
+use super::super::history::StepKind;
+use crate::config::PreferenceMask;
+use crate::utils::{self, word};
 
 
 impl super::super::Editing {
     pub fn newline(&mut self) {
         let Some(file) = self.file.as_ref() else { return; };
         let      file  = self.files.get_mut(file).unwrap();
-        file.clean     = false;
+        file.begin(StepKind::Other);
 
-        for cursor in &mut file.cursors {
-            let trail = file.lines[cursor.y as usize].utf8_split_off(cursor.x);
+        for i in 0..file.cursors.len() {
+            file.erase_selection_at(i);
+
+            let mut cursor = file.cursors[i];
+
+            file.insert(cursor.x, cursor.y, "\n");
 
             cursor.x       = 0;
             cursor.last_x  = cursor.x;
             cursor.y      += 1;
 
-            file.lines.insert(cursor.y as usize, trail);
+            file.cursors[i] = cursor;
         }
 
+        file.end();
         self.snap_to_cursor();
     }
 
-    pub fn tab(&mut self) {
+    /// spaces up to the next multiple of `width`
+    pub fn tab(&mut self, width: usize) {
         let Some(file) = self.file.as_ref() else { return; };
         let      file  = self.files.get_mut(file).unwrap();
-        file.clean     = false;
+        file.begin(StepKind::Other);
 
-        for cursor in &mut file.cursors {
-            let count = 4 - (cursor.x as usize % 4);
+        for i in 0..file.cursors.len() {
+            file.erase_selection_at(i);
 
-            file.lines[cursor.y as usize].utf8_insert_str(cursor.x, &(" ".repeat(count)));
+            let mut cursor = file.cursors[i];
+            let     count  = width - (cursor.x as usize % width);
+
+            file.insert(cursor.x, cursor.y, &" ".repeat(count));
 
             cursor.x += count as isize;
+
+            file.cursors[i] = cursor;
         }
 
+        file.end();
         self.snap_to_cursor();
     }
 
     pub fn character(&mut self, ch: char) {
         let Some(file) = self.file.as_ref() else { return; };
         let      file  = self.files.get_mut(file).unwrap();
-        file.clean     = false;
+        file.begin(StepKind::Typing);
 
-        for cursor in &mut file.cursors {
-            file.lines[cursor.y as usize].utf8_insert(cursor.x, ch);
+        for i in 0..file.cursors.len() {
+            file.erase_selection_at(i);
+
+            let mut cursor = file.cursors[i];
+
+            file.insert(cursor.x, cursor.y, ch.encode_utf8(&mut [0; 4]));
 
             cursor.x      += 1;
             cursor.last_x  = cursor.x;
+
+            file.cursors[i] = cursor;
         }
 
+        file.end();
         self.snap_to_cursor();
     }
 
-    pub fn erase_left(&mut self) {
+    pub fn erase_left(&mut self, preferences: PreferenceMask) {
         let Some(file) = self.file.as_ref() else { return; };
         let      file  = self.files.get_mut(file).unwrap();
+        let      wraps = preferences.has(PreferenceMask::TYPING_ERASE_CHARACTER_LEFT_WRAPS_AT_LINE_BOUNDARY);
+        file.begin(StepKind::Other);
 
-        for cursor in &mut file.cursors {
-            if cursor.x == 0 {
-                if cursor.y != 0 {
-                    let line = file.lines.remove(cursor.y as usize);
-
-                    cursor.y -= 1;
-                    cursor.x  = file.lines[cursor.y as usize].utf8_len();
-
-                    file.lines[cursor.y as usize].push_str(&line);
-                    file.clean = false;
-                }
-            } else {
-                cursor.x -= 1;
-
-                file.lines[cursor.y as usize].utf8_remove(cursor.x);
-                file.clean = false;
+        for i in 0..file.cursors.len() {
+            if !file.erase_selection_at(i) {
+                file.erase_left_at(i, wraps);
             }
-
-            cursor.last_x = cursor.x;
         }
 
+        file.end();
         self.snap_to_cursor();
     }
 
-    pub fn erase_right(&mut self) {
+    pub fn erase_right(&mut self, preferences: PreferenceMask) {
         let Some(file) = self.file.as_ref() else { return; };
         let      file  = self.files.get_mut(file).unwrap();
+        let      wraps = preferences.has(PreferenceMask::TYPING_ERASE_CHARACTER_RIGHT_WRAPS_AT_LINE_BOUNDARY);
+        file.begin(StepKind::Other);
 
-        for cursor in &mut file.cursors {
-            if cursor.x == file.lines[cursor.y as usize].utf8_len() {
-                if cursor.y != (file.lines.len() - 1) as isize {
-                    let line = file.lines.remove((cursor.y + 1) as usize);
-
-                    file.lines[cursor.y as usize].push_str(&line);
-                    file.clean = false;
-                }
-            } else {
-                file.lines[cursor.y as usize].utf8_remove(cursor.x);
-                file.clean = false;
+        for i in 0..file.cursors.len() {
+            if !file.erase_selection_at(i) {
+                file.erase_right_at(i, wraps);
             }
-
-            cursor.last_x = cursor.x;
         }
 
+        file.end();
         self.snap_to_cursor();
     }
 
     pub fn move_line_up(&mut self) {
         let Some(file) = self.file.as_ref() else { return; };
         let      file  = self.files.get_mut(file).unwrap();
+        file.begin(StepKind::Other);
 
-        for cursor in &mut file.cursors {
-            if cursor.y != 0 {
-                file.lines.swap(cursor.y as usize, (cursor.y - 1) as usize);
-                cursor.y -= 1;
-
-                file.clean = false;
-            }
-
-            cursor.last_x = cursor.x;
-        }
+        file.move_lines(true);
 
+        file.end();
         self.snap_to_cursor();
     }
 
     pub fn move_line_down(&mut self) {
         let Some(file) = self.file.as_ref() else { return; };
         let      file  = self.files.get_mut(file).unwrap();
+        file.begin(StepKind::Other);
 
-        for cursor in &mut file.cursors {
-            if cursor.y != (file.lines.len() - 1) as isize {
-                file.lines.swap(cursor.y as usize, (cursor.y + 1) as usize);
-                cursor.y += 1;
-
-                file.clean = false;
-            }
-
-            cursor.last_x = cursor.x;
-        }
+        file.move_lines(false);
 
+        file.end();
         self.snap_to_cursor();
     }
 
-    pub fn erase_prev_word(&mut self) {
+    pub fn erase_prev_word(&mut self, preferences: PreferenceMask) {
         let Some(file) = self.file.as_ref() else { return; };
         let      file  = self.files.get_mut(file).unwrap();
+        let      wraps = preferences.has(PreferenceMask::TYPING_ERASE_WORD_LEFT_WRAPS_AT_LINE_BOUNDARY);
+        file.begin(StepKind::Other);
+
+        for i in 0..file.cursors.len() {
+            if file.erase_selection_at(i) {
+                continue;
+            }
+
+            let mut cursor = file.cursors[i];
 
-        for cursor in &mut file.cursors {
             if cursor.x == 0 {
-                self.erase_left();
-                return;
+                file.erase_left_at(i, wraps);
+                continue;
             }
 
-            let line  = &mut file.lines[cursor.y as usize];
-            let start = line.chars().nth((cursor.x - 1) as usize).unwrap();
+            let line  = file.buffer.line(cursor.y);
+            let start = line.char_at(cursor.x - 1).unwrap();
 
             let end = if utils::is_alphanumericx(start) {
-                word::to_left(line, cursor.x, |ch| !utils::is_alphanumericx(ch))
+                word::to_left(line.as_str(), cursor.x, |ch| !utils::is_alphanumericx(ch))
             } else {
-                word::to_left(line, cursor.x, |ch| ch != start)
+                word::to_left(line.as_str(), cursor.x, |ch| ch != start)
             };
 
             let old_x     = cursor.x;
             cursor.x      = end.map(|i| i+1).unwrap_or(0);
             cursor.last_x = cursor.x;
 
-            line.utf8_drain(cursor.x, old_x);
+            file.remove(cursor.x, cursor.y, old_x, cursor.y);
+
+            file.cursors[i] = cursor;
         }
 
+        file.end();
         self.snap_to_cursor();
     }
 
-    pub fn erase_next_word(&mut self) {
+    pub fn erase_next_word(&mut self, preferences: PreferenceMask) {
         let Some(file) = self.file.as_ref() else { return; };
         let      file  = self.files.get_mut(file).unwrap();
+        let      wraps = preferences.has(PreferenceMask::TYPING_ERASE_WORD_RIGHT_WRAPS_AT_LINE_BOUNDARY);
+        file.begin(StepKind::Other);
 
-        for cursor in &mut file.cursors {
-            let line = &file.lines[cursor.y as usize];
+        for i in 0..file.cursors.len() {
+            if file.erase_selection_at(i) {
+                continue;
+            }
+
+            let mut cursor = file.cursors[i];
+            let     line   = file.buffer.line(cursor.y);
 
-            if cursor.x == line.utf8_len() {
-                self.erase_right();
-                return;
+            if cursor.x == line.len() {
+                file.erase_right_at(i, wraps);
+                continue;
             }
 
-            let line  = &mut file.lines[cursor.y as usize];
-            let start = line.chars().nth(cursor.x as usize).unwrap();
+            let start = line.char_at(cursor.x).unwrap();
 
             let end = if utils::is_alphanumericx(start) {
-                word::to_right(line, cursor.x, |ch| !utils::is_alphanumericx(ch))
+                word::to_right(line.as_str(), cursor.x, |ch| !utils::is_alphanumericx(ch))
             } else {
-                word::to_right(line, cursor.x, |ch| ch != start)
+                word::to_right(line.as_str(), cursor.x, |ch| ch != start)
             };
 
+            let end       = end.unwrap_or(line.len());
             cursor.last_x = cursor.x;
 
-            line.utf8_drain(cursor.x, end.unwrap_or(line.utf8_len()));
+            file.remove(cursor.x, cursor.y, end, cursor.y);
+
+            file.cursors[i] = cursor;
         }
 
+        file.end();
         self.snap_to_cursor();
     }
 }
+
+impl File {
+    /// `wraps` joins the line onto the one above when at its start
+    fn erase_left_at(&mut self, i: usize, wraps: bool) {
+        let mut cursor = self.cursors[i];
+
+        if cursor.x == 0 {
+            if wraps && cursor.y != 0 {
+                let above = self.buffer.line(cursor.y - 1).len();
+
+                self.remove(above, cursor.y - 1, 0, cursor.y);
+
+                cursor.y -= 1;
+                cursor.x  = above;
+            }
+        } else {
+            cursor.x -= 1;
+
+            self.remove(cursor.x, cursor.y, cursor.x + 1, cursor.y);
+        }
+
+        cursor.last_x = cursor.x;
+
+        self.cursors[i] = cursor;
+    }
+
+    /// swaps every line with a cursor on it with the one above, or below, each line only once
+    fn move_lines(&mut self, up: bool) {
+        let mut lines = self.cursor_order().into_iter().map(|i| self.cursors[i].y).collect::<Vec<isize>>();
+        lines.dedup();
+
+        if !up {
+            lines.reverse();
+        }
+
+        // NOTE: a line against the edge stays, and so does one against a line that stayed
+        let (mut edge, step) = if up { (0, 1) } else { (self.buffer.line_count() - 1, -1) };
+
+        let     cursors = self.cursors.clone();
+        let mut swaps   = Vec::new();
+
+        for y in lines {
+            if y == edge {
+                edge += step;
+                continue;
+            }
+
+            let above = if up { y - 1 } else { y };
+            let len   = self.buffer.line(above    ).len();
+            let below = self.buffer.line(above + 1).len();
+            let line  = self.remove(len, above, below, above + 1);
+
+            self.insert(0, above, &format!("{}\n", &line[1..]));
+            swaps.push(above);
+        }
+
+        // NOTE: the edits drag cursors around, so they go back where they were and follow their lines
+        self.cursors = cursors;
+
+        for cursor in &mut self.cursors {
+            for above in &swaps {
+                if cursor.y == *above {
+                    cursor.y += 1;
+                } else if cursor.y == above + 1 {
+                    cursor.y -= 1;
+                }
+            }
+
+            cursor.anchor = None;
+            cursor.last_x = cursor.x;
+        }
+    }
+
+    /// `wraps` joins the line below onto this one when at its end
+    fn erase_right_at(&mut self, i: usize, wraps: bool) {
+        let mut cursor = self.cursors[i];
+        let     len    = self.buffer.line(cursor.y).len();
+
+        if cursor.x == len {
+            if wraps && cursor.y != self.buffer.line_count() - 1 {
+                self.remove(cursor.x, cursor.y, 0, cursor.y + 1);
+            }
+        } else {
+            self.remove(cursor.x, cursor.y, cursor.x + 1, cursor.y);
+        }
+
+        cursor.last_x = cursor.x;
+
+        self.cursors[i] = cursor;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::PreferenceMask;
+    use crate::view::Editing;
+    use super::super::super::with;
+
+
+    #[test]
+    fn erase_character_left_wraps_at_line_boundary() {
+        for (on, text, cursor) in [(true, "abcd", (2, 0)), (false, "ab\ncd", (0, 1))] {
+            let mut view = Editing::with_text("ab\ncd", (0, 1), 10);
+            view.erase_left(with(PreferenceMask::TYPING_ERASE_CHARACTER_LEFT_WRAPS_AT_LINE_BOUNDARY, on));
+
+            assert_eq!(view.text(),   text,   "on: {on}");
+            assert_eq!(view.cursor(), cursor, "on: {on}");
+        }
+    }
+
+    #[test]
+    fn erase_character_right_wraps_at_line_boundary() {
+        for (on, text) in [(true, "abcd"), (false, "ab\ncd")] {
+            let mut view = Editing::with_text("ab\ncd", (2, 0), 10);
+            view.erase_right(with(PreferenceMask::TYPING_ERASE_CHARACTER_RIGHT_WRAPS_AT_LINE_BOUNDARY, on));
+
+            assert_eq!(view.text(),   text,   "on: {on}");
+            assert_eq!(view.cursor(), (2, 0), "on: {on}");
+        }
+    }
+
+    #[test]
+    fn erase_word_left_wraps_at_line_boundary() {
+        for (on, text, cursor) in [(true, "abcd", (2, 0)), (false, "ab\ncd", (0, 1))] {
+            let mut view = Editing::with_text("ab\ncd", (0, 1), 10);
+            view.erase_prev_word(with(PreferenceMask::TYPING_ERASE_WORD_LEFT_WRAPS_AT_LINE_BOUNDARY, on));
+
+            assert_eq!(view.text(),   text,   "on: {on}");
+            assert_eq!(view.cursor(), cursor, "on: {on}");
+        }
+    }
+
+    #[test]
+    fn erase_word_right_wraps_at_line_boundary() {
+        for (on, text) in [(true, "abcd"), (false, "ab\ncd")] {
+            let mut view = Editing::with_text("ab\ncd", (2, 0), 10);
+            view.erase_next_word(with(PreferenceMask::TYPING_ERASE_WORD_RIGHT_WRAPS_AT_LINE_BOUNDARY, on));
+
+            assert_eq!(view.text(),   text,   "on: {on}");
+            assert_eq!(view.cursor(), (2, 0), "on: {on}");
+        }
+    }
+
+    #[test]
+    fn move_lines_with_many_cursors() {
+        let cases = [
+            (true,  (0, 1), (0, 2), "B\nC\nA\nD", [(0, 1), (0, 0)]),
+            (true,  (0, 2), (0, 1), "B\nC\nA\nD", [(0, 0), (0, 1)]),
+            (false, (0, 1), (0, 2), "A\nD\nB\nC", [(0, 3), (0, 2)]),
+            (false, (0, 2), (0, 1), "A\nD\nB\nC", [(0, 2), (0, 3)]),
+            (true,  (0, 1), (1, 1), "B\nA\nC\nD", [(1, 0), (0, 0)]),
+            (false, (0, 0), (0, 1), "C\nA\nB\nD", [(0, 2), (0, 1)]),
+            (true,  (0, 0), (0, 1), "A\nB\nC\nD", [(0, 1), (0, 0)])
+        ];
+
+        for (up, first, added, text, cursors) in cases {
+            let mut view = Editing::with_text("A\nB\nC\nD", first, 10);
+            view.add_cursor_at(added.0 as u16, added.1 as u16);
+
+            if up {
+                view.move_line_up();
+            } else {
+                view.move_line_down();
+            }
+
+            assert_eq!(view.text(),    text,    "up: {up}, cursors: {first:?} {added:?}");
+            assert_eq!(view.cursors(), cursors, "up: {up}, cursors: {first:?} {added:?}");
+        }
+    }
+}
@@ -1,6 +1,9 @@
 // mochou-p/text-editor/src/view/editing/actions/view.rs
 
-use crate::view::View;
+use std::path::PathBuf;
+use crate::Editor;
+use crate::message::Message;
+use crate::view::{View, Browsing, Hex, Searching};
 
 
 impl super::super::Editing {
@@ -12,10 +15,34 @@ impl super::super::Editing {
         }
     }
 
+    /// opens the project search panel under this view
+    pub fn search_project(&mut self, editor: &mut Editor) {
+        let root = editor.view::<Browsing, PathBuf>(|_, view| view.current_dir().clone());
+        editor.view::<Searching, ()>(|editor, view| view.open(editor, &mut self.view_data, root));
+    }
+
+    /// hands the open file over to `Hex`, in the same spot
+    pub fn open_in_hex(&mut self, editor: &mut Editor) {
+        let Some(path) = self.file.clone() else { return; };
+
+        if !self.files[&path].clean {
+            editor.message = Some(Message::warning("unsaved changes, save them before switching to hex"));
+            return;
+        }
+
+        if editor.view::<Hex, bool>(|editor, view| view.open(editor, &mut self.view_data, &path)) {
+            self.files.remove(&path);
+            self.file   = None;
+            self.search = None;
+
+            editor.view = Hex::name();
+        }
+    }
+
     fn scroll_down(&mut self) {
         let Some(file) = self.file.as_ref() else { return; };
 
-        if self.scroll().y != (self.files[file].lines.len() - 1) as isize {
+        if self.scroll().y != self.files[file].buffer.line_count() - 1 {
             self.scroll_mut().y += 1;
         }
     }
@@ -0,0 +1,124 @@
+// mochou-p/text-editor/src/view/editing/cursor.rs
+
+type Position = (isize, isize);
+
+
+#[derive(Default, Clone, Copy, PartialEq)]
+pub struct Cursor {
+    pub last_x: isize,
+    pub x:      isize,
+    pub y:      isize,
+    pub anchor: Option<Position>
+}
+
+impl Cursor {
+    /// drops or plants the anchor before a movement
+    pub fn select(&mut self, select: bool) {
+        if select {
+            self.anchor.get_or_insert((self.x, self.y));
+        } else {
+            self.anchor = None;
+        }
+    }
+
+    /// drops the anchor if the movement ended up on it
+    pub fn settle(&mut self) {
+        if self.anchor == Some((self.x, self.y)) {
+            self.anchor = None;
+        }
+    }
+
+    /// ordered `(start, end)` of the selection, as `(x, y)`
+    pub fn selection(&self) -> Option<(Position, Position)> {
+        let anchor = self.anchor?;
+        let caret  = (self.x, self.y);
+
+        if before(anchor, caret) {
+            Some((anchor, caret))
+        } else {
+            Some((caret, anchor))
+        }
+    }
+
+    pub fn move_to(&mut self, (x, y): Position) {
+        self.x      = x;
+        self.y      = y;
+        self.last_x = x;
+    }
+
+    /// keeps the cursor on the same text after an insertion
+    pub fn shift_inserted(&mut self, start: Position, end: Position) {
+        self.shift(|position| {
+            if before(position, start) {
+                position
+            } else if position.1 == start.1 {
+                (end.0 + position.0 - start.0, end.1)
+            } else {
+                (position.0, position.1 + end.1 - start.1)
+            }
+        });
+    }
+
+    /// keeps the cursor on the same text after a removal
+    pub fn shift_removed(&mut self, start: Position, end: Position) {
+        self.shift(|position| {
+            if !before(start, position) {
+                position
+            } else if !before(end, position) {
+                start
+            } else if position.1 == end.1 {
+                (start.0 + position.0 - end.0, start.1)
+            } else {
+                (position.0, position.1 - (end.1 - start.1))
+            }
+        });
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        let (start,       end      ) = self .span();
+        let (other_start, other_end) = other.span();
+
+        (start, end) == (other_start, other_end)
+        ||
+        (before(start, other_end) && before(other_start, end))
+    }
+
+    /// grows the selection to also cover `other`
+    pub fn absorb(&mut self, other: &Self) {
+        let (start,       end      ) = self .span();
+        let (other_start, other_end) = other.span();
+
+        if (start, end) == (other_start, other_end) {
+            return;
+        }
+
+        let start = if before(other_start, start) { other_start } else { start };
+        let end   = if before(end, other_end)     { other_end   } else { end   };
+
+        if (self.x, self.y) == self.span().1 {
+            self.anchor = Some(start);
+            self.move_to(end);
+        } else {
+            self.anchor = Some(end);
+            self.move_to(start);
+        }
+    }
+
+    fn span(&self) -> (Position, Position) {
+        self.selection().unwrap_or(((self.x, self.y), (self.x, self.y)))
+    }
+
+    fn shift(&mut self, f: impl Fn(Position) -> Position) {
+        let caret = f((self.x, self.y));
+
+        if caret != (self.x, self.y) {
+            self.move_to(caret);
+        }
+
+        self.anchor = self.anchor.map(f);
+    }
+}
+
+fn before(a: Position, b: Position) -> bool {
+    (a.1, a.0) < (b.1, b.0)
+}
@@ -0,0 +1,115 @@
+// mochou-p/text-editor/src/view/editing/format.rs
+
+//! how a file was laid out on disk, so saving writes it back the same way
+
+use crate::Buffer;
+use crate::utils::encoding::{self, Encoding};
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Ending {
+    Lf,
+    Crlf
+}
+
+#[derive(Clone, Copy)]
+pub struct Format {
+    pub ending:   Ending,
+    pub bom:      bool,
+    pub encoding: Encoding
+}
+
+/// what did not go smoothly while reading
+#[derive(Clone, Copy, PartialEq)]
+pub enum Trouble {
+    // NOTE: not utf-8, so it got read as windows-1252
+    Fallback,
+    // NOTE: read byte for byte, not meant to be edited as text
+    Binary,
+    // NOTE: the given encoding did not fit, so some chars got replaced
+    Lossy,
+    // NOTE: both lf and crlf, the next save writes only the one it went with
+    Mixed
+}
+
+impl Ending {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf   => "\n",
+            Self::Crlf => "\r\n"
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Lf   => "LF",
+            Self::Crlf => "CRLF"
+        }
+    }
+}
+
+impl Format {
+    /// decodes `bytes` into a buffer, remembering its encoding, line endings and bom,
+    /// the encoding gets guessed unless one is given
+    // NOTE: the final newline needs no flag, it is the empty last line of the buffer
+    pub fn read(bytes: &[u8], encoding: Option<Encoding>) -> (Self, Buffer, Option<Trouble>) {
+        let guess = Encoding::detect(bytes);
+
+        let (encoding, mut trouble) = match encoding {
+            Some(encoding) => (encoding, None),
+            None           => match guess {
+                Encoding::Utf16Le | Encoding::Utf16Be => (guess, None),
+                _ if encoding::is_binary(bytes)       => (Encoding::Windows1252, Some(Trouble::Binary)),
+                Encoding::Windows1252                 => (guess, Some(Trouble::Fallback)),
+                _                                     => (guess, None)
+            }
+        };
+
+        let (text, bom, lossy) = encoding.decode(bytes);
+
+        if lossy {
+            trouble = Some(Trouble::Lossy);
+        }
+
+        let newlines = text.matches('\n').count();
+        let crlfs    = text.matches("\r\n").count();
+
+        // NOTE: mixed files go with the majority, and get normalised on save
+        let ending = if crlfs * 2 > newlines { Ending::Crlf } else { Ending::Lf };
+
+        if crlfs != 0 && crlfs != newlines {
+            trouble = trouble.or(Some(Trouble::Mixed));
+        }
+
+        let mut parts = text.split('\n').peekable();
+        let mut lines = Vec::new();
+
+        while let Some(part) = parts.next() {
+            let part = if parts.peek().is_some() {
+                part.strip_suffix('\r').unwrap_or(part)
+            } else {
+                part
+            };
+
+            lines.push(String::from(part));
+        }
+
+        (Self { ending, bom, encoding }, Buffer::from_lines(lines.into_iter()), trouble)
+    }
+
+    pub fn write(&self, buffer: &Buffer) -> Result<Vec<u8>, String> {
+        self.encoding
+            .encode(&buffer.join(self.ending.as_str()), self.bom)
+            .map_err(|ch| format!("{ch:?} has no {} encoding", self.encoding.name()))
+    }
+
+    /// short description for indicators, like `UTF-16LE CRLF BOM`
+    pub fn label(&self) -> String {
+        format!(
+            "{} {}{}",
+            self.encoding.name(),
+            self.ending.name(),
+            if self.bom { " BOM" } else { "" }
+        )
+    }
+}
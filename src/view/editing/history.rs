@@ -0,0 +1,193 @@
+// mochou-p/text-editor/src/view/editing/history.rs
+
+use super::Cursor;
+use super::format::{Ending, Format};
+use crate::Buffer;
+use crate::highlight::Highlighter;
+
+
+#[derive(Clone)]
+pub enum Edit {
+    Insert { x: isize, y: isize, text: String },
+    Remove { x: isize, y: isize, text: String },
+    // NOTE: lf and crlf swapped, its own inverse
+    Ending
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum StepKind {
+    Typing,
+    Other
+}
+
+struct Step {
+    kind:   StepKind,
+    edits:  Vec<Edit>,
+    before: Vec<Cursor>,
+    after:  Vec<Cursor>
+}
+
+pub struct History {
+    undos:   Vec<Step>,
+    redos:   Vec<Step>,
+    pending: Option<Step>,
+    // NOTE: how many undos deep the file matches the one on disk
+    saved:   Option<usize>,
+    merge:   bool
+}
+
+impl Edit {
+    pub fn apply(&self, buffer: &mut Buffer, highlight: &mut Highlighter, format: &mut Format) {
+        match self {
+            Self::Insert { x, y, text } => {
+                buffer.insert(*x, *y, text);
+                highlight.edited(*y as usize, 0, text.matches('\n').count());
+            },
+            Self::Remove { x, y, text } => {
+                let (end_x, end_y) = Self::end_of(*x, *y, text);
+                buffer.remove(*x, *y, end_x, end_y);
+                highlight.edited(*y as usize, text.matches('\n').count(), 0);
+            },
+            Self::Ending => {
+                format.ending = match format.ending {
+                    Ending::Lf   => Ending::Crlf,
+                    Ending::Crlf => Ending::Lf
+                };
+            }
+        }
+    }
+
+    fn inverse(&self) -> Self {
+        match self.clone() {
+            Self::Insert { x, y, text } => Self::Remove { x, y, text },
+            Self::Remove { x, y, text } => Self::Insert { x, y, text },
+            Self::Ending                => Self::Ending
+        }
+    }
+
+    fn end_of(x: isize, y: isize, text: &str) -> (isize, isize) {
+        let newlines = text.matches('\n').count() as isize;
+        let last     = text.rsplit('\n').next().unwrap().chars().count() as isize;
+
+        if newlines == 0 {
+            (x + last, y)
+        } else {
+            (last, y + newlines)
+        }
+    }
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            undos:   Vec::new(),
+            redos:   Vec::new(),
+            pending: None,
+            saved:   Some(0),
+            merge:   false
+        }
+    }
+
+    pub fn begin(&mut self, kind: StepKind, cursors: &[Cursor]) {
+        self.pending = Some(Step {
+            kind,
+            edits:  Vec::new(),
+            before: cursors.to_vec(),
+            after:  Vec::new()
+        });
+    }
+
+    pub fn record(&mut self, edit: Edit) {
+        if let Some(step) = self.pending.as_mut() {
+            step.edits.push(edit);
+        }
+    }
+
+    pub fn end(&mut self, cursors: &[Cursor]) {
+        let Some(mut step) = self.pending.take() else { return; };
+
+        if step.edits.is_empty() {
+            return;
+        }
+
+        step.after = cursors.to_vec();
+
+        if self.saved.is_some_and(|saved| saved > self.undos.len()) {
+            self.saved = None;
+        }
+        self.redos.clear();
+
+        let mergeable = self.merge
+            && step.kind == StepKind::Typing
+            && self.saved != Some(self.undos.len())
+            && self.undos.last().is_some_and(|last| last.kind == StepKind::Typing && last.after == step.before);
+
+        // NOTE: swapping the line ending back is no step, so it can get back to what was saved
+        let cancels = self.saved != Some(self.undos.len())
+            && Self::only_ending(&step)
+            && self.undos.last().is_some_and(Self::only_ending);
+
+        self.merge = step.kind == StepKind::Typing;
+
+        if cancels {
+            self.undos.pop();
+            return;
+        }
+
+        if mergeable {
+            let last = self.undos.last_mut().unwrap();
+
+            last.edits.append(&mut step.edits);
+            last.after = step.after;
+        } else {
+            self.undos.push(step);
+        }
+    }
+
+    pub fn undo(&mut self, buffer: &mut Buffer, highlight: &mut Highlighter, format: &mut Format) -> Option<Vec<Cursor>> {
+        let step = self.undos.pop()?;
+
+        for edit in step.edits.iter().rev() {
+            edit.inverse().apply(buffer, highlight, format);
+        }
+
+        let cursors = step.before.clone();
+        self.redos.push(step);
+        self.merge = false;
+
+        Some(cursors)
+    }
+
+    pub fn redo(&mut self, buffer: &mut Buffer, highlight: &mut Highlighter, format: &mut Format) -> Option<Vec<Cursor>> {
+        let step = self.redos.pop()?;
+
+        for edit in &step.edits {
+            edit.apply(buffer, highlight, format);
+        }
+
+        let cursors = step.after.clone();
+        self.undos.push(step);
+        self.merge = false;
+
+        Some(cursors)
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved = Some(self.undos.len());
+        self.merge = false;
+    }
+
+    /// for changes outside the buffer, no undo gets back to what is on disk
+    pub fn forget_saved(&mut self) {
+        self.saved = None;
+        self.merge = false;
+    }
+
+    pub fn is_saved(&self) -> bool {
+        self.saved == Some(self.undos.len())
+    }
+
+    fn only_ending(step: &Step) -> bool {
+        matches!(step.edits.as_slice(), [Edit::Ending])
+    }
+}
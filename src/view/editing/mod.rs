@@ -1,25 +1,57 @@
 // mochou-p/text-editor/src/view/editing/mod.rs
 
 mod actions;
+mod cursor;
+mod format;
+mod history;
+mod search;
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use termion::event::{Event, Key, MouseButton, MouseEvent};
-use super::{View, ViewData, Files};
-use crate::{Editor, Cursor};
-use crate::utils::{ToWith, Utf8};
+use termion::style;
+use super::{View, ViewData, Files, Hex};
+use crate::{Buffer, Editor};
+use crate::command::{Command, Run};
+use crate::highlight::Highlighter;
+use crate::keymap::Context;
+use crate::message::Message;
+use crate::utils::{self, ToWith};
+use crate::utils::encoding::Encoding;
+use crate::watcher::Stamp;
+use cursor::Cursor;
+use format::{Format, Trouble};
+use history::{Edit, History, StepKind};
+use search::{Field, Search};
+
+pub use search::Query;
 
 
 pub struct Editing {
     view_data: ViewData,
     file:      Option<PathBuf>,
-    files:     HashMap<PathBuf, File>
+    files:     HashMap<PathBuf, File>,
+    search:    Option<Search>,
+    // NOTE: the last query, to start the next search from
+    query:     Query
 }
 
 pub struct File {
-    clean:   bool,
-    cursors: Vec<Cursor>,
-    lines:   Vec<String>
+    clean:     bool,
+    // NOTE: binary, or text that did not decode cleanly, saving would mangle it,
+    //       or opened with `--readonly`
+    readonly:  bool,
+    // NOTE: how the file looked on disk when last read or saved
+    disk:      Option<Stamp>,
+    // NOTE: set once warned that saving overwrites changes made on disk
+    clobber:   bool,
+    cursors:   Vec<Cursor>,
+    buffer:    Buffer,
+    history:   History,
+    format:    Format,
+    // NOTE: by the path, and the first line if that does not tell
+    highlight: Highlighter
 }
 
 impl Editing {
@@ -27,37 +59,223 @@ impl Editing {
         Self {
             view_data: ViewData::fullscreen(),
             file:      None,
-            files:     HashMap::new()
+            files:     HashMap::new(),
+            search:    None,
+            query:     Query::default()
         }
     }
 
-    fn read_file(path: &PathBuf) -> File {
-        let     string = std::fs::read_to_string(path).unwrap();
-        let mut lines  = string.lines().map(str::to_owned).collect::<Vec<String>>();
+    /// reads `path` in `encoding`, or in whatever it looks like
+    fn read_file(path: &PathBuf, encoding: Option<Encoding>) -> Result<(File, Option<Trouble>), String> {
+        // NOTE: before reading, a change in between shows up as another change
+        let disk  = Stamp::of(path);
+        let bytes = std::fs::read(path)
+            .map_err(|error| format!("could not open {}: {error}", path.display()))?;
+
+        let (format, buffer, trouble) = Format::read(&bytes, encoding);
+        let highlight                 = Highlighter::new(path, buffer.line(0).as_str());
+
+        let file = File {
+            clean:     true,
+            readonly:  matches!(trouble, Some(Trouble::Binary | Trouble::Lossy)),
+            disk,
+            clobber:   false,
+            cursors:   vec![Cursor::default()],
+            buffer,
+            history:   History::new(),
+            format,
+            highlight
+        };
 
-        if lines.is_empty() || string.ends_with('\n') {
-            lines.push(String::new());
-        }
+        Ok((file, trouble))
+    }
+
+    /// an empty utf-8 buffer for a path with nothing on disk yet, unsaved so saving creates it
+    fn new_file(path: &Path) -> File {
+        let (format, buffer, _) = Format::read(&[], None);
+
+        let mut history = History::new();
+        history.forget_saved();
 
         File {
-            clean:   true,
-            cursors: vec![Cursor { last_x: 0, x: 0, y: 0 }],
-            lines
+            clean:     false,
+            readonly:  false,
+            disk:      None,
+            clobber:   false,
+            cursors:   vec![Cursor::default()],
+            buffer,
+            history,
+            format,
+            highlight: Highlighter::new(path, "")
+        }
+    }
+
+    fn trouble_message(trouble: Trouble, file: &File) -> Message {
+        match trouble {
+            Trouble::Fallback => Message::warning("not valid UTF-8, opened as Windows-1252"),
+            Trouble::Binary   => Message::warning("binary file, opened read-only"),
+            Trouble::Lossy    => Message::warning(format!("not valid {}, opened read-only", file.format.encoding.name())),
+            Trouble::Mixed    => Message::warning(format!("mixed line endings, saving makes them all {}", file.format.ending.name()))
         }
     }
 
+    /// makes `path` the open file, reading it first if needed, or starting a new one,
+    /// files open in `Hex` stay there, and so do new binary ones if `hex` is set
+    // NOTE: an already open file keeps its unsaved changes
+    fn switch_to(&mut self, editor: &mut Editor, path: &PathBuf, hex: bool) -> bool {
+        if editor.view::<Hex, bool>(|_, view| view.has(path)) {
+            return editor.view::<Hex, bool>(|editor, view| view.open(editor, &mut self.view_data, path));
+        }
+
+        if !self.files.contains_key(path) && !path.exists() {
+            self.files.insert(path.clone(), Self::new_file(path));
+            editor.message = Some(Message::info(format!("new file {}, created on the first save", path.display())));
+
+            if let Some(watcher) = editor.watcher.as_mut() {
+                watcher.watch(path);
+            }
+        }
+
+        if !self.files.contains_key(path) {
+            match Self::read_file(path, None) {
+                Ok((_, Some(Trouble::Binary))) if hex => {
+                    let opened = editor.view::<Hex, bool>(|editor, view| view.open(editor, &mut self.view_data, path));
+
+                    if opened {
+                        editor.message = Some(Message::warning("binary file, opened in hex, Alt+H shows it as text"));
+                    }
+
+                    return opened;
+                },
+                Ok((file, trouble)) => {
+                    editor.message = trouble.map(|trouble| Self::trouble_message(trouble, &file));
+                    self.files.insert(path.clone(), file);
+
+                    if let Some(watcher) = editor.watcher.as_mut() {
+                        watcher.watch(path);
+                    }
+                },
+                Err(error) => {
+                    editor.message = Some(Message::error(error));
+                    return false;
+                }
+            }
+        }
+
+        editor.view::<Hex, ()>(|_, view| view.hide(&mut self.view_data));
+
+        self.file   = Some(path.clone());
+        self.search = None;
+
+        true
+    }
+
     pub fn open_file_from_browser(&mut self, editor: &mut Editor, path: PathBuf) {
-        let file = Self::read_file(&path);
-        self.files.insert(path.clone(), file);
-        self.file = Some(path.clone());
+        if self.switch_to(editor, &path, true) {
+            editor.view::<Files, ()>(|_, view| view.add_file(path));
+        }
+    }
+
+    pub fn open_file_from_files(&mut self, editor: &mut Editor, path: PathBuf) {
+        self.switch_to(editor, &path, true);
+    }
+
+    /// opens a file named on the command line in a tab, at `position` if given
+    // NOTE: read-only files open as text, `Hex` has no such thing
+    pub fn open_from_args(&mut self, editor: &mut Editor, path: PathBuf, position: Option<(isize, isize)>, readonly: bool) {
+        if !self.switch_to(editor, &path, !readonly) {
+            return;
+        }
 
         editor.view::<Files, ()>(|_, view| view.add_file(path.clone()));
+
+        if self.file.as_ref() != Some(&path) {
+            return;
+        }
+
+        if readonly {
+            self.files.get_mut(&path).unwrap().readonly = true;
+        }
+
+        if let Some((x, y)) = position {
+            self.go_to(x, y);
+        }
     }
 
-    pub fn open_file_from_files(&mut self, _editor: &mut Editor, path: PathBuf) {
-        let file = Self::read_file(&path);
-        self.files.insert(path.clone(), file);
-        self.file = Some(path.clone());
+    /// opens `path` as text, even if it looks binary
+    pub fn open_as_text(&mut self, editor: &mut Editor, path: PathBuf) {
+        self.switch_to(editor, &path, false);
+    }
+
+    /// moves to a spot in the open file, dropping other cursors
+    pub fn go_to(&mut self, x: isize, y: isize) {
+        let Some(file) = self.file.as_ref() else { return; };
+        let      file  = self.files.get_mut(file).unwrap();
+
+        let y = y.clamp(0, file.buffer.line_count() - 1);
+        let x = x.clamp(0, file.buffer.line(y).len());
+
+        file.cursors = vec![Cursor::default()];
+        file.cursors[0].move_to((x, y));
+
+        self.snap_to_cursor();
+    }
+
+    /// name of the language the open file is highlighted as, if any
+    pub fn language_label(&self) -> Option<String> {
+        let file = &self.files[self.file.as_ref()?];
+
+        file.highlight.language().map(|language| String::from(language.name))
+    }
+
+    /// encoding, line ending and bom of the open file, and whether it is read-only
+    pub fn format_label(&self) -> Option<String> {
+        let file = &self.files[self.file.as_ref()?];
+
+        if file.readonly {
+            Some(format!("{} RO", file.format.label()))
+        } else {
+            Some(file.format.label())
+        }
+    }
+
+    /// the open file, and whether it has unsaved changes
+    pub fn open_file(&self) -> Option<(PathBuf, bool)> {
+        let path = self.file.as_ref()?;
+
+        Some((path.clone(), !self.files[path].clean))
+    }
+
+    pub fn file_is_open(&self) -> bool {
+        self.file.is_some()
+    }
+
+    pub fn has(&self, path: &PathBuf) -> bool {
+        self.files.contains_key(path)
+    }
+
+    /// line and column of the first cursor, counted from 1
+    pub fn cursor_label(&self) -> Option<String> {
+        let cursor = self.files[self.file.as_ref()?].cursors[0];
+
+        Some(format!("{}:{}", cursor.y + 1, cursor.x + 1))
+    }
+
+    /// what Tab inserts, always spaces whatever the file itself indents with
+    pub fn tab_label(tab_width: usize) -> String {
+        format!("spaces: {tab_width}")
+    }
+
+    fn is_readonly(&self) -> bool {
+        self.file
+            .as_ref()
+            .is_some_and(|file| self.files[file].readonly)
+    }
+
+    pub fn cursor_count(&self) -> usize {
+        self.file
+            .as_ref()
+            .map_or(0, |file| self.files[file].cursors.len())
     }
 
     fn cursor_visible_relative_position(&self, file: &PathBuf) -> (isize, isize) {
@@ -72,13 +290,19 @@ impl Editing {
     fn snap_to_cursor(&mut self) {
         let cursor = {
             let Some(ref file) = self.file.as_ref().cloned() else { return; };
-            self.files[file].cursors[0].clone()
+            let      file      = self.files.get_mut(file).unwrap();
+
+            // NOTE: every action ends up here
+            file.merge_cursors();
+            file.cursors[0]
         };
 
+        let height = self.text_height();
+
         if cursor.y < self.scroll().y {
             self.scroll_mut().y = cursor.y;
-        } else if cursor.y > self.scroll().y + self.size().y - 1 {
-            self.scroll_mut().y = cursor.y - self.size().y + 1;
+        } else if cursor.y > self.scroll().y + height - 1 {
+            self.scroll_mut().y = cursor.y - height + 1;
         }
 
         if cursor.x < self.scroll().x {
@@ -88,32 +312,269 @@ impl Editing {
         }
     }
 
+    /// lines of text in view, the search prompt covers the last ones
+    fn text_height(&self) -> isize {
+        self.size().y - self.search.as_ref().map_or(0, Search::rows)
+    }
+
     // TODO: wrong
     fn warp_cursor(&mut self, x: u16, y: u16) {
         let scroll         = self.scroll();
         let Some(ref file) = self.file.as_ref().cloned() else { return; };
 
         let y = {
-            let line_count = self.files[file].lines.len() as isize;
+            let line_count = self.files[file].buffer.line_count();
             let file       = &mut self.files.get_mut(file).unwrap();
 
             file.cursors.drain(1..);
 
             let cursor = &mut file.cursors[0];
 
-            cursor.y = y as isize + scroll.y;
-            cursor.x = x as isize + scroll.x;
+            cursor.anchor = None;
+            cursor.y      = y as isize + scroll.y;
+            cursor.x      = x as isize + scroll.x;
 
             cursor.y.to_min_with(line_count - 1);
-            cursor.y as usize
+            cursor.y
         };
 
-        let line_len = self.files[file].lines[y].utf8_len();
+        let line_len = self.files[file].buffer.line(y).len();
         let cursor   = &mut self.files.get_mut(file).unwrap().cursors[0];
 
         cursor.x.to_min_with(line_len);
         cursor.last_x = x as isize + scroll.x;
     }
+
+    /// runs one of the `Editing` commands, unless it would change a read-only file
+    pub fn run_command(&mut self, editor: &mut Editor, command: &Command) {
+        let Run::Editing(run) = command.run else { return; };
+
+        // NOTE: a command instead of a register name gives up on picking one
+        editor.registers.awaiting = false;
+
+        if !self.refuses_edit(editor, command.edits) {
+            run(self, editor);
+        }
+    }
+
+    fn refuses_edit(&self, editor: &mut Editor, edits: bool) -> bool {
+        let refused = edits && self.is_readonly();
+
+        if refused {
+            let reopen = Command::find("file.reopen_in_encoding").and_then(|command| editor.keymap.label(command));
+            let text   = reopen.map_or_else(
+                || String::from("read-only"),
+                |keys| format!("read-only, {keys} reopens it in another encoding")
+            );
+
+            editor.message = Some(Message::warning(text));
+        }
+
+        refused
+    }
+
+    /// a line of the search prompt, drawn over the last lines
+    fn print_search(&self, editor: &mut Editor, buffer: &mut String, loop_i: usize) {
+        let Some(search) = self.search.as_ref() else { return; };
+
+        let theme       = &editor.theme;
+        let width       = self.size().x as usize;
+        let replace_row = search.replacing && loop_i as isize == self.size().y - 1;
+
+        let (label, text, status, toggles) = if replace_row {
+            let toggles = vec![("AB", search.query.preserve_case)];
+
+            (" replace: ", &search.query.replace, None, toggles)
+        } else {
+            let status = if let Some(error) = &search.error {
+                (error.clone(), true)
+            } else if let Some(i) = search.current {
+                (format!("match {} of {}", i + 1, search.matches.len()), false)
+            } else {
+                (String::from("no matches"), !search.query.text.is_empty())
+            };
+
+            let mut toggles = vec![
+                ("Aa", search.query.case_sensitive),
+                ("ab", search.query.whole_word    ),
+                (".*", search.query.regex         )
+            ];
+
+            if !search.scope.is_empty() {
+                toggles.push(("sel", search.in_scope));
+            }
+
+            (" find:    ", &search.query.text, Some(status), toggles)
+        };
+
+        let right = status.as_ref().map_or(0, |(status, _)| 1 + status.chars().count())
+            + toggles.iter().map(|(name, _)| 1 + name.len()).sum::<usize>()
+            + 1;
+        let right = if label.len() + right <= width { right } else { 0 };
+
+        // NOTE: the end of a long field stays visible
+        let room  = width.saturating_sub(label.len() + right);
+        let count = text.chars().count();
+        let text  = text.chars().skip(count.saturating_sub(room)).collect::<String>();
+        let shown = (label.len() + text.chars().count()).min(width);
+
+        buffer.push_str(&format!(
+            "{}{}{}{}{text}{}",
+            theme.backgrounds.secondary.normal,
+            theme.foreground.normal,
+            &label[..label.len().min(width)],
+            theme.foreground.active,
+            " ".repeat(width - shown - right)
+        ));
+
+        if right != 0 {
+            if let Some((status, failed)) = status {
+                let color = if failed { &theme.diagnostics.error } else { &theme.foreground.normal };
+                buffer.push_str(&format!(" {color}{status}"));
+            }
+
+            for (name, on) in toggles {
+                let color = if on { &theme.ansi.yellow } else { &theme.foreground.normal };
+                buffer.push_str(&format!(" {color}{name}"));
+            }
+
+            buffer.push(' ');
+        }
+
+        if (search.field == Field::Replace) == replace_row {
+            editor.cursor = Some((self.position().x + 1 + shown as isize, self.position().y + 1 + loop_i as isize));
+        }
+    }
+}
+
+impl File {
+    fn begin(&mut self, kind: StepKind) {
+        self.history.begin(kind, &self.cursors);
+    }
+
+    fn end(&mut self) {
+        self.history.end(&self.cursors);
+    }
+
+    /// returns the position right after the inserted text
+    fn insert(&mut self, x: isize, y: isize, text: &str) -> (isize, isize) {
+        let end  = self.buffer.insert(x, y, text);
+        let edit = Edit::Insert { x, y, text: String::from(text) };
+
+        self.highlight.edited(y as usize, 0, (end.1 - y) as usize);
+
+        for cursor in &mut self.cursors {
+            cursor.shift_inserted((x, y), end);
+        }
+
+        self.history.record(edit);
+        self.clean = false;
+
+        end
+    }
+
+    fn remove(&mut self, x1: isize, y1: isize, x2: isize, y2: isize) -> String {
+        let text = self.buffer.remove(x1, y1, x2, y2);
+        let edit = Edit::Remove { x: x1, y: y1, text: text.clone() };
+
+        self.highlight.edited(y1 as usize, (y2 - y1) as usize, 0);
+
+        for cursor in &mut self.cursors {
+            cursor.shift_removed((x1, y1), (x2, y2));
+        }
+
+        self.history.record(edit);
+        self.clean = false;
+
+        text
+    }
+
+    fn erase_selection_at(&mut self, i: usize) -> bool {
+        let Some((start, end)) = self.cursors[i].selection() else { return false; };
+
+        self.remove(start.0, start.1, end.0, end.1);
+
+        let cursor    = &mut self.cursors[i];
+        cursor.anchor = None;
+        cursor.move_to(start);
+
+        true
+    }
+
+    /// swaps whole lines for others, as edits, so cursors below move along
+    fn replace_lines(&mut self, old: Range<usize>, new: &[String]) {
+        let count = self.buffer.line_count();
+        let start = old.start as isize;
+        let end   = old.end   as isize;
+        let text  = new.join("\n");
+
+        if end < count {
+            if start < end {
+                self.remove(0, start, 0, end);
+            }
+
+            if !new.is_empty() {
+                self.insert(0, start, &format!("{text}\n"));
+            }
+        } else if start > 0 {
+            // NOTE: up to the end of the buffer, so the newline before goes instead of the one after
+            let x = self.buffer.line(start - 1).len();
+
+            if start < end {
+                self.remove(x, start - 1, self.buffer.line(count - 1).len(), count - 1);
+            }
+
+            if !new.is_empty() {
+                self.insert(x, start - 1, &format!("\n{text}"));
+            }
+        } else {
+            if start < end {
+                self.remove(0, 0, self.buffer.line(count - 1).len(), count - 1);
+            }
+
+            if !text.is_empty() {
+                self.insert(0, 0, &text);
+            }
+        }
+    }
+
+    /// folds cursors that ended up on the same spot into the earlier one
+    fn merge_cursors(&mut self) {
+        let mut i = 1;
+
+        while i < self.cursors.len() {
+            let cursor = self.cursors[i];
+
+            if let Some(other) = self.cursors[..i].iter_mut().find(|other| other.overlaps(&cursor)) {
+                other.absorb(&cursor);
+                self.cursors.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    /// cursor indices from the top of the file down
+    fn cursor_order(&self) -> Vec<usize> {
+        let mut order = (0..self.cursors.len()).collect::<Vec<usize>>();
+        order.sort_by_key(|i| (self.cursors[*i].y, self.cursors[*i].x));
+        order
+    }
+
+    /// char ranges of `y` covered by selections, past the end means the newline
+    fn selected_ranges(&self, y: isize) -> Vec<(isize, isize)> {
+        self.cursors
+            .iter()
+            .filter_map(Cursor::selection)
+            .filter(|(start, end)| start.1 <= y && y <= end.1)
+            .map(|(start, end)| {
+                let from = if start.1 == y { start.0 } else { 0 };
+                let to   = if end  .1 == y { end  .0 } else { self.buffer.line(y).len() + 1 };
+
+                (from, to)
+            })
+            .collect()
+    }
 }
 
 impl View for Editing {
@@ -122,7 +583,21 @@ impl View for Editing {
     fn view_data    (&    self) -> &        ViewData      { &    self.view_data     }
     fn view_data_mut(&mut self) -> &mut     ViewData      { &mut self.view_data     }
 
-    fn print_line(&mut self, editor: &mut Editor, buffer: &mut String, _loop_i: usize, scrolled_i: usize) {
+    fn mode(&self) -> &'static str {
+        match self.search.as_ref() {
+            Some(search) if search.field == Field::Replace => "REPLACE",
+            Some(_)                                        => "FIND",
+            None if self.is_readonly()                     => "VIEW",
+            None                                           => "EDIT"
+        }
+    }
+
+    // NOTE: the search prompt takes the keys while open
+    fn context(&self) -> Option<Context> {
+        self.search.is_none().then_some(Context::Editing)
+    }
+
+    fn print_line(&mut self, editor: &mut Editor, buffer: &mut String, loop_i: usize, scrolled_i: usize) {
         let Some(file) = self.file.as_ref().cloned() else {
             buffer.push_str(&format!(
                 "{}{}",
@@ -133,29 +608,87 @@ impl View for Editing {
             return;
         };
 
-        if scrolled_i < self.files[&file].lines.len() {
-            let x = self.scroll().x;
-            let y = scrolled_i as isize;
+        if self.search.as_ref().is_some_and(|search| loop_i as isize >= self.size().y - search.rows()) {
+            self.print_search(editor, buffer, loop_i);
+            return;
+        }
 
-            let line         = &self.files[&file].lines[y as usize];
-            let visible_line = line.utf8_range(x, x + self.size().x);
-            let cursor_line  = y == self.files[&file].cursors[0].y;
+        if (scrolled_i as isize) < self.files[&file].buffer.line_count() {
+            let x     = self.scroll().x;
+            let y     = scrolled_i as isize;
+            let width = self.size().x;
 
-            let style = if cursor_line {
+            let opened = self.files.get_mut(&file).unwrap();
+            let tokens = opened.highlight.tokens(&opened.buffer, y as usize);
+
+            let cursors      = &self.files[&file].cursors;
+            let line         = self.files[&file].buffer.line(y);
+            let visible_line = line.range(x, x + width);
+            let cursor_line  = cursors.iter().any(|cursor| cursor.y == y);
+
+            // NOTE: the primary cursor is the real terminal one, the rest are drawn
+            let carets = cursors[1..]
+                .iter()
+                .filter(|cursor| cursor.y == y && cursor.x >= x && cursor.x < x + width)
+                .map(|cursor| (cursor.x - x) as usize)
+                .collect::<Vec<usize>>();
+
+            if y == cursors[0].y {
                 let (vx, vy)  = self.cursor_visible_relative_position(&file);
                 editor.cursor = Some((self.position().x + vx, self.position().y + vy));
+            }
 
+            let style = if cursor_line {
                 (&editor.theme.backgrounds.primary.active, &editor.theme.foreground.active)
             } else {
                 (&editor.theme.backgrounds.primary.normal, &editor.theme.foreground.normal)
             };
 
-            buffer.push_str(&format!(
-                "{}{}{visible_line}{}",
-                style.0,
-                style.1,
-                " ".repeat((self.size().x - visible_line.utf8_len()).max(0) as usize)
-            ));
+            // NOTE: (background, foreground) of every visible cell
+            let mut cells = vec![style; width as usize];
+
+            for token in &tokens {
+                for i in (token.range.start as isize).max(x)..(token.range.end as isize).min(x + width) {
+                    cells[(i - x) as usize].1 = editor.theme.syntax.of(token.kind);
+                }
+            }
+
+            for (start, end) in self.search.iter().flat_map(|search| search.on_line(y)) {
+                for i in start.0.max(x)..end.0.min(x + width) {
+                    cells[(i - x) as usize] = (&editor.theme.search_match.background, &editor.theme.search_match.foreground);
+                }
+            }
+
+            for (start, end) in self.files[&file].selected_ranges(y) {
+                for i in start.max(x)..end.min(x + width) {
+                    cells[(i - x) as usize].0 = &editor.theme.selection;
+                }
+            }
+
+            let mut last = None;
+            let     text = visible_line.chars().map(utils::printable).chain(std::iter::repeat(' '));
+
+            for (i, (ch, cell)) in text.zip(cells).enumerate() {
+                if carets.contains(&i) {
+                    buffer.push_str(&format!(
+                        "{}{}{}{ch}{}",
+                        editor.theme.backgrounds.primary.active,
+                        editor.theme.foreground.active,
+                        style::Invert,
+                        style::NoInvert
+                    ));
+
+                    last = None;
+                    continue;
+                }
+
+                if last != Some(cell) {
+                    buffer.push_str(&format!("{}{}", cell.0, cell.1));
+                    last = Some(cell);
+                }
+
+                buffer.push(ch);
+            }
         } else {
             buffer.push_str(&format!(
                 "{}{}",
@@ -218,31 +751,32 @@ impl View for Editing {
     }
 
     fn handle_event(&mut self, editor: &mut Editor, event: Event) {
+        if editor.registers.awaiting {
+            if let Event::Key(Key::Char(name)) = event {
+                editor.registers.select(name);
+            } else {
+                editor.registers.awaiting = false;
+            }
+
+            return;
+        }
+
+        if self.search.is_some() {
+            if self.search_event(editor, &event) {
+                return;
+            }
+
+            // NOTE: closed the prompt, so the key gets what it would have done without it
+            if let Some(command) = editor.keymap.find(Context::Editing, &event) {
+                self.run_command(editor, command);
+                return;
+            }
+        }
+
         match event {
-            Event::Key(key) => match key {
-                Key::Esc       => { self.exit           (editor); },
-                Key::Ctrl('s') => { self.save           (editor); },
-                Key::Left      => { self.left           (      ); },
-                Key::Right     => { self.right          (      ); },
-                Key::Up        => { self.up             (      ); },
-                Key::Down      => { self.down           (      ); },
-                Key::CtrlLeft  => { self.prev_word      (      ); },
-                Key::CtrlRight => { self.next_word      (      ); },
-                Key::Home      => { self.line_start     (      ); },
-                Key::End       => { self.line_end       (      ); },
-                Key::CtrlHome  => { self.file_start     (      ); },
-                Key::CtrlEnd   => { self.file_end       (      ); },
-                Key::Backspace => { self.erase_left     (      ); },
-                Key::Delete    => { self.erase_right    (      ); },
-                Key::Ctrl('h') => { self.erase_prev_word(      ); },
-                Key::AltUp     => { self.move_line_up   (      ); },
-                Key::AltDown   => { self.move_line_down (      ); },
-                Key::Char(ch)  => match ch {
-                    '\n'  => { self.newline  (     ); },
-                    '\t'  => { self.tab      (     ); },
-                    other => { self.character(other); }
-                },
-                _ => ()
+            // NOTE: typing, anything else bound is a command
+            Event::Key(Key::Char(ch)) if !self.refuses_edit(editor, true) => {
+                self.character(ch);
             },
             Event::Mouse(MouseEvent::Press(mouse_button, x, y)) => match mouse_button {
                 MouseButton::Left      => { self.warp_cursor(x, y); },
@@ -251,11 +785,70 @@ impl View for Editing {
                 _                      => ()
             },
             Event::Unsupported(bytes) => {
-                if bytes == [27, 91, 51, 59, 53, 126] {
-                    self.erase_next_word();
+                if let Some((x, y)) = utils::alt_click(&bytes) {
+                    let position = self.position();
+                    let size     = self.size();
+                    let x        = x as isize - 1 - position.x;
+                    let y        = y as isize - 1 - position.y;
+
+                    // NOTE: the focus does not always follow the click, so it can land outside the view
+                    if (0..size.x).contains(&x) && (0..size.y).contains(&y) {
+                        self.add_cursor_at(x as u16, y as u16);
+                    }
                 }
             },
             _ => ()
         }
     }
 }
+
+/// the default preferences with only `flag` changed
+#[cfg(test)]
+pub fn with(flag: u16, on: bool) -> crate::config::PreferenceMask {
+    let mut preferences = crate::config::PreferenceMask::default();
+    preferences.set(flag, on);
+    preferences
+}
+
+#[cfg(test)]
+impl Editing {
+    /// `text` open with a cursor at `(x, y)`, in a view `height` lines tall
+    pub fn with_text(text: &str, (x, y): (isize, isize), height: isize) -> Self {
+        let (format, buffer, _) = Format::read(text.as_bytes(), None);
+        let path                = PathBuf::from("test");
+
+        let file = File {
+            clean:     true,
+            readonly:  false,
+            disk:      None,
+            clobber:   false,
+            cursors:   vec![Cursor { x, y, last_x: x, anchor: None }],
+            buffer,
+            history:   History::new(),
+            format,
+            highlight: Highlighter::default()
+        };
+
+        Self {
+            view_data: ViewData { size: crate::Ivec2 { x: 80, y: height }, ..ViewData::default() },
+            file:      Some(path.clone()),
+            files:     HashMap::from([(path, file)]),
+            search:    None,
+            query:     Query::default()
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.files[self.file.as_ref().unwrap()].buffer.join("\n")
+    }
+
+    pub fn cursor(&self) -> (isize, isize) {
+        let cursor = self.files[self.file.as_ref().unwrap()].cursors[0];
+
+        (cursor.x, cursor.y)
+    }
+
+    pub fn cursors(&self) -> Vec<(isize, isize)> {
+        self.files[self.file.as_ref().unwrap()].cursors.iter().map(|cursor| (cursor.x, cursor.y)).collect()
+    }
+}
@@ -0,0 +1,307 @@
+// mochou-p/text-editor/src/view/editing/search.rs
+
+use super::cursor::Cursor;
+use crate::{Buffer, Ivec2};
+use crate::utils::{self, regex::{Captures, Memo, Regex}};
+
+
+/// `(start, end)` of a match, as `(x, y)`
+pub type Match = ((isize, isize), (isize, isize));
+
+#[derive(Clone, Default)]
+pub struct Query {
+    pub text:           String,
+    pub case_sensitive: bool,
+    pub whole_word:     bool,
+    pub regex:          bool,
+    pub replace:        String,
+    pub preserve_case:  bool
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Field {
+    Find,
+    Replace
+}
+
+pub struct Search {
+    pub query:     Query,
+    pub matches:   Vec<Match>,
+    pub current:   Option<usize>,
+    pub error:     Option<String>,
+    // NOTE: where to go back to on cancel
+    pub origin:    Vec<Cursor>,
+    pub scroll:    Ivec2,
+    // NOTE: selections it was opened with, kept up to date through replaces
+    pub scope:     Vec<Cursor>,
+    pub in_scope:  bool,
+    pub replacing: bool,
+    pub field:     Field,
+    // NOTE: the query compiled, with a memo for the longest line, kept until the query changes
+    matcher:       Option<(Regex, Memo)>
+}
+
+impl Query {
+    pub fn matcher(&self) -> Result<Regex, String> {
+        if self.regex {
+            Regex::new(&self.text, !self.case_sensitive)
+        } else {
+            Ok(Regex::literal(&self.text, !self.case_sensitive))
+        }
+    }
+
+    /// non-empty matches within one line, as char indices, `None` if it is too long to search
+    pub fn find_in(&self, regex: &Regex, memo: &mut Memo, chars: &[char]) -> Option<Vec<Captures>> {
+        let is_word = |i: usize| chars.get(i).copied().is_some_and(utils::is_alphanumericx);
+
+        let found = regex
+            .find_all(chars, memo)?
+            .into_iter()
+            .filter(|captures| {
+                let (start, end) = captures[0].unwrap();
+
+                start != end
+                &&
+                (!self.whole_word || !((start > 0 && is_word(start - 1)) || is_word(end)))
+            })
+            .collect();
+
+        Some(found)
+    }
+}
+
+impl Search {
+    pub fn new(query: Query, origin: Vec<Cursor>, scroll: Ivec2) -> Self {
+        let scope = origin
+            .iter()
+            .filter(|cursor| cursor.anchor.is_some())
+            .copied()
+            .collect::<Vec<Cursor>>();
+
+        // NOTE: a one-line selection is more likely the query than the scope
+        let in_scope = scope
+            .iter()
+            .filter_map(Cursor::selection)
+            .any(|(start, end)| start.1 != end.1);
+
+        Self {
+            query,
+            matches:   Vec::new(),
+            current:   None,
+            error:     None,
+            origin,
+            scroll,
+            scope,
+            in_scope,
+            replacing: false,
+            field:     Field::Find,
+            matcher:   None
+        }
+    }
+
+    /// prompt lines it takes up at the bottom of the view
+    pub fn rows(&self) -> isize {
+        1 + self.replacing as isize
+    }
+
+    /// finds every match again after the query changed, and picks the first one from the origin on
+    pub fn update(&mut self, buffer: &Buffer) {
+        self.matches.clear();
+        self.current = None;
+        self.error   = None;
+        self.matcher = None;
+
+        if self.query.text.is_empty() {
+            return;
+        }
+
+        let regex = match self.query.matcher() {
+            Ok(regex)  => regex,
+            Err(error) => { self.error = Some(error); return; }
+        };
+
+        let longest = buffer.lines().map(|line| line.len()).max().unwrap_or(0);
+        let memo    = regex.memo(longest as usize);
+
+        self.matcher = Some((regex, memo));
+
+        let mut skipped = 0;
+
+        for y in 0..buffer.line_count() {
+            let Some(found) = self.find_on(buffer, y) else { skipped += 1; continue; };
+            self.matches.extend(found);
+        }
+
+        if skipped != 0 {
+            self.error = Some(format!("{skipped} lines are too long to search for this"));
+        }
+
+        self.pick_current();
+    }
+
+    /// finds the matches on `lines` again after they were edited, the others stay as they were
+    pub fn rescan(&mut self, buffer: &Buffer, lines: &[isize]) {
+        self.current = None;
+
+        for y in lines {
+            let from = self.matches.partition_point(|(start, _)| start.1 <  *y);
+            let to   = self.matches.partition_point(|(start, _)| start.1 <= *y);
+
+            let found = self.find_on(buffer, *y).unwrap_or_default();
+            self.matches.splice(from..to, found);
+        }
+
+        self.pick_current();
+    }
+
+    fn find_on(&mut self, buffer: &Buffer, y: isize) -> Option<Vec<Match>> {
+        let (regex, memo) = self.matcher.as_mut()?;
+        let chars         = buffer.line(y).as_str().chars().collect::<Vec<char>>();
+
+        let found = self.query
+            .find_in(regex, memo, &chars)?
+            .into_iter()
+            .map(|captures| {
+                let (start, end) = captures[0].unwrap();
+                ((start as isize, y), (end as isize, y))
+            })
+            .filter(|found| !self.in_scope || self.scope.iter().any(|cursor| contains(cursor, *found)))
+            .collect();
+
+        Some(found)
+    }
+
+    fn pick_current(&mut self) {
+        if self.matches.is_empty() {
+            return;
+        }
+
+        let origin = &self.origin[0];
+        let from   = origin.selection().map_or((origin.x, origin.y), |(start, _)| start);
+        let i      = self.matches.partition_point(|(start, _)| (start.1, start.0) < (from.1, from.0));
+
+        self.current = Some(if i == self.matches.len() { 0 } else { i });
+    }
+
+    /// moves to the next or previous match, wrapping around the file
+    pub fn step(&mut self, forward: bool) {
+        let count = self.matches.len();
+
+        self.current = self.current.map(|i| {
+            if forward { (i + 1) % count } else { (i + count - 1) % count }
+        });
+    }
+
+    pub fn on_line(&self, y: isize) -> &[Match] {
+        let from = self.matches.partition_point(|(start, _)| start.1 <  y);
+        let to   = self.matches.partition_point(|(start, _)| start.1 <= y);
+
+        &self.matches[from..to]
+    }
+
+    /// what each of the `indices` matches would be replaced with
+    pub fn replacements(&mut self, buffer: &Buffer, indices: &[usize]) -> Vec<(Match, String)> {
+        let Some((regex, memo)) = self.matcher.as_mut() else { return Vec::new(); };
+
+        let mut line: Option<(isize, Vec<char>, Vec<Captures>)> = None;
+
+        indices
+            .iter()
+            .map(|i| {
+                let found = self.matches[*i];
+                let y     = found.0.1;
+
+                if line.as_ref().is_none_or(|(line_y, _, _)| *line_y != y) {
+                    let chars    = buffer.line(y).as_str().chars().collect::<Vec<char>>();
+                    let captures = self.query.find_in(regex, memo, &chars).unwrap_or_default();
+
+                    line = Some((y, chars, captures));
+                }
+
+                let (_, chars, captures) = line.as_ref().unwrap();
+
+                let captures = captures
+                    .iter()
+                    .find(|captures| captures[0].unwrap().0 as isize == found.0.0)
+                    .unwrap();
+
+                let (start, end) = captures[0].unwrap();
+
+                let text = if self.query.regex {
+                    expand(&self.query.replace, captures, chars)
+                } else {
+                    self.query.replace.clone()
+                };
+
+                let text = if self.query.preserve_case {
+                    match_case(&text, &chars[start..end])
+                } else {
+                    text
+                };
+
+                (found, text)
+            })
+            .collect()
+    }
+}
+
+fn contains(cursor: &Cursor, (start, end): Match) -> bool {
+    let Some((from, to)) = cursor.selection() else { return false; };
+
+    (from.1, from.0) <= (start.1, start.0) && (end.1, end.0) <= (to.1, to.0)
+}
+
+/// fills in `$0` to `$9` with capture groups, `$$` is a plain `$`
+fn expand(template: &str, captures: &Captures, chars: &[char]) -> String {
+    let mut text = String::new();
+    let mut iter = template.chars().peekable();
+
+    while let Some(ch) = iter.next() {
+        if ch != '$' {
+            text.push(ch);
+            continue;
+        }
+
+        match iter.peek().copied() {
+            Some('$') => {
+                iter.next();
+                text.push('$');
+            },
+            Some(digit) if digit.is_ascii_digit() => {
+                iter.next();
+
+                let group = digit as usize - '0' as usize;
+
+                if let Some(Some((start, end))) = captures.get(group) {
+                    text.extend(&chars[*start..*end]);
+                }
+            },
+            _ => text.push('$')
+        }
+    }
+
+    text
+}
+
+/// gives `text` the casing of `like`: all upper, all lower or capitalized
+fn match_case(text: &str, like: &[char]) -> String {
+    let letters = like.iter().filter(|ch| ch.is_alphabetic()).collect::<Vec<&char>>();
+
+    let Some(first) = letters.first() else { return String::from(text); };
+
+    if letters.len() > 1 && letters.iter().all(|ch| ch.is_uppercase()) {
+        return text.to_uppercase();
+    }
+    if letters.iter().all(|ch| ch.is_lowercase()) {
+        return text.to_lowercase();
+    }
+    if first.is_uppercase() && letters[1..].iter().all(|ch| ch.is_lowercase()) {
+        let mut chars = text.chars();
+
+        return chars
+            .next()
+            .map_or_else(String::new, |ch| ch.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect());
+    }
+
+    String::from(text)
+}
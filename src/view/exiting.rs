@@ -0,0 +1,156 @@
+// mochou-p/text-editor/src/view/exiting.rs
+
+use std::path::PathBuf;
+use termion::event::{Event, Key};
+use super::{View, ViewData};
+use super::editing::Editing;
+use super::hex::Hex;
+use crate::{Editor, Ivec2};
+use crate::config::PreferenceMask;
+
+
+/// asks what to do with unsaved files before quitting,
+/// drawn over the middle of the screen
+pub struct Exiting {
+    view_data: ViewData,
+    dirty:     Vec<PathBuf>,
+    // NOTE: gets the focus back on cancel
+    previous:  String
+}
+
+impl Exiting {
+    pub fn new() -> Self {
+        Self {
+            view_data: ViewData::default(),
+            dirty:     Vec::new(),
+            previous:  String::new()
+        }
+    }
+
+    /// quits right away when there is nothing to lose
+    pub fn open(&mut self, editor: &mut Editor) {
+        if editor.preferences.has(PreferenceMask::FILE_SAVE_ON_EDITOR_EXIT) && Self::save_all(editor) {
+            editor.exit = true;
+            return;
+        }
+
+        self.dirty = Self::dirty_files(editor);
+
+        if self.dirty.is_empty() {
+            editor.exit = true;
+            return;
+        }
+
+        if editor.view != Self::name() {
+            self.previous = editor.view.clone();
+            editor.view   = Self::name();
+        }
+
+        self.layout();
+    }
+
+    fn dirty_files(editor: &mut Editor) -> Vec<PathBuf> {
+        let mut dirty = editor.view::<Editing, _>(|_, view| view.dirty_files());
+        dirty.extend(editor.view::<Hex, _>(|_, view| view.dirty_files()));
+
+        dirty
+    }
+
+    fn save_all(editor: &mut Editor) -> bool {
+        let text = editor.view::<Editing, bool>(|editor, view| view.save_all(editor));
+        let hex  = editor.view::<Hex,     bool>(|editor, view| view.save_all(editor));
+
+        text && hex
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            " unsaved changes in {} file{} ",
+            self.dirty.len(),
+            if self.dirty.len() == 1 { "" } else { "s" }
+        )];
+
+        lines.extend(self.dirty.iter().map(|path| format!("   {} ", path.display())));
+        lines.push(String::new());
+        lines.push(String::from(" [s]ave all  [d]iscard all  [c]ancel "));
+
+        lines
+    }
+
+    /// centered, as big as the text, as long as it fits
+    fn layout(&mut self) {
+        let screen = Ivec2::from(termion::terminal_size().unwrap());
+        let lines  = self.lines();
+
+        let width  = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as isize;
+        let width  = (width + 2).min(screen.x);
+        let height = (lines.len() as isize + 2).min(screen.y);
+
+        self.view_data.size     = Ivec2 { x: width, y: height };
+        self.view_data.position = Ivec2 { x: (screen.x - width) / 2, y: (screen.y - height) / 2 };
+        self.view_data.scroll   = Ivec2::ZERO;
+    }
+
+    fn save(&mut self, editor: &mut Editor) {
+        if Self::save_all(editor) {
+            editor.exit = true;
+            return;
+        }
+
+        // NOTE: the ones that failed stay listed, next to the error message
+        self.dirty = Self::dirty_files(editor);
+        self.layout();
+    }
+
+    fn cancel(&mut self, editor: &mut Editor) {
+        self.view_data.size = Ivec2::ZERO;
+        editor.view         = self.previous.clone();
+    }
+}
+
+impl View for Exiting {
+    fn any          (&mut self) -> &mut dyn std::any::Any { self                    }
+    fn name         (         ) ->          String        { String::from("exiting") }
+    fn view_data    (&    self) -> &        ViewData      { &    self.view_data     }
+    fn view_data_mut(&mut self) -> &mut     ViewData      { &mut self.view_data     }
+
+    fn mode(&self) -> &'static str {
+        "PROMPT"
+    }
+
+    // NOTE: only while open, a closed one must not hold on to a stray click
+    fn is_modal(&self) -> bool {
+        self.size().x != 0
+    }
+
+    fn print_line(&mut self, editor: &mut Editor, buffer: &mut String, loop_i: usize, _scrolled_i: usize) {
+        let theme = &editor.theme;
+        let width = self.size().x as usize;
+
+        // NOTE: a blank line around the text
+        let line = loop_i
+            .checked_sub(1)
+            .and_then(|i| self.lines().into_iter().nth(i))
+            .unwrap_or_default();
+
+        let line  = format!(" {line}").chars().take(width).collect::<String>();
+        let color = if loop_i == 1 { &theme.ansi.yellow } else { &theme.foreground.active };
+
+        buffer.push_str(&format!(
+            "{}{color}{line}{}",
+            theme.backgrounds.secondary.normal,
+            " ".repeat(width - line.chars().count())
+        ));
+    }
+
+    fn handle_event(&mut self, editor: &mut Editor, event: Event) {
+        let Event::Key(key) = event else { return; };
+
+        match key {
+            Key::Char('s') | Key::Char('\n') => { self.save  (editor); },
+            Key::Char('d')                   => { editor.exit = true;  },
+            Key::Char('c') | Key::Esc        => { self.cancel(editor); },
+            _                                => ()
+        }
+    }
+}
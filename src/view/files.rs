@@ -35,12 +35,16 @@ impl View for Files {
     fn view_data    (&    self) -> &        ViewData      { &    self.view_data   }
     fn view_data_mut(&mut self) -> &mut     ViewData      { &mut self.view_data   }
 
+    fn mode(&self) -> &'static str {
+        "TABS"
+    }
+
     fn print_line(&mut self, editor: &mut Editor, buffer: &mut String, _loop_i: usize, _scrolled_i: usize) {
         let mut size = self.size().x as usize;
 
         for file in self.files.iter() {
             let text = file.file_name().unwrap();
-            let text = format!(" {} ", text.display().to_string());
+            let text = format!(" {} ", text.display());
             let len  = text.len();
 
             if len > size {
@@ -0,0 +1,661 @@
+// mochou-p/text-editor/src/view/hex.rs
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use termion::event::{Event, Key, MouseEvent, MouseButton};
+use super::{View, ViewData};
+use super::conflict::Conflict;
+use super::editing::Editing;
+use crate::{Editor, Ivec2};
+use crate::clipboard::{self, Clip};
+use crate::keymap::Context;
+use crate::message::Message;
+use crate::utils::{self, atomic::Written};
+use crate::watcher::Stamp;
+
+
+// NOTE: `00000000  ` before the bytes, `|` around the ascii column
+const OFFSET_WIDTH: usize = 10;
+
+/// files byte by byte, as an offset / hex / ascii grid,
+/// in the place of `Editing` while shown
+pub struct Hex {
+    view_data: ViewData,
+    file:      Option<PathBuf>,
+    files:     HashMap<PathBuf, HexFile>
+}
+
+struct HexFile {
+    clean:   bool,
+    // NOTE: what the file on disk looked like when last read or saved
+    disk:    Option<Stamp>,
+    // NOTE: the user already got warned it changed on disk, so the next save overwrites it
+    clobber: bool,
+    bytes:   Vec<u8>,
+    // NOTE: goes up with every change to `bytes`, for the journal
+    edits:   u64,
+    cursor:  usize,
+    anchor:  Option<usize>,
+    side:    Side,
+    // NOTE: the next hex digit goes into the low half of the byte
+    low:     bool
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Hex,
+    Ascii
+}
+
+impl HexFile {
+    /// first and last selected byte, both included
+    fn selection(&self) -> Option<(usize, usize)> {
+        self.anchor.map(|anchor| (anchor.min(self.cursor), anchor.max(self.cursor)))
+    }
+
+    fn move_to(&mut self, i: usize, select: bool) {
+        if select {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+
+        self.cursor = i.min(self.bytes.len().saturating_sub(1));
+        self.low    = false;
+    }
+
+    fn changed_on_disk(&self, path: &Path) -> bool {
+        !self.clobber && Stamp::of(path) != self.disk
+    }
+
+    fn overwrite(&mut self, byte: u8, advance: bool) {
+        let Some(old) = self.bytes.get_mut(self.cursor) else { return; };
+
+        if *old != byte {
+            *old        = byte;
+            self.clean  = false;
+            self.edits += 1;
+        }
+
+        self.anchor = None;
+
+        if advance {
+            self.cursor = (self.cursor + 1).min(self.bytes.len() - 1);
+        }
+    }
+}
+
+impl Hex {
+    pub fn new() -> Self {
+        Self {
+            view_data: ViewData::default(),
+            file:      None,
+            files:     HashMap::new()
+        }
+    }
+
+    pub fn has(&self, path: &PathBuf) -> bool {
+        self.files.contains_key(path)
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.size().x != 0
+    }
+
+    /// shows `path` in the place of `editing`, reading it first if needed
+    pub fn open(&mut self, editor: &mut Editor, editing: &mut ViewData, path: &PathBuf) -> bool {
+        if !self.files.contains_key(path) {
+            // NOTE: before reading, a change in between shows up as another change
+            let disk  = Stamp::of(path);
+            let bytes = match std::fs::read(path) {
+                Ok(bytes)  => bytes,
+                Err(error) => {
+                    editor.message = Some(Message::error(format!("could not open {}: {error}", path.display())));
+                    return false;
+                }
+            };
+
+            let file = HexFile { clean: true, disk, clobber: false, bytes, edits: 0, cursor: 0, anchor: None, side: Side::Hex, low: false };
+            self.files.insert(path.clone(), file);
+
+            if let Some(watcher) = editor.watcher.as_mut() {
+                watcher.watch(path);
+            }
+        }
+
+        self.file = Some(path.clone());
+
+        if !self.is_shown() {
+            self.view_data.position = editing.position;
+            self.view_data.size     = editing.size;
+            self.view_data.scroll   = Ivec2::ZERO;
+            editing.size            = Ivec2::ZERO;
+        }
+
+        self.snap_to_cursor();
+        true
+    }
+
+    /// gives the space back to `editing`
+    pub fn hide(&mut self, editing: &mut ViewData) {
+        if self.is_shown() {
+            editing.size        = self.view_data.size;
+            self.view_data.size = Ivec2::ZERO;
+        }
+    }
+
+    /// offset, size and selection, for indicators
+    pub fn label(&self) -> Option<String> {
+        if !self.is_shown() {
+            return None;
+        }
+
+        let     file  = &self.files[self.file.as_ref()?];
+        let mut label = format!("HEX {:08x} of {} bytes", file.cursor, file.bytes.len());
+
+        if let Some((start, end)) = file.selection() {
+            label.push_str(&format!(", {} selected", end - start + 1));
+        }
+
+        Some(label)
+    }
+
+    fn current(&mut self) -> Option<&mut HexFile> {
+        self.files.get_mut(self.file.as_ref()?)
+    }
+
+    /// as many bytes as fit, in steps of 4
+    fn per_row(&self) -> usize {
+        let width = self.size().x as usize;
+
+        [16, 8, 4]
+            .into_iter()
+            .find(|n| OFFSET_WIDTH + 4 * n + 2 <= width)
+            .unwrap_or(4)
+    }
+
+    fn snap_to_cursor(&mut self) {
+        let Some(path) = self.file.as_ref() else { return; };
+
+        let row    = (self.files[path].cursor / self.per_row()) as isize;
+        let height = self.size().y;
+
+        if row < self.scroll().y {
+            self.scroll_mut().y = row;
+        } else if row > self.scroll().y + height - 1 {
+            self.scroll_mut().y = row - height + 1;
+        }
+    }
+
+    fn scroll_by(&mut self, direction: isize) {
+        let per_row = self.per_row();
+        let Some(file) = self.current() else { return; };
+
+        let rows = file.bytes.len().div_ceil(per_row).max(1) as isize;
+
+        self.scroll_mut().y = (self.scroll().y + direction).clamp(0, rows - 1);
+    }
+
+    fn move_by(&mut self, delta: isize, select: bool) {
+        let Some(file) = self.current() else { return; };
+
+        let i = (file.cursor as isize + delta).max(0) as usize;
+        file.move_to(i, select);
+
+        self.snap_to_cursor();
+    }
+
+    pub fn left(&mut self, select: bool) {
+        self.move_by(-1, select);
+    }
+
+    pub fn right(&mut self, select: bool) {
+        self.move_by(1, select);
+    }
+
+    pub fn up(&mut self, select: bool) {
+        self.move_by(-(self.per_row() as isize), select);
+    }
+
+    pub fn down(&mut self, select: bool) {
+        self.move_by(self.per_row() as isize, select);
+    }
+
+    pub fn row_start(&mut self, select: bool) {
+        let per_row    = self.per_row();
+        let Some(file) = self.current() else { return; };
+
+        file.move_to(file.cursor - file.cursor % per_row, select);
+        self.snap_to_cursor();
+    }
+
+    pub fn row_end(&mut self, select: bool) {
+        let per_row    = self.per_row();
+        let Some(file) = self.current() else { return; };
+
+        file.move_to(file.cursor - file.cursor % per_row + per_row - 1, select);
+        self.snap_to_cursor();
+    }
+
+    pub fn file_start(&mut self, select: bool) {
+        let Some(file) = self.current() else { return; };
+
+        file.move_to(0, select);
+        self.snap_to_cursor();
+    }
+
+    pub fn file_end(&mut self, select: bool) {
+        let Some(file) = self.current() else { return; };
+
+        file.move_to(usize::MAX, select);
+        self.snap_to_cursor();
+    }
+
+    pub fn switch_side(&mut self) {
+        let Some(file) = self.current() else { return; };
+
+        file.side = match file.side {
+            Side::Hex   => Side::Ascii,
+            Side::Ascii => Side::Hex
+        };
+        file.low  = false;
+    }
+
+    /// a hex digit on the hex side, a printable ascii char on the other
+    fn type_char(&mut self, ch: char) {
+        let Some(file) = self.current() else { return; };
+        let Some(old)  = file.bytes.get(file.cursor).copied() else { return; };
+
+        match file.side {
+            Side::Hex => {
+                let Some(digit) = ch.to_digit(16) else { return; };
+                let      digit  = digit as u8;
+
+                if file.low {
+                    file.overwrite(old & 0xF0 | digit, true);
+                    file.low = false;
+                } else {
+                    file.overwrite(digit << 4 | old & 0x0F, false);
+                    file.low = true;
+                }
+            },
+            Side::Ascii => {
+                if ch.is_ascii() && !ch.is_ascii_control() {
+                    file.overwrite(ch as u8, true);
+                }
+            }
+        }
+
+        self.snap_to_cursor();
+    }
+
+    /// the selected bytes as hex, or the byte under the cursor
+    pub fn copy(&mut self, editor: &mut Editor) {
+        let Some(file) = self.current() else { return; };
+        if file.bytes.is_empty() {
+            return;
+        }
+
+        let (start, end) = file.selection().unwrap_or((file.cursor, file.cursor));
+
+        let text = file.bytes[start..=end]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        let clip = Clip { text, linewise: false };
+
+        if editor.registers.store(clip.clone()) {
+            clipboard::osc52(&mut editor.stdout, &clip.text);
+        }
+    }
+
+    pub fn escape(&mut self) {
+        if let Some(file) = self.current() {
+            file.anchor = None;
+        }
+    }
+
+    pub fn save(&mut self, editor: &mut Editor) {
+        let Some(path) = self.file.clone() else { return; };
+
+        let file = self.files.get_mut(&path).unwrap();
+
+        if file.clean {
+            return;
+        }
+
+        if file.changed_on_disk(&path) {
+            file.clobber   = true;
+            editor.message = Some(Message::warning(format!(
+                "{} changed on disk since it was loaded, Ctrl+S again overwrites it",
+                path.display()
+            )));
+
+            return;
+        }
+
+        editor.message = Some(match self.save_file(&path) {
+            Ok((len, Written::Renamed))      => Message::info(format!("saved {}, {len} bytes", path.display())),
+            Ok((len, Written::InPlace(why))) => Message::warning(format!("saved {}, {len} bytes, but in place as {why}", path.display())),
+            Err(error)                       => Message::error(error)
+        });
+    }
+
+    /// returns whether every file got saved
+    pub fn save_all(&mut self, editor: &mut Editor) -> bool {
+        let mut saved    = 0;
+        let mut failed   = None;
+        let mut in_place = None;
+
+        for path in self.dirty_files() {
+            match self.save_file(&path) {
+                Ok((_, Written::Renamed))      => { saved += 1; },
+                Ok((_, Written::InPlace(why))) => { saved += 1; in_place = Some((path, why)); },
+                Err(error)                     => { failed = Some(error); }
+            }
+        }
+
+        if let Some(error) = failed {
+            editor.message = Some(Message::error(error));
+            return false;
+        }
+
+        if let Some((path, why)) = in_place {
+            editor.message = Some(Message::warning(format!("saved {saved} files, {} in place as {why}", path.display())));
+        } else if saved != 0 {
+            editor.message = Some(Message::info(format!("saved {saved} files")));
+        }
+
+        true
+    }
+
+    pub fn dirty_files(&self) -> Vec<PathBuf> {
+        let mut paths = self.files
+            .iter()
+            .filter(|(_, file)| !file.clean)
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<PathBuf>>();
+
+        paths.sort();
+        paths
+    }
+
+    /// the open file, and whether it has unsaved changes
+    pub fn open_file(&self) -> Option<(PathBuf, bool)> {
+        let path = self.file.as_ref()?;
+
+        Some((path.clone(), !self.files[path].clean))
+    }
+
+    /// unsaved files and how many edits in they are, with their bytes unless `journaled` already has them
+    pub fn unsaved(&self, journaled: &HashMap<PathBuf, u64>) -> Vec<(PathBuf, u64, Option<Vec<u8>>)> {
+        self.dirty_files()
+            .into_iter()
+            .map(|path| {
+                let file  = &self.files[&path];
+                let bytes = (journaled.get(&path) != Some(&file.edits)).then(|| file.bytes.clone());
+                (path, file.edits, bytes)
+            })
+            .collect()
+    }
+
+    /// opens `path` with `bytes` recovered after a crash, as an unsaved change
+    pub fn restore(&mut self, editor: &mut Editor, editing: &mut ViewData, path: &PathBuf, bytes: Vec<u8>) -> bool {
+        if !self.open(editor, editing, path) {
+            return false;
+        }
+
+        let file = self.files.get_mut(path).unwrap();
+
+        if file.bytes != bytes {
+            file.bytes  = bytes;
+            file.clean  = false;
+            file.edits += 1;
+            file.move_to(file.cursor, false);
+        }
+
+        editor.message = Some(Message::info(format!("restored {}, not saved yet", path.display())));
+        true
+    }
+
+    /// returns how many bytes got written, and how
+    fn save_file(&mut self, path: &PathBuf) -> Result<(usize, Written), String> {
+        let file = self.files.get_mut(path).unwrap();
+
+        if file.changed_on_disk(path) {
+            return Err(format!("{} changed on disk, Ctrl+S in it to overwrite", path.display()));
+        }
+
+        let written = utils::atomic::write(path, &file.bytes)
+            .map_err(|error| format!("could not save {}: {error}", path.display()))?;
+
+        file.clean   = true;
+        file.clobber = false;
+        file.disk    = Stamp::of(path);
+
+        Ok((file.bytes.len(), written))
+    }
+
+    /// takes the new bytes of a clean file, asks what to do with one with unsaved changes
+    pub fn disk_changed(&mut self, editor: &mut Editor, path: &PathBuf) {
+        let Some(file) = self.files.get_mut(path) else { return; };
+
+        // NOTE: our own saves, and events about the same change
+        let stamp = Stamp::of(path);
+        if stamp == file.disk {
+            return;
+        }
+
+        if stamp.is_none() {
+            file.disk      = None;
+            editor.message = Some(Message::warning(format!("{} got deleted on disk", path.display())));
+            return;
+        }
+
+        let Ok(bytes) = std::fs::read(path) else { return; };
+
+        if bytes == file.bytes {
+            file.disk = stamp;
+            return;
+        }
+
+        if file.clean {
+            self.reload(editor, path);
+        } else {
+            editor.view::<Conflict, ()>(|editor, view| view.push(editor, path.clone()));
+        }
+    }
+
+    /// takes the bytes on disk, keeping the cursor where it was as far as they go
+    pub fn reload(&mut self, editor: &mut Editor, path: &PathBuf) {
+        let Some(file) = self.files.get_mut(path) else { return; };
+
+        let disk  = Stamp::of(path);
+        let bytes = match std::fs::read(path) {
+            Ok(bytes)  => bytes,
+            Err(error) => {
+                editor.message = Some(Message::error(format!("could not reload {}: {error}", path.display())));
+                return;
+            }
+        };
+
+        file.bytes   = bytes;
+        file.disk    = disk;
+        file.clean   = true;
+        file.clobber = false;
+        file.edits  += 1;
+        file.move_to(file.cursor, false);
+
+        editor.message = Some(Message::info(format!("reloaded {}, it changed on disk", path.display())));
+    }
+
+    /// leaves the bytes as they are, saving them will not warn about the change on disk
+    pub fn keep_mine(&mut self, path: &PathBuf) {
+        let Some(file) = self.files.get_mut(path) else { return; };
+
+        file.disk = Stamp::of(path);
+    }
+
+    /// the bytes of the buffer, and of the file on disk
+    pub fn disk_diff(&self, path: &PathBuf) -> Option<(Vec<u8>, Vec<u8>)> {
+        let file = self.files.get(path)?;
+        let disk = std::fs::read(path).ok()?;
+
+        Some((file.bytes.clone(), disk))
+    }
+
+    /// hands the file over to `Editing`, as text
+    pub fn show_as_text(&mut self, editor: &mut Editor) {
+        let Some(path) = self.file.clone() else { return; };
+
+        if !self.files[&path].clean {
+            editor.message = Some(Message::warning("unsaved changes, save them before switching to text"));
+            return;
+        }
+
+        self.files.remove(&path);
+        self.file = None;
+
+        let held = editor.view::<Editing, bool>(|editor, view| {
+            self.hide(view.view_data_mut());
+            view.open_as_text(editor, path.clone());
+            view.has(&path)
+        });
+
+        // NOTE: it could not be read as text, so nothing has it open anymore
+        if !held && let Some(watcher) = editor.watcher.as_mut() {
+            watcher.unwatch(&path);
+        }
+
+        editor.view = Editing::name();
+    }
+
+    fn click(&mut self, x: u16, y: u16) {
+        let per_row = self.per_row();
+        let row     = (y as isize + self.scroll().y) as usize;
+        let x       = x as usize;
+
+        let Some(file) = self.current() else { return; };
+
+        let (side, k) = if (OFFSET_WIDTH..OFFSET_WIDTH + 3 * per_row).contains(&x) {
+            (Side::Hex, (x - OFFSET_WIDTH) / 3)
+        } else if (OFFSET_WIDTH + 3 * per_row + 1..OFFSET_WIDTH + 4 * per_row + 1).contains(&x) {
+            (Side::Ascii, x - OFFSET_WIDTH - 3 * per_row - 1)
+        } else {
+            return;
+        };
+
+        file.side = side;
+        file.move_to(row * per_row + k, false);
+    }
+}
+
+impl View for Hex {
+    fn any          (&mut self) -> &mut dyn std::any::Any { self                }
+    fn name         (         ) ->          String        { String::from("hex") }
+    fn view_data    (&    self) -> &        ViewData      { &    self.view_data }
+    fn view_data_mut(&mut self) -> &mut     ViewData      { &mut self.view_data }
+
+    fn context(&self) -> Option<Context> {
+        Some(Context::Hex)
+    }
+
+    fn mode(&self) -> &'static str {
+        match self.file.as_ref().map(|file| self.files[file].side) {
+            Some(Side::Ascii) => "HEX ASCII",
+            _                 => "HEX"
+        }
+    }
+
+    fn print_line(&mut self, editor: &mut Editor, buffer: &mut String, loop_i: usize, scrolled_i: usize) {
+        let theme   = &editor.theme;
+        let width   = self.size().x as usize;
+        let per_row = self.per_row();
+        let start   = scrolled_i * per_row;
+
+        let file = self.file.as_ref().map(|path| &self.files[path]);
+
+        let Some(file) = file.filter(|file| start < file.bytes.len() || start == 0) else {
+            buffer.push_str(&format!("{}{}", theme.backgrounds.primary.disabled, " ".repeat(width)));
+            return;
+        };
+
+        let selection = file.selection();
+
+        // NOTE: the byte under the cursor is marked on the side without the terminal cursor
+        let background = |i: usize| {
+            if selection.is_some_and(|(first, last)| first <= i && i <= last) {
+                &theme.selection
+            } else if i == file.cursor {
+                &theme.backgrounds.secondary.normal
+            } else {
+                &theme.backgrounds.primary.normal
+            }
+        };
+
+        let number = if (start..start + per_row).contains(&file.cursor) {
+            &theme.line_number.active
+        } else {
+            &theme.line_number.normal
+        };
+
+        buffer.push_str(&format!(
+            "{}{number}{start:08x}{}  {}",
+            theme.gutter,
+            theme.backgrounds.primary.normal,
+            theme.foreground.active
+        ));
+
+        for i in start..start + per_row {
+            match file.bytes.get(i) {
+                Some(byte) => buffer.push_str(&format!("{}{byte:02x}{} ", background(i), theme.backgrounds.primary.normal)),
+                None       => buffer.push_str("   ")
+            }
+        }
+
+        buffer.push_str(&format!("{}|{}", theme.foreground.normal, theme.foreground.active));
+
+        for i in start..start + per_row {
+            let ch = match file.bytes.get(i) {
+                Some(byte @ 0x20..=0x7E) => *byte as char,
+                Some(_)                  => '.',
+                None                     => ' '
+            };
+
+            buffer.push_str(&format!("{}{ch}", background(i)));
+        }
+
+        buffer.push_str(&format!(
+            "{}{}|{}",
+            theme.backgrounds.primary.normal,
+            theme.foreground.normal,
+            " ".repeat(width.saturating_sub(OFFSET_WIDTH + 4 * per_row + 2))
+        ));
+
+        if (start..start + per_row).contains(&file.cursor) {
+            let k = file.cursor - start;
+            let x = match file.side {
+                Side::Hex   => OFFSET_WIDTH + 3 * k + file.low as usize,
+                Side::Ascii => OFFSET_WIDTH + 3 * per_row + 1 + k
+            };
+
+            editor.cursor = Some((self.position().x + 1 + x as isize, self.position().y + 1 + loop_i as isize));
+        }
+    }
+
+    fn handle_event(&mut self, _editor: &mut Editor, event: Event) {
+        // NOTE: typing, anything else bound is a command, see `Keymap`
+        match event {
+            Event::Key(Key::Char(ch)) => { self.type_char(ch); },
+            Event::Mouse(MouseEvent::Press(mouse_button, x, y)) => match mouse_button {
+                MouseButton::Left      => { self.click    (x, y); },
+                MouseButton::WheelUp   => { self.scroll_by(-1  ); },
+                MouseButton::WheelDown => { self.scroll_by( 1  ); },
+                _                      => ()
+            },
+            _ => ()
+        }
+    }
+}
@@ -1,13 +1,21 @@
 // mochou-p/text-editor/src/view/mod.rs
 
 mod browsing;
+mod conflict;
 mod editing;
+mod exiting;
 mod files;
+mod hex;
+mod palette;
+mod searching;
+mod status;
+mod themes;
 
 use termion::event::Event;
 use crate::{Editor, Ivec2};
+use crate::keymap::Context;
 
-pub use {browsing::Browsing, editing::Editing, files::Files};
+pub use {browsing::Browsing, conflict::Conflict, editing::Editing, exiting::Exiting, files::Files, hex::Hex, palette::Palette, searching::Searching, status::Status, themes::Themes};
 
 
 pub trait View {
@@ -23,6 +31,15 @@ pub trait View {
     fn   scroll    (&    self) ->      Ivec2 {      self.view_data    ().scroll   }
     fn   scroll_mut(&mut self) -> &mut Ivec2 { &mut self.view_data_mut().scroll   }
 
+    /// keeps the focus while open, even through clicks elsewhere
+    fn is_modal(&self) -> bool { false }
+
+    /// what the keys do while focused, for the status bar
+    fn mode(&self) -> &'static str { "" }
+
+    /// whose bindings the keys run while focused, on top of the global ones
+    fn context(&self) -> Option<Context> { None }
+
     fn print_line(&mut self, editor: &mut Editor, buffer: &mut String, _loop_i: usize, _scrolled_i: usize) {
         buffer.push_str(&format!(
             "{}{}",
@@ -102,7 +119,7 @@ impl ViewData {
             let size     = Ivec2 { x: other.size().x, y: height };
             let scroll   = Ivec2::ZERO;
 
-            other.size_mut().x -= height;
+            other.size_mut().y -= height;
 
             Self { position, size, scroll }
         })
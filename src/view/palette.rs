@@ -0,0 +1,217 @@
+// mochou-p/text-editor/src/view/palette.rs
+
+use termion::event::{Event, Key, MouseEvent, MouseButton};
+use super::{View, ViewData};
+use super::browsing::Browsing;
+use super::editing::Editing;
+use super::hex::Hex;
+use crate::{Editor, Ivec2};
+use crate::command::{Command, Run, COMMANDS};
+use crate::utils::fuzzy;
+
+
+// NOTE: the most commands shown at once
+const ROWS: usize = 12;
+
+/// every command, filtered by what gets typed, drawn over the top of the screen
+pub struct Palette {
+    view_data: ViewData,
+    query:     String,
+    // NOTE: indices into `COMMANDS`, best match first
+    matches:   Vec<usize>,
+    focused:   usize,
+    // NOTE: gets the focus back once closed
+    previous:  String
+}
+
+impl Palette {
+    pub fn new() -> Self {
+        Self {
+            view_data: ViewData::default(),
+            query:     String::new(),
+            matches:   Vec::new(),
+            focused:   0,
+            previous:  String::new()
+        }
+    }
+
+    pub fn open(&mut self, editor: &mut Editor) {
+        if editor.view != Self::name() {
+            self.previous = editor.view.clone();
+            editor.view   = Self::name();
+        }
+
+        self.query.clear();
+        self.filter(editor);
+
+        let screen = Ivec2::from(termion::terminal_size().unwrap());
+        let width  = (screen.x - 8).clamp(1, 80);
+        let height = (ROWS as isize + 1).clamp(1, screen.y);
+
+        self.view_data.size     = Ivec2 { x: width, y: height };
+        self.view_data.position = Ivec2 { x: (screen.x - width) / 2, y: 2.min(screen.y - height) };
+        self.view_data.scroll   = Ivec2::ZERO;
+    }
+
+    fn close(&mut self, editor: &mut Editor) {
+        self.view_data.size = Ivec2::ZERO;
+        editor.view         = self.previous.clone();
+    }
+
+    /// `Editing` commands are left out while `Hex` stands in for it, `Hex` ones otherwise,
+    /// `Browsing` ones unless opened from there
+    fn filter(&mut self, editor: &mut Editor) {
+        let hex      = editor.view::<Hex, bool>(|_, view| view.is_shown());
+        let browsing = self.previous == Browsing::name();
+
+        let mut scored = COMMANDS
+            .iter()
+            .enumerate()
+            .filter(|(_, command)| match command.run {
+                Run::Global  (_) => true,
+                Run::Editing (_) => !hex,
+                Run::Browsing(_) => browsing,
+                Run::Hex     (_) => hex
+            })
+            .filter_map(|(i, command)| {
+                let score = fuzzy::score(&self.query, command.title).max(fuzzy::score(&self.query, command.id))?;
+                Some((i, score))
+            })
+            .collect::<Vec<(usize, i32)>>();
+
+        // NOTE: stable, so equally good ones keep the order of the registry
+        scored.sort_by_key(|(_, score)| -score);
+
+        self.matches          = scored.into_iter().map(|(i, _)| i).collect();
+        self.focused          = 0;
+        self.view_data.scroll = Ivec2::ZERO;
+    }
+
+    fn move_focus(&mut self, direction: isize) {
+        if self.matches.is_empty() {
+            return;
+        }
+
+        self.focused = (self.focused as isize + direction).clamp(0, self.matches.len() as isize - 1) as usize;
+
+        // NOTE: keeps the focused one in view
+        let rows   = (self.size().y - 1) as usize;
+        let scroll = self.scroll().y as usize;
+
+        if self.focused < scroll {
+            self.scroll_mut().y = self.focused as isize;
+        } else if self.focused >= scroll + rows {
+            self.scroll_mut().y = (self.focused + 1 - rows) as isize;
+        }
+    }
+
+    fn run_focused(&mut self, editor: &mut Editor) {
+        let Some(command) = self.matches.get(self.focused).map(|i| &COMMANDS[*i]) else { return; };
+
+        self.close(editor);
+        Self::run(editor, command);
+    }
+
+    fn run(editor: &mut Editor, command: &'static Command) {
+        match command.run {
+            Run::Editing(_) => { editor.view = Editing::name(); },
+            Run::Hex    (_) => { editor.view = Hex::name();     },
+            _               => ()
+        }
+
+        editor.run_command(command);
+    }
+}
+
+impl View for Palette {
+    fn any          (&mut self) -> &mut dyn std::any::Any { self                    }
+    fn name         (         ) ->          String        { String::from("palette") }
+    fn view_data    (&    self) -> &        ViewData      { &    self.view_data     }
+    fn view_data_mut(&mut self) -> &mut     ViewData      { &mut self.view_data     }
+
+    fn mode(&self) -> &'static str {
+        "COMMAND"
+    }
+
+    // NOTE: only while open, a closed one must not hold on to a stray click
+    fn is_modal(&self) -> bool {
+        self.size().x != 0
+    }
+
+    fn print_line(&mut self, editor: &mut Editor, buffer: &mut String, loop_i: usize, _scrolled_i: usize) {
+        let theme = &editor.theme;
+        let width = self.size().x as usize;
+
+        if loop_i == 0 {
+            let prompt = format!(" > {}", self.query).chars().take(width).collect::<String>();
+            let shown  = prompt.chars().count();
+
+            editor.cursor = Some((self.position().x + 1 + shown as isize, self.position().y + 1));
+
+            buffer.push_str(&format!(
+                "{}{}{prompt}{}",
+                theme.backgrounds.secondary.active,
+                theme.foreground.active,
+                " ".repeat(width - shown)
+            ));
+
+            return;
+        }
+
+        let i = loop_i - 1 + self.scroll().y as usize;
+
+        let Some(command) = self.matches.get(i).map(|i| &COMMANDS[*i]) else {
+            let text = if i == 0 { " no matching commands" } else { "" };
+            let text = text.chars().take(width).collect::<String>();
+
+            buffer.push_str(&format!(
+                "{}{}{text}{}",
+                theme.backgrounds.secondary.normal,
+                theme.foreground.normal,
+                " ".repeat(width - text.chars().count())
+            ));
+
+            return;
+        };
+
+        let background = if i == self.focused { &theme.backgrounds.secondary.active } else { &theme.backgrounds.secondary.normal };
+
+        // NOTE: the binding keeps its room, the title gets cut
+        let binding = editor.keymap.label(command).map_or_else(String::new, |keys| format!(" {keys} ")).chars().take(width).collect::<String>();
+        let room    = width - binding.chars().count();
+        let title   = format!(" {}", command.title).chars().take(room).collect::<String>();
+
+        buffer.push_str(&format!(
+            "{background}{}{title}{}{}{binding}",
+            theme.foreground.active,
+            " ".repeat(room - title.chars().count()),
+            theme.foreground.normal
+        ));
+    }
+
+    fn handle_event(&mut self, editor: &mut Editor, event: Event) {
+        match event {
+            Event::Key(key) => match key {
+                Key::Esc        => { self.close      (editor); },
+                Key::Char('\n') => { self.run_focused(editor); },
+                Key::Up         => { self.move_focus (-1    ); },
+                Key::Down       => { self.move_focus ( 1    ); },
+                Key::Backspace  => {
+                    self.query.pop();
+                    self.filter(editor);
+                },
+                Key::Char(ch) if !ch.is_control() => {
+                    self.query.push(ch);
+                    self.filter(editor);
+                },
+                _               => ()
+            },
+            Event::Mouse(MouseEvent::Press(mouse_button, _x, _y)) => match mouse_button {
+                MouseButton::WheelUp   => { self.move_focus(-1); },
+                MouseButton::WheelDown => { self.move_focus( 1); },
+                _                      => ()
+            },
+            _ => ()
+        }
+    }
+}
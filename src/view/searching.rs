@@ -0,0 +1,552 @@
+// mochou-p/text-editor/src/view/searching.rs
+
+use std::fs::File;
+use std::io::Read as _;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+use termion::event::{Event, Key, MouseEvent, MouseButton};
+use super::{View, ViewData};
+use super::editing::{Editing, Query};
+use super::hex::Hex;
+use crate::{Editor, Ivec2, Signal};
+use crate::utils::{self, ignore::Ignore, regex::{Memo, Regex}};
+
+
+// NOTE: past this many, the search stops early
+const MAX_HITS: usize = 10_000;
+
+// NOTE: bigger files are skipped, they are rarely worth searching and would take all the memory
+const MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
+// NOTE: a nul byte this early on means binary
+const BINARY_SNIFF: usize = 8192;
+
+pub struct Searching {
+    view_data: ViewData,
+    root:      PathBuf,
+    query:     Query,
+    results:   Vec<FileHits>,
+    rows:      Vec<Row>,
+    focused:   usize,
+    hits:      usize,
+    done:      bool,
+    error:     Option<String>,
+    cancel:    Arc<AtomicBool>,
+    receiver:  Option<Receiver<Found>>
+}
+
+struct FileHits {
+    path: PathBuf,
+    hits: Vec<Hit>
+}
+
+struct Hit {
+    x:    isize,
+    y:    isize,
+    len:  isize,
+    line: String
+}
+
+#[derive(Clone, Copy)]
+enum Row {
+    File(usize),
+    Hit (usize, usize)
+}
+
+enum Found {
+    File(FileHits),
+    Done
+}
+
+impl Searching {
+    pub fn new(editor: &mut Editor) -> Self {
+        Self {
+            view_data: ViewData::under::<Editing>(editor, 0),
+            root:      PathBuf::new(),
+            query:     Query::default(),
+            results:   Vec::new(),
+            rows:      Vec::new(),
+            focused:   0,
+            hits:      0,
+            done:      true,
+            error:     None,
+            cancel:    Arc::new(AtomicBool::new(false)),
+            receiver:  None
+        }
+    }
+
+    /// shows the panel under `editing`, or under `Hex` when it has the spot, taking a third of its lines
+    pub fn open(&mut self, editor: &mut Editor, editing: &mut ViewData, root: PathBuf) {
+        if self.size().y == 0 {
+            let taken = if editor.view::<Hex, bool>(|_, view| view.is_shown()) {
+                editor.view::<Hex, bool>(|_, view| self.take_lines(view.view_data_mut()))
+            } else {
+                self.take_lines(editing)
+            };
+
+            if !taken {
+                return;
+            }
+        }
+
+        editor.view = Self::name();
+
+        if root != self.root || !self.done {
+            self.root = root;
+            self.restart(editor);
+        }
+    }
+
+    fn take_lines(&mut self, from: &mut ViewData) -> bool {
+        let height = (from.size.y / 3).max(4).min(from.size.y - 1);
+
+        if height <= 0 {
+            return false;
+        }
+
+        from.size.y -= height;
+
+        self.view_data.position = Ivec2 { x: from.position.x, y: from.position.y + from.size.y };
+        self.view_data.size     = Ivec2 { x: from.size.x,     y: height                        };
+
+        true
+    }
+
+    /// gives the lines back to `Editing`, or `Hex`
+    fn close(&mut self, editor: &mut Editor) {
+        self.cancel.store(true, Ordering::Relaxed);
+
+        // NOTE: to whichever of the two is in that spot now
+        let height = self.size().y;
+
+        if editor.view::<Hex, bool>(|_, view| view.is_shown()) {
+            editor.view::<Hex, ()>(|_, view| view.size_mut().y += height);
+            editor.view = Hex::name();
+        } else {
+            editor.view::<Editing, ()>(|_, view| view.size_mut().y += height);
+            editor.view = Editing::name();
+        }
+
+        self.size_mut().y = 0;
+    }
+
+    /// drops the results and starts over in the background
+    fn restart(&mut self, editor: &Editor) {
+        self.cancel.store(true, Ordering::Relaxed);
+
+        self.results.clear();
+        self.rows   .clear();
+
+        self.focused        = 0;
+        self.hits           = 0;
+        self.done           = true;
+        self.error          = None;
+        self.receiver       = None;
+        self.scroll_mut().y = 0;
+
+        if self.query.text.is_empty() {
+            return;
+        }
+
+        if let Err(error) = self.query.matcher() {
+            self.error = Some(error);
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let cancel             = Arc::new(AtomicBool::new(false));
+        let root               = self.root.clone();
+        let query              = self.query.clone();
+        let wake               = editor.sender.clone();
+
+        self.cancel   = cancel.clone();
+        self.receiver = Some(receiver);
+        self.done     = false;
+
+        std::thread::spawn(move || search(&root, &query, &cancel, &sender, &wake));
+    }
+
+    fn edit(&mut self, editor: &Editor, f: impl FnOnce(&mut Query)) {
+        f(&mut self.query);
+        self.restart(editor);
+    }
+
+    /// takes in whatever the search found since the last time
+    fn poll(&mut self) {
+        let Some(receiver) = self.receiver.as_ref() else { return; };
+
+        while let Ok(found) = receiver.try_recv() {
+            let Found::File(file) = found else {
+                self.done = true;
+                continue;
+            };
+
+            let i = self.results.len();
+
+            self.rows.push(Row::File(i));
+            self.rows.extend((0..file.hits.len()).map(|j| Row::Hit(i, j)));
+
+            self.hits += file.hits.len();
+            self.results.push(file);
+        }
+
+        // NOTE: the first row is always a file
+        if self.focused == 0 && self.rows.len() > 1 {
+            self.focused = 1;
+        }
+    }
+
+    fn up(&mut self) {
+        if let Some(i) = (0..self.focused).rev().find(|i| matches!(self.rows[*i], Row::Hit(..))) {
+            self.focused = i;
+            self.snap_to_focused();
+        }
+    }
+
+    fn down(&mut self) {
+        if let Some(i) = (self.focused + 1..self.rows.len()).find(|i| matches!(self.rows[*i], Row::Hit(..))) {
+            self.focused = i;
+            self.snap_to_focused();
+        }
+    }
+
+    fn scroll_list(&mut self, direction: isize) {
+        let y = self.scroll().y + direction;
+
+        if y >= 0 && y < self.rows.len() as isize {
+            self.scroll_mut().y = y;
+        }
+    }
+
+    fn snap_to_focused(&mut self) {
+        let focused = self.focused as isize;
+        let height  = self.size().y - 1;
+
+        if focused < self.scroll().y {
+            self.scroll_mut().y = focused;
+        } else if focused >= self.scroll().y + height {
+            self.scroll_mut().y = focused - height + 1;
+        }
+    }
+
+    fn open_focused(&mut self, editor: &mut Editor) {
+        let Some(Row::Hit(i, j)) = self.rows.get(self.focused).copied() else { return; };
+
+        let path = self.results[i].path.clone();
+        let hit  = &self.results[i].hits[j];
+        let x    = hit.x;
+        let y    = hit.y;
+
+        // NOTE: it may have opened in `Hex` instead, or not at all, then the cursor stays put
+        let opened = editor.view::<Editing, bool>(|editor, view| {
+            view.open_file_from_browser(editor, path.clone());
+
+            let opened = view.open_file().is_some_and(|(open, _)| open == path);
+
+            if opened {
+                view.go_to(x, y);
+            }
+
+            opened
+        });
+
+        editor.view = if !opened && editor.view::<Hex, bool>(|_, view| view.is_shown()) {
+            Hex::name()
+        } else {
+            Editing::name()
+        };
+    }
+
+    fn print_prompt(&self, editor: &mut Editor, buffer: &mut String) {
+        let theme = &editor.theme;
+        let width = self.size().x as usize;
+
+        let (status, failed) = if let Some(error) = &self.error {
+            (error.clone(), true)
+        } else if !self.done {
+            (format!("searching... {}", self.hits), false)
+        } else if self.query.text.is_empty() {
+            (String::new(), false)
+        } else if self.hits == 0 {
+            (String::from("no matches"), true)
+        } else {
+            let stopped = if self.hits >= MAX_HITS { " (stopped)" } else { "" };
+            (format!("{} matches in {} files{stopped}", self.hits, self.results.len()), false)
+        };
+
+        let toggles = [
+            ("Aa", self.query.case_sensitive),
+            ("ab", self.query.whole_word    ),
+            (".*", self.query.regex         )
+        ];
+
+        let label = " search: ";
+        let right = 1 + status.chars().count() + toggles.len() * 3 + 1;
+        let right = if label.len() + right <= width { right } else { 0 };
+
+        // NOTE: the end of a long query stays visible
+        let room  = width.saturating_sub(label.len() + right);
+        let count = self.query.text.chars().count();
+        let text  = self.query.text.chars().skip(count.saturating_sub(room)).collect::<String>();
+        let shown = (label.len() + text.chars().count()).min(width);
+
+        buffer.push_str(&format!(
+            "{}{}{}{}{text}{}",
+            theme.backgrounds.primary.disabled,
+            theme.foreground.normal,
+            &label[..label.len().min(width)],
+            theme.foreground.active,
+            " ".repeat(width - shown - right)
+        ));
+
+        if right != 0 {
+            let color = if failed { &theme.diagnostics.error } else { &theme.foreground.normal };
+            buffer.push_str(&format!(" {color}{status}"));
+
+            for (name, on) in toggles {
+                let color = if on { &theme.ansi.yellow } else { &theme.foreground.normal };
+                buffer.push_str(&format!(" {color}{name}"));
+            }
+
+            buffer.push(' ');
+        }
+
+        if editor.view == Self::name() {
+            editor.cursor = Some((self.position().x + 1 + shown as isize, self.position().y + 1));
+        }
+    }
+
+    fn print_row(&self, editor: &Editor, buffer: &mut String, row: usize) {
+        let theme = &editor.theme;
+        let width = self.size().x as usize;
+
+        let Some(kind) = self.rows.get(row) else {
+            buffer.push_str(&format!("{}{}", theme.backgrounds.secondary.disabled, " ".repeat(width)));
+            return;
+        };
+
+        let background = if row == self.focused {
+            &theme.backgrounds.secondary.active
+        } else {
+            &theme.backgrounds.secondary.normal
+        };
+
+        let (i, j) = match *kind {
+            Row::File(i) => {
+                let file = &self.results[i];
+                let path = file.path.strip_prefix(&self.root).unwrap_or(&file.path);
+                let text = format!(" {} ({})", path.display(), file.hits.len());
+                let text = text.chars().take(width).collect::<String>();
+
+                buffer.push_str(&format!(
+                    "{background}{}{text}{}",
+                    theme.ansi.blue,
+                    " ".repeat(width - text.chars().count())
+                ));
+
+                return;
+            },
+            Row::Hit(i, j) => (i, j)
+        };
+
+        let hit    = &self.results[i].hits[j];
+        let prefix = format!("   {}:{} ", hit.y + 1, hit.x + 1);
+        let prefix = prefix.chars().take(width).collect::<String>();
+        let room   = width - prefix.chars().count();
+
+        // NOTE: leading indentation is skipped, and long lines shift to keep the match in view
+        let line   = hit.line.trim_start();
+        let start  = hit.x - (hit.line.chars().count() - line.chars().count()) as isize;
+        let end    = start + hit.len;
+        let offset = if end > room as isize { (start - room as isize / 3).max(0) } else { 0 };
+
+        buffer.push_str(&format!("{background}{}{prefix}", theme.foreground.normal));
+
+        let mut last  = None;
+        let mut shown = 0;
+
+        for (k, ch) in line.chars().map(utils::printable).skip(offset as usize).take(room).enumerate() {
+            let k     = k as isize + offset;
+            let color = if start <= k && k < end { &theme.search_match.foreground } else { &theme.foreground.active };
+
+            if last != Some(color) {
+                buffer.push_str(color);
+                last = Some(color);
+            }
+
+            buffer.push(ch);
+            shown += 1;
+        }
+
+        buffer.push_str(&" ".repeat(room - shown));
+    }
+}
+
+impl View for Searching {
+    fn any          (&mut self) -> &mut dyn std::any::Any { self                      }
+    fn name         (         ) ->          String        { String::from("searching") }
+    fn view_data    (&    self) -> &        ViewData      { &    self.view_data       }
+    fn view_data_mut(&mut self) -> &mut     ViewData      { &mut self.view_data       }
+
+    fn mode(&self) -> &'static str {
+        "SEARCH"
+    }
+
+    fn print_line(&mut self, editor: &mut Editor, buffer: &mut String, loop_i: usize, _scrolled_i: usize) {
+        if loop_i == 0 {
+            self.poll();
+            self.print_prompt(editor, buffer);
+            return;
+        }
+
+        // NOTE: the prompt does not scroll, so the list is one line behind
+        let row = loop_i - 1 + self.scroll().y as usize;
+        self.print_row(editor, buffer, row);
+    }
+
+    fn handle_event(&mut self, editor: &mut Editor, event: Event) {
+        match event {
+            Event::Key(key) => match key {
+                Key::Esc        => { self.close       (editor); },
+                Key::Up         => { self.up          (      ); },
+                Key::Down       => { self.down        (      ); },
+                Key::Char('\n') => { self.open_focused(editor); },
+                Key::Char('\t') => (),
+                Key::Alt('c')   => { self.edit(editor, |query| query.case_sensitive ^= true); },
+                Key::Alt('w')   => { self.edit(editor, |query| query.whole_word     ^= true); },
+                Key::Alt('r')   => { self.edit(editor, |query| query.regex          ^= true); },
+                Key::Backspace  => { self.edit(editor, |query| { query.text.pop(); });     },
+                Key::Char(ch)   => { self.edit(editor, |query| query.text.push(ch));       },
+                _               => ()
+            },
+            Event::Mouse(MouseEvent::Press(mouse_button, _x, y)) => match mouse_button {
+                MouseButton::Left => {
+                    let row = y as isize - 1 + self.scroll().y;
+
+                    if row >= 0 && matches!(self.rows.get(row as usize), Some(Row::Hit(..))) {
+                        self.focused = row as usize;
+                        self.open_focused(editor);
+                    }
+                },
+                MouseButton::WheelUp   => { self.scroll_list(-1); },
+                MouseButton::WheelDown => { self.scroll_list( 1); },
+                _                      => ()
+            },
+            _ => ()
+        }
+    }
+}
+
+/// walks `root` depth first, sending every file with matches as it goes
+fn search(root: &Path, query: &Query, cancel: &AtomicBool, sender: &Sender<Found>, wake: &Sender<Signal>) {
+    let Ok(regex) = query.matcher() else { return; };
+
+    // NOTE: one for the whole walk, it grows to the longest line it comes across
+    let mut memo = regex.memo(0);
+
+    let mut stack     = vec![(root.to_path_buf(), Ignore::default().within(root))];
+    let mut hits      = 0;
+    let mut last_wake = Instant::now();
+
+    'walk: while let Some((dir, ignore)) = stack.pop() {
+        let Ok(entries) = dir.read_dir() else { continue; };
+
+        let mut paths = entries.flatten().map(|entry| entry.path()).collect::<Vec<PathBuf>>();
+        let mut dirs  = Vec::new();
+
+        paths.sort();
+
+        for path in paths {
+            if cancel.load(Ordering::Relaxed) {
+                return;
+            }
+
+            // NOTE: symlinked directories are not followed, they could loop
+            let Ok(metadata) = path.symlink_metadata() else { continue; };
+            let is_dir       = metadata.is_dir();
+
+            if path.file_name().is_some_and(|name| name == ".git") || ignore.is_ignored(&path, is_dir) {
+                continue;
+            }
+
+            if is_dir {
+                dirs.push(path);
+                continue;
+            }
+
+            if !path.is_file() {
+                continue;
+            }
+
+            let Some(found) = search_file(&path, query, &regex, &mut memo) else { continue; };
+            hits += found.len();
+
+            if sender.send(Found::File(FileHits { path, hits: found })).is_err() {
+                return;
+            }
+
+            // NOTE: batches redraws while results pour in
+            if last_wake.elapsed() > Duration::from_millis(50) {
+                let _ = wake.send(Signal::Redraw);
+                last_wake = Instant::now();
+            }
+
+            if hits >= MAX_HITS {
+                break 'walk;
+            }
+        }
+
+        for dir in dirs.into_iter().rev() {
+            let ignore = ignore.within(&dir);
+            stack.push((dir, ignore));
+        }
+    }
+
+    let _ = sender.send(Found::Done);
+    let _ = wake.send(Signal::Redraw);
+}
+
+/// `None` for binary, huge or unreadable files, and files without matches
+fn search_file(path: &Path, query: &Query, regex: &Regex, memo: &mut Memo) -> Option<Vec<Hit>> {
+    let mut file = File::open(path).ok()?;
+    let     size = file.metadata().ok()?.len();
+
+    if size > MAX_FILE_SIZE {
+        return None;
+    }
+
+    // NOTE: the start first, so a binary file is not read any further
+    let mut bytes = Vec::with_capacity(size as usize);
+    (&mut file).take(BINARY_SNIFF as u64).read_to_end(&mut bytes).ok()?;
+
+    if bytes.contains(&0) {
+        return None;
+    }
+
+    // NOTE: it could have grown since
+    file.take(MAX_FILE_SIZE - bytes.len() as u64).read_to_end(&mut bytes).ok()?;
+
+    let text     = String::from_utf8(bytes).ok()?;
+    let mut hits = Vec::new();
+
+    for (y, line) in text.lines().enumerate() {
+        let chars = line.chars().collect::<Vec<char>>();
+
+        // NOTE: a line too long to search with the pattern has nothing to show
+        for captures in query.find_in(regex, memo, &chars).unwrap_or_default() {
+            let (start, end) = captures[0].unwrap();
+
+            hits.push(Hit {
+                x:    start as isize,
+                y:    y as isize,
+                len:  (end - start) as isize,
+                line: String::from(line)
+            });
+        }
+    }
+
+    (!hits.is_empty()).then_some(hits)
+}
@@ -0,0 +1,99 @@
+// mochou-p/text-editor/src/view/status.rs
+
+use super::{View, ViewData};
+use super::browsing::Browsing;
+use super::editing::Editing;
+use super::hex::Hex;
+use crate::Editor;
+use crate::message::Level;
+
+
+/// the line under everything, with the mode, the open file and the message on the left,
+/// and where the cursor is and how the file is laid out on the right
+pub struct Status {
+    view_data: ViewData
+}
+
+impl Status {
+    pub fn new(editor: &mut Editor) -> Self {
+        Self {
+            view_data: ViewData::under::<Editing>(editor, 1)
+        }
+    }
+
+    /// the open file relative to the browsing root, with a marker if unsaved,
+    /// and what goes on the right
+    fn file_and_details(editor: &mut Editor) -> (String, Vec<String>) {
+        let hex = editor.view::<Hex, _>(|_, view| view.label().zip(view.open_file()));
+
+        let (file, details) = if let Some((label, file)) = hex {
+            (Some(file), vec![label])
+        } else {
+            editor.view::<Editing, _>(|editor, view| {
+                let cursors = view.cursor_count();
+
+                let details = [
+                    (cursors > 1).then(|| format!("{cursors} cursors")),
+                    view.cursor_label(),
+                    view.language_label(),
+                    view.format_label(),
+                    view.file_is_open().then(|| Editing::tab_label(editor.tab_width))
+                ];
+
+                (view.open_file(), details.into_iter().flatten().collect())
+            })
+        };
+
+        let Some((path, unsaved)) = file else { return (String::new(), details); };
+
+        let path = editor.view::<Browsing, _>(|_, view| view.relative(&path));
+
+        (format!("{}{}", path.display(), if unsaved { " [+]" } else { "" }), details)
+    }
+}
+
+impl View for Status {
+    fn any          (&mut self) -> &mut dyn std::any::Any { self                   }
+    fn name         (         ) ->          String        { String::from("status") }
+    fn view_data    (&    self) -> &        ViewData      { &    self.view_data    }
+    fn view_data_mut(&mut self) -> &mut     ViewData      { &mut self.view_data    }
+
+    fn print_line(&mut self, editor: &mut Editor, buffer: &mut String, _loop_i: usize, _scrolled_i: usize) {
+        let mode            = editor.views.get(&editor.view).map_or("", |view| view.mode());
+        let (file, details) = Self::file_and_details(editor);
+
+        let theme    = &editor.theme;
+        let mut room = self.size().x as usize;
+
+        // NOTE: takes what fits, in order of importance
+        let mut fit = |text: String| {
+            let text = text.chars().take(room).collect::<String>();
+            room    -= text.chars().count();
+            text
+        };
+
+        let details = fit(if details.is_empty() { String::new() } else { format!(" {} ", details.join("  ")) });
+        let mode    = fit(if mode.is_empty()    { String::new() } else { format!(" {mode} ")                  });
+        let file    = fit(if file.is_empty()    { String::new() } else { format!(" {file} ")                  });
+
+        let (color, message) = editor.message.as_ref().map_or((&theme.foreground.normal, String::new()), |message| {
+            let color = match message.level {
+                Level::Info    => &theme.diagnostics.info,
+                Level::Warning => &theme.diagnostics.warning,
+                Level::Error   => &theme.diagnostics.error
+            };
+
+            (color, fit(format!(" {} ", message.text)))
+        });
+
+        buffer.push_str(&format!(
+            "{}{}{mode}{}{}{file}{color}{message}{}{}{details}",
+            theme.backgrounds.primary.normal,
+            theme.foreground.active,
+            theme.backgrounds.primary.disabled,
+            theme.foreground.normal,
+            " ".repeat(room),
+            theme.foreground.active
+        ));
+    }
+}
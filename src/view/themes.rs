@@ -0,0 +1,157 @@
+// mochou-p/text-editor/src/view/themes.rs
+
+use termion::event::{Event, Key, MouseEvent, MouseButton};
+use super::{View, ViewData};
+use crate::{Editor, Ivec2};
+use crate::config::{Theme, THEMES};
+
+
+/// every built-in theme, each one shown on the whole screen while focused
+pub struct Themes {
+    view_data: ViewData,
+    // NOTE: an index into `THEMES`
+    focused:   usize,
+    // NOTE: gets the focus back once closed
+    previous:  String,
+    // NOTE: put back if nothing gets picked
+    original:  Option<Theme>
+}
+
+impl Themes {
+    pub fn new() -> Self {
+        Self {
+            view_data: ViewData::default(),
+            focused:   0,
+            previous:  String::new(),
+            original:  None
+        }
+    }
+
+    pub fn open(&mut self, editor: &mut Editor) {
+        if editor.view != Self::name() {
+            self.previous = editor.view.clone();
+            editor.view   = Self::name();
+        }
+
+        self.focused  = THEMES.iter().position(|(name, _)| *name == editor.theme.name).unwrap_or(0);
+        self.original = Some(editor.theme.clone());
+
+        let screen = Ivec2::from(termion::terminal_size().unwrap());
+        let width  = (screen.x - 8).clamp(1, 40);
+        let height = (THEMES.len() as isize + 1).clamp(1, screen.y);
+
+        self.view_data.size     = Ivec2 { x: width, y: height };
+        self.view_data.position = Ivec2 { x: (screen.x - width) / 2, y: 2.min(screen.y - height) };
+        self.view_data.scroll   = Ivec2::ZERO;
+    }
+
+    fn close(&mut self, editor: &mut Editor) {
+        self.view_data.size = Ivec2::ZERO;
+        editor.view         = self.previous.clone();
+    }
+
+    fn cancel(&mut self, editor: &mut Editor) {
+        if let Some(original) = self.original.take() {
+            editor.theme = original;
+        }
+
+        self.close(editor);
+    }
+
+    fn pick(&mut self, editor: &mut Editor) {
+        self.original = None;
+        self.close(editor);
+
+        editor.save_theme(THEMES[self.focused].0);
+    }
+
+    fn move_focus(&mut self, direction: isize) {
+        self.focused = (self.focused as isize + direction).clamp(0, THEMES.len() as isize - 1) as usize;
+
+        // NOTE: keeps the focused one in view
+        let rows   = (self.size().y - 1).max(1) as usize;
+        let scroll = self.scroll().y as usize;
+
+        if self.focused < scroll {
+            self.scroll_mut().y = self.focused as isize;
+        } else if self.focused >= scroll + rows {
+            self.scroll_mut().y = (self.focused + 1 - rows) as isize;
+        }
+    }
+
+    fn preview(&mut self, editor: &mut Editor, direction: isize) {
+        self.move_focus(direction);
+
+        let Some(theme) = Theme::named(THEMES[self.focused].0, editor.theme.depth) else { return; };
+
+        editor.theme = theme;
+    }
+}
+
+impl View for Themes {
+    fn any          (&mut self) -> &mut dyn std::any::Any { self                   }
+    fn name         (         ) ->          String        { String::from("themes") }
+    fn view_data    (&    self) -> &        ViewData      { &    self.view_data    }
+    fn view_data_mut(&mut self) -> &mut     ViewData      { &mut self.view_data    }
+
+    fn mode(&self) -> &'static str {
+        "THEME"
+    }
+
+    // NOTE: only while open, a closed one must not hold on to a stray click
+    fn is_modal(&self) -> bool {
+        self.size().x != 0
+    }
+
+    fn print_line(&mut self, editor: &mut Editor, buffer: &mut String, loop_i: usize, _scrolled_i: usize) {
+        let theme = &editor.theme;
+        let width = self.size().x as usize;
+
+        if loop_i == 0 {
+            let title = " theme, enter keeps it, esc goes back".chars().take(width).collect::<String>();
+
+            buffer.push_str(&format!(
+                "{}{}{title}{}",
+                theme.backgrounds.secondary.active,
+                theme.foreground.normal,
+                " ".repeat(width - title.chars().count())
+            ));
+
+            return;
+        }
+
+        let i = loop_i - 1 + self.scroll().y as usize;
+
+        let Some((name, _)) = THEMES.get(i) else {
+            buffer.push_str(&format!("{}{}", theme.backgrounds.secondary.normal, " ".repeat(width)));
+            return;
+        };
+
+        let background = if i == self.focused { &theme.backgrounds.secondary.active } else { &theme.backgrounds.secondary.normal };
+        let name       = format!(" {name}").chars().take(width).collect::<String>();
+
+        buffer.push_str(&format!(
+            "{background}{}{name}{}",
+            theme.foreground.active,
+            " ".repeat(width - name.chars().count())
+        ));
+    }
+
+    fn handle_event(&mut self, editor: &mut Editor, event: Event) {
+        match event {
+            Event::Key(key) => match key {
+                Key::Esc        => { self.cancel (editor    ); },
+                Key::Char('\n') => { self.pick   (editor    ); },
+                Key::Up         => { self.preview(editor, -1); },
+                Key::Down       => { self.preview(editor,  1); },
+                _               => ()
+            },
+            Event::Mouse(MouseEvent::Press(mouse_button, _x, _y)) => match mouse_button {
+                MouseButton::WheelUp   => { self.preview(editor, -1); },
+                MouseButton::WheelDown => { self.preview(editor,  1); },
+                _                      => ()
+            },
+            _ => ()
+        }
+    }
+}
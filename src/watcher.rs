@@ -0,0 +1,146 @@
+// mochou-p/text-editor/src/watcher.rs
+
+//! notices open files changing on disk, through inotify
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::{CString, OsStr};
+use std::os::unix::ffi::OsStrExt as _;
+use std::os::unix::fs::MetadataExt as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::Sender;
+use std::time::SystemTime;
+use crate::Signal;
+
+
+const MASK: u32 = libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_MOVED_FROM | libc::IN_CREATE | libc::IN_DELETE;
+
+// NOTE: `struct inotify_event` before its name, `wd` first and `len` last
+const HEADER: usize = std::mem::size_of::<libc::inotify_event>();
+
+/// what a file looked like on disk, to tell our own saves apart from changes
+#[derive(Clone, Copy, PartialEq)]
+pub struct Stamp {
+    modified: SystemTime,
+    len:      u64,
+    inode:    u64
+}
+
+impl Stamp {
+    /// `None` when there is no such file
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+
+        Some(Self {
+            modified: metadata.modified().ok()?,
+            len:      metadata.len(),
+            inode:    metadata.ino()
+        })
+    }
+}
+
+// NOTE: watches the directories, files replaced by a rename would lose a watch of their own
+pub struct Watcher {
+    fd:    i32,
+    files: HashSet<PathBuf>,
+    dirs:  Arc<Mutex<HashMap<i32, PathBuf>>>
+}
+
+impl Watcher {
+    /// sends `Signal::Changed` for every event next to a watched file,
+    /// `None` if inotify is not available
+    pub fn new(sender: Sender<Signal>) -> Option<Self> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+
+        if fd < 0 {
+            return None;
+        }
+
+        let dirs   = Arc::new(Mutex::new(HashMap::new()));
+        let shared = Arc::clone(&dirs);
+
+        std::thread::spawn(move || read_events(fd, &shared, &sender));
+
+        Some(Self { fd, files: HashSet::new(), dirs })
+    }
+
+    pub fn watch(&mut self, path: &Path) {
+        if !self.files.insert(path.to_path_buf()) {
+            return;
+        }
+
+        let Some(dir) = path.parent() else { return; };
+
+        // NOTE: the event paths get joined onto `dir`, so they come out the way `path` went in
+        let watched = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+
+        let Ok(cstring) = CString::new(watched.as_os_str().as_bytes()) else { return; };
+
+        let wd = unsafe { libc::inotify_add_watch(self.fd, cstring.as_ptr(), MASK) };
+
+        if wd >= 0 {
+            self.dirs.lock().unwrap().insert(wd, dir.to_path_buf());
+        }
+    }
+
+    /// for a file nothing has open anymore, the directory goes once no other watched file is in it
+    pub fn unwatch(&mut self, path: &Path) {
+        if !self.files.remove(path) {
+            return;
+        }
+
+        let Some(dir) = path.parent() else { return; };
+
+        if self.files.iter().any(|file| file.parent() == Some(dir)) {
+            return;
+        }
+
+        let mut dirs = self.dirs.lock().unwrap();
+
+        // NOTE: inotify gives the same `wd` for the same directory, so there is one at most
+        let Some(wd) = dirs.iter().find(|(_, watched)| *watched == dir).map(|(wd, _)| *wd) else { return; };
+
+        dirs.remove(&wd);
+        unsafe { libc::inotify_rm_watch(self.fd, wd); }
+    }
+}
+
+fn read_events(fd: i32, dirs: &Mutex<HashMap<i32, PathBuf>>, sender: &Sender<Signal>) {
+    let mut buffer = [0_u8; 4096];
+
+    loop {
+        let read = unsafe { libc::read(fd, buffer.as_mut_ptr().cast(), buffer.len()) };
+
+        if read < 0 && std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+            continue;
+        }
+
+        if read <= 0 {
+            return;
+        }
+
+        let     read   = read as usize;
+        let mut offset = 0;
+
+        while offset + HEADER <= read {
+            let wd   = i32::from_ne_bytes(buffer[offset     ..offset +  4].try_into().unwrap());
+            let len  = u32::from_ne_bytes(buffer[offset + 12..offset + 16].try_into().unwrap()) as usize;
+            let name = &buffer[offset + HEADER..(offset + HEADER + len).min(read)];
+
+            offset += HEADER + len;
+
+            // NOTE: the name is padded with nuls
+            let name = &name[..name.iter().position(|byte| *byte == 0).unwrap_or(name.len())];
+
+            if name.is_empty() {
+                continue;
+            }
+
+            let Some(dir) = dirs.lock().unwrap().get(&wd).cloned() else { continue; };
+
+            if sender.send(Signal::Changed(dir.join(OsStr::from_bytes(name)))).is_err() {
+                return;
+            }
+        }
+    }
+}